@@ -14,8 +14,14 @@ fn main() {
     println!("Found {} NEF files", paths.len());
     
     let mut runner = ExiftoolRunner::new().unwrap();
-    let exif_data = runner.extract(&paths).unwrap();
+    let (exif_data, errors): (Vec<_>, Vec<_>) = runner.extract(&paths).unwrap()
+        .into_iter()
+        .partition(|r| r.is_ok());
+    let exif_data: Vec<_> = exif_data.into_iter().map(Result::unwrap).collect();
     println!("Extracted EXIF for {} files", exif_data.len());
+    for err in errors.into_iter().map(Result::unwrap_err) {
+        println!("  skipped {}", err);
+    }
     
     let result = BurstDetector::detect(exif_data).unwrap();
     println!("\nCameras: {}", result.cameras.len());
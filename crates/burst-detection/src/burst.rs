@@ -1,23 +1,98 @@
 //! Burst detection: groups continuous-shooting sequences from EXIF metadata.
 //!
-//! Two-strategy hierarchy:
+//! Three-strategy hierarchy, chosen per camera body:
 //! 1. **Native BurstGroupID** (primary) — Nikon Z cameras tag each burst with a
 //!    unique ID in maker notes. This is ground truth; no heuristics needed.
 //! 2. **Drive mode inference** (fallback) — for cameras without native burst IDs,
 //!    groups consecutive images shot in continuous drive mode (CL/CH) with
 //!    sub-threshold time gaps.
+//! 3. **Similarity** — for bodies whose drive mode we mostly can't read (phones,
+//!    some mirrorless bodies, stripped JPEGs), where strategy 2 would call every
+//!    frame a single. Groups frames shot within `similarity_max_gap_ms` of each
+//!    other that also look alike (see `similarity`). Needs perceptual hashes,
+//!    so it only runs via `detect_with_hashes`; unhashed frames stay singles.
+//!
+//! Each `BurstGroup` records the strategy that produced it in `source`, plus a
+//! `confidence` in [0, 1] so the UI can tell ground truth from a guess: native
+//! IDs are 1.0; inferred bursts start from their strategy's base confidence
+//! (drive mode 0.8, similarity 0.6) and lose up to half of it for irregular
+//! frame spacing, which is what a mis-grouped sequence usually looks like.
+//! `CameraInfo` reports each body's strategy and mean burst confidence.
+//! Bursts the user has corrected (see `editing`) are `Manual`.
 //!
 //! Images are partitioned by camera serial number first — clock sync between
 //! camera bodies is irrelevant for burst detection. A 2-body sports shoot
 //! processes each body independently.
 //!
+//! Both strategies are followed by a time-gap splitter (see `BurstDetectorConfig`).
+//! A shooter who leaves the body in CH all day never leaves continuous mode, so
+//! drive mode alone would chain every frame of the day into one "burst". Native
+//! groups get the same treatment as a sanity check against firmware that reuses IDs.
+//!
 //! FPS is calculated as (frame_count - 1) / duration, since N frames produce
 //! N-1 intervals.
+//!
+//! Burst IDs are content-derived (see `stable_group_id`) rather than sequential,
+//! so re-importing the same folder yields the same IDs and anything keyed on
+//! them in the session database stays attached.
+//!
+//! Because bodies are independent, an incremental rescan only re-detects the
+//! bodies whose files changed (`redetect_cameras`) and keeps the rest of a
+//! stored result, rebuilt with `BurstResult::from_parts`.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
-use crate::exif::ExifData;
+use anyhow::{anyhow, Result};
+use crate::exif::{DriveMode, ExifData};
+use crate::similarity::{cluster_similar, PerceptualHash, SimilarityConfig};
+
+/// Which detection strategy produced a burst (see module docs).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BurstSource {
+    /// Camera-native BurstGroupID
+    NativeId,
+    /// Continuous drive mode plus time gaps
+    #[default]
+    DriveMode,
+    /// Capture gaps plus thumbnail similarity
+    Similarity,
+    /// Split, merged, or re-membered by the user (see `editing`); kept as-is
+    /// when detection re-runs
+    Manual,
+}
+
+impl BurstSource {
+    /// Confidence in a burst from this strategy before looking at its timing.
+    pub fn base_confidence(&self) -> f64 {
+        match self {
+            BurstSource::NativeId => 1.0,
+            BurstSource::DriveMode => 0.8,
+            BurstSource::Similarity => 0.6,
+            BurstSource::Manual => 1.0,
+        }
+    }
+}
+
+/// Parses the `Debug` name the session database stores.
+impl FromStr for BurstSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "NativeId" => Ok(BurstSource::NativeId),
+            "DriveMode" => Ok(BurstSource::DriveMode),
+            "Similarity" => Ok(BurstSource::Similarity),
+            "Manual" => Ok(BurstSource::Manual),
+            _ => Err(anyhow!("Unknown burst source: {}", s)),
+        }
+    }
+}
+
+fn default_confidence() -> f64 {
+    1.0
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BurstGroup {
@@ -35,13 +110,19 @@ pub struct BurstGroup {
     pub avg_gap_ms: f64,
     /// Estimated frames per second
     pub estimated_fps: f64,
+    /// Strategy that produced this burst
+    #[serde(default)]
+    pub source: BurstSource,
+    /// How sure detection is that these frames form one burst, in [0, 1]
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
 }
 
 impl BurstGroup {
     /// Create a new burst group
     pub fn new(id: String, camera_serial: String, mut images: Vec<ExifData>) -> Self {
-        // Sort images by capture time
-        images.sort_by_key(|img| img.capture_time);
+        // Sort images by capture time (path breaks ties so frame order is deterministic)
+        sort_by_capture_time(&mut images);
         
         let frame_count = images.len();
         let (duration_ms, avg_gap_ms, estimated_fps) = if frame_count > 1 {
@@ -81,7 +162,32 @@ impl BurstGroup {
             duration_ms,
             avg_gap_ms,
             estimated_fps,
+            source: BurstSource::default(),
+            confidence: default_confidence(),
+        }
+    }
+
+    /// Set the source, and the confidence that follows from it and the frame timing.
+    pub fn with_source(mut self, source: BurstSource) -> Self {
+        self.source = source;
+        self.confidence = match source {
+            BurstSource::NativeId | BurstSource::Manual => source.base_confidence(),
+            BurstSource::DriveMode | BurstSource::Similarity => source.base_confidence() * self.gap_regularity(),
+        };
+        self
+    }
+
+    /// 1.0 for evenly spaced frames, down to 0.5 as the gaps' coefficient of
+    /// variation grows.
+    fn gap_regularity(&self) -> f64 {
+        let gaps: Vec<f64> = self.images.windows(2)
+            .map(|pair| pair[1].capture_time.signed_duration_since(pair[0].capture_time).num_milliseconds() as f64)
+            .collect();
+        if gaps.len() < 2 || self.avg_gap_ms <= 0.0 {
+            return 1.0;
         }
+        let variance = gaps.iter().map(|g| (g - self.avg_gap_ms).powi(2)).sum::<f64>() / gaps.len() as f64;
+        (1.0 - variance.sqrt() / self.avg_gap_ms).clamp(0.5, 1.0)
     }
 }
 
@@ -97,6 +203,16 @@ pub struct CameraInfo {
     pub image_count: usize,
     /// Number of burst groups from this camera
     pub burst_count: usize,
+    /// Clock correction applied to this body's capture times (milliseconds)
+    #[serde(default)]
+    pub clock_offset_ms: i64,
+    /// Detection strategy used for this body
+    #[serde(default)]
+    pub burst_source: BurstSource,
+    /// Mean confidence of this body's bursts (the strategy's base confidence
+    /// if it has none)
+    #[serde(default = "default_confidence")]
+    pub burst_confidence: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,17 +240,128 @@ impl BurstResult {
     pub fn camera_info(&self, serial: &str) -> Option<&CameraInfo> {
         self.cameras.iter().find(|c| c.serial == serial)
     }
+
+    /// Rebuild a result from stored bursts and singles, e.g. a session loaded
+    /// from disk. Each body's strategy and confidence come from its detected
+    /// (non-Manual) bursts, defaulting to drive mode if it has none.
+    pub fn from_parts(bursts: Vec<BurstGroup>, singles: Vec<ExifData>) -> Self {
+        let mut frames: BTreeMap<&str, Vec<&ExifData>> = BTreeMap::new();
+        for img in bursts.iter().flat_map(|b| b.images.iter()).chain(singles.iter()) {
+            frames.entry(img.serial_number.as_str()).or_default().push(img);
+        }
+        let cameras = frames.into_iter()
+            .map(|(serial, images)| {
+                let body_bursts: Vec<&BurstGroup> = bursts.iter().filter(|b| b.camera_serial == serial).collect();
+                let detected: Vec<&BurstGroup> = body_bursts.iter()
+                    .copied()
+                    .filter(|b| b.source != BurstSource::Manual)
+                    .collect();
+                let first = images.iter().min_by_key(|img| (img.capture_time, &img.file_path)).unwrap();
+                camera_summary(serial, first, images.len(), body_bursts.len(), BurstSource::default(), &detected)
+            })
+            .collect();
+
+        Self { bursts, singles, cameras }
+    }
+}
+
+/// Summary of one body's detection from its detected (non-Manual) bursts:
+/// the source most of them share (ties go to the earlier strategy) and their
+/// mean confidence. Without any, `fallback` and its base confidence.
+fn camera_summary(
+    serial: &str,
+    first: &ExifData,
+    image_count: usize,
+    burst_count: usize,
+    fallback: BurstSource,
+    detected: &[&BurstGroup],
+) -> CameraInfo {
+    let mut source = fallback;
+    let mut most = 0;
+    for candidate in [BurstSource::NativeId, BurstSource::DriveMode, BurstSource::Similarity] {
+        let count = detected.iter().filter(|b| b.source == candidate).count();
+        if count > most {
+            source = candidate;
+            most = count;
+        }
+    }
+
+    CameraInfo {
+        serial: serial.to_string(),
+        make: first.make.clone().unwrap_or_else(|| "Unknown".to_string()),
+        model: first.model.clone().unwrap_or_else(|| "Unknown".to_string()),
+        image_count,
+        burst_count,
+        clock_offset_ms: first.clock_offset_ms,
+        burst_source: source,
+        burst_confidence: if detected.is_empty() {
+            source.base_confidence()
+        } else {
+            detected.iter().map(|b| b.confidence).sum::<f64>() / detected.len() as f64
+        },
+    }
+}
+
+/// Thresholds for splitting a candidate sequence into separate bursts by time gap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurstDetectorConfig {
+    /// Hard ceiling on the gap between consecutive frames (milliseconds).
+    /// Any larger gap always ends the current burst.
+    pub max_gap_ms: i64,
+    /// A gap larger than this multiple of the sequence's median gap ends the burst.
+    /// Catches pauses that are short in absolute terms but long for the frame rate
+    /// (e.g., a 600ms pause inside a 20fps burst).
+    pub gap_factor: f64,
+    /// Minimum number of frames for a sequence to count as a burst.
+    /// Shorter sequences are returned as singles.
+    pub min_frames: usize,
+    /// Similarity strategy: max gap between consecutive frames (milliseconds).
+    /// Sub-second, since nothing else says the shutter was held down.
+    pub similarity_max_gap_ms: i64,
+    /// Similarity strategy: max perceptual hash distance between consecutive frames.
+    pub similarity_max_distance: u32,
+    /// Fraction of a body's frames with `DriveMode::Unknown` above which the
+    /// similarity strategy replaces drive mode inference.
+    pub unknown_drive_mode_ratio: f64,
+}
+
+impl Default for BurstDetectorConfig {
+    fn default() -> Self {
+        Self {
+            max_gap_ms: 2000,
+            gap_factor: 4.0,
+            min_frames: 2,
+            similarity_max_gap_ms: 1000,
+            similarity_max_distance: 10,
+            unknown_drive_mode_ratio: 0.5,
+        }
+    }
 }
 
 pub struct BurstDetector;
 
 impl BurstDetector {
-    /// Detect burst groups from a collection of images.
+    /// Detect burst groups from a collection of images using the default config.
     ///
     /// Strategy hierarchy:
     /// 1. Camera-native BurstGroupID (e.g., Nikon) — ground truth
     /// 2. Drive mode inference — consecutive continuous-mode frames
     pub fn detect(images: Vec<ExifData>) -> Result<BurstResult> {
+        Self::detect_with_config(images, &BurstDetectorConfig::default())
+    }
+
+    /// Detect burst groups with explicit time-gap thresholds.
+    pub fn detect_with_config(images: Vec<ExifData>, config: &BurstDetectorConfig) -> Result<BurstResult> {
+        Self::detect_with_hashes(images, config, &HashMap::new())
+    }
+
+    /// Detect burst groups, using perceptual hashes for bodies that get the
+    /// similarity strategy. Only frames from `similarity_candidates` need hashes.
+    pub fn detect_with_hashes(
+        images: Vec<ExifData>,
+        config: &BurstDetectorConfig,
+        hashes: &HashMap<PathBuf, PerceptualHash>,
+    ) -> Result<BurstResult> {
         if images.is_empty() {
             return Ok(BurstResult {
                 bursts: Vec::new(),
@@ -143,11 +370,9 @@ impl BurstDetector {
             });
         }
 
-        // Check if any images have camera-native burst group IDs
-        let has_native_burst_ids = images.iter().any(|img| img.burst_group_id.is_some());
-
-        // Step 1: Partition images by camera serial number
-        let mut camera_partitions: HashMap<String, Vec<ExifData>> = HashMap::new();
+        // Step 1: Partition images by camera serial number.
+        // BTreeMap so cameras are always processed (and reported) in the same order.
+        let mut camera_partitions: BTreeMap<String, Vec<ExifData>> = BTreeMap::new();
         for image in images {
             camera_partitions.entry(image.serial_number.clone())
                 .or_default()
@@ -157,41 +382,30 @@ impl BurstDetector {
         let mut all_bursts = Vec::new();
         let mut all_singles = Vec::new();
         let mut cameras = Vec::new();
-        let mut burst_id_counter = 0;
-        let mut single_id_counter = 0;
 
         // Step 2: Process each camera partition independently
         for (serial, mut camera_images) in camera_partitions {
             // Sort by capture time within this camera
-            camera_images.sort_by_key(|img| img.capture_time);
-            
-            // Extract camera info from the first image
-            let camera_info = {
-                let first_img = &camera_images[0];
-                CameraInfo {
-                    serial: serial.clone(),
-                    make: first_img.make.clone().unwrap_or_else(|| "Unknown".to_string()),
-                    model: first_img.model.clone().unwrap_or_else(|| "Unknown".to_string()),
-                    image_count: camera_images.len(),
-                    burst_count: 0,
-                }
-            };
+            sort_by_capture_time(&mut camera_images);
+            let first_img = camera_images[0].clone();
+            let image_count = camera_images.len();
 
             // Step 3: Detect bursts — choose strategy based on available data
             let camera_has_native_ids = camera_images.iter().any(|img| img.burst_group_id.is_some());
             
-            let (camera_bursts, camera_singles) = if camera_has_native_ids {
-                Self::detect_by_native_id(camera_images, &serial, &mut burst_id_counter)
+            let (source, (camera_bursts, camera_singles)) = if camera_has_native_ids {
+                (BurstSource::NativeId, Self::detect_by_native_id(camera_images, &serial, config))
+            } else if Self::drive_mode_mostly_unknown(&camera_images, config) {
+                (BurstSource::Similarity, Self::detect_by_similarity(camera_images, &serial, config, hashes))
             } else {
-                Self::detect_by_drive_mode(camera_images, &serial, &mut burst_id_counter, &mut single_id_counter)
+                (BurstSource::DriveMode, Self::detect_by_drive_mode(camera_images, &serial, config))
             };
 
-            let mut camera_info = camera_info;
-            camera_info.burst_count = camera_bursts.len();
-            
+            let burst_refs: Vec<&BurstGroup> = camera_bursts.iter().collect();
+            cameras.push(camera_summary(&serial, &first_img, image_count, burst_refs.len(), source, &burst_refs));
+
             all_bursts.extend(camera_bursts);
             all_singles.extend(camera_singles);
-            cameras.push(camera_info);
         }
 
         Ok(BurstResult {
@@ -201,17 +415,69 @@ impl BurstDetector {
         })
     }
 
+    /// Re-run detection for the bodies in `serials` only, keeping `previous`'s
+    /// bursts, singles and camera info for every other body. `images` must
+    /// hold every frame of those bodies; frames from other bodies are ignored.
+    pub fn redetect_cameras(
+        previous: BurstResult,
+        images: Vec<ExifData>,
+        serials: &BTreeSet<String>,
+        config: &BurstDetectorConfig,
+        hashes: &HashMap<PathBuf, PerceptualHash>,
+    ) -> Result<BurstResult> {
+        let images: Vec<ExifData> = images.into_iter().filter(|img| serials.contains(&img.serial_number)).collect();
+        let fresh = Self::detect_with_hashes(images, config, hashes)?;
+
+        let mut bursts: Vec<BurstGroup> = previous.bursts.into_iter()
+            .filter(|b| !serials.contains(&b.camera_serial))
+            .collect();
+        let mut singles: Vec<ExifData> = previous.singles.into_iter()
+            .filter(|img| !serials.contains(&img.serial_number))
+            .collect();
+        let mut cameras: Vec<CameraInfo> = previous.cameras.into_iter()
+            .filter(|c| !serials.contains(&c.serial))
+            .collect();
+        bursts.extend(fresh.bursts);
+        singles.extend(fresh.singles);
+        cameras.extend(fresh.cameras);
+        cameras.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+        Ok(BurstResult { bursts, singles, cameras })
+    }
+
+    /// Frames from bodies that will use the similarity strategy — the ones
+    /// `detect_with_hashes` needs perceptual hashes for.
+    pub fn similarity_candidates<'a>(images: &'a [ExifData], config: &BurstDetectorConfig) -> Vec<&'a ExifData> {
+        let mut partitions: BTreeMap<&str, Vec<&ExifData>> = BTreeMap::new();
+        for image in images {
+            partitions.entry(image.serial_number.as_str()).or_default().push(image);
+        }
+
+        partitions.into_values()
+            .filter(|camera_images| {
+                !camera_images.iter().any(|img| img.burst_group_id.is_some())
+                    && Self::drive_mode_mostly_unknown(camera_images, config)
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn drive_mode_mostly_unknown<T: std::borrow::Borrow<ExifData>>(images: &[T], config: &BurstDetectorConfig) -> bool {
+        let unknown = images.iter().filter(|img| img.borrow().drive_mode == DriveMode::Unknown).count();
+        !images.is_empty() && unknown as f64 / images.len() as f64 > config.unknown_drive_mode_ratio
+    }
+
     /// Strategy 1: Use camera-native BurstGroupID (Nikon, etc.)
     fn detect_by_native_id(
         images: Vec<ExifData>,
         camera_serial: &str,
-        burst_id_counter: &mut usize,
+        config: &BurstDetectorConfig,
     ) -> (Vec<BurstGroup>, Vec<ExifData>) {
         let mut bursts = Vec::new();
         let mut singles = Vec::new();
 
         // Group by native burst group ID
-        let mut groups: HashMap<u64, Vec<ExifData>> = HashMap::new();
+        let mut groups: BTreeMap<u64, Vec<ExifData>> = BTreeMap::new();
         let mut no_id: Vec<ExifData> = Vec::new();
 
         for image in images {
@@ -222,94 +488,200 @@ impl BurstDetector {
             }
         }
 
-        // Convert groups to BurstGroups (only if >= 2 frames)
-        for (_native_id, mut group_images) in groups {
-            group_images.sort_by_key(|img| img.capture_time);
-
-            if group_images.len() >= 2 {
-                let burst = BurstGroup::new(
-                    format!("burst_{}", burst_id_counter),
-                    camera_serial.to_string(),
-                    group_images,
-                );
-                bursts.push(burst);
-                *burst_id_counter += 1;
-            } else {
-                // Single-frame "burst" (e.g., quick tap in continuous mode)
-                singles.extend(group_images);
+        // Convert groups to BurstGroups, splitting any group with an implausible gap
+        for (native_id, mut group_images) in groups {
+            sort_by_capture_time(&mut group_images);
+
+            for segment in Self::split_by_time_gaps(group_images, config) {
+                // Short segments (e.g., quick tap in continuous mode) are singles
+                Self::emit_segment(segment, camera_serial, Some(native_id), BurstSource::NativeId, config, &mut bursts, &mut singles);
             }
         }
 
         singles.extend(no_id);
+        sort_by_capture_time(&mut singles);
 
         // Sort bursts by first image timestamp for consistent ordering
-        bursts.sort_by_key(|b| b.images.first().map(|img| img.capture_time));
+        bursts.sort_by(|a, b| {
+            let a_first = a.images.first().map(|img| img.capture_time);
+            let b_first = b.images.first().map(|img| img.capture_time);
+            a_first.cmp(&b_first).then_with(|| a.id.cmp(&b.id))
+        });
 
         (bursts, singles)
     }
 
     /// Strategy 2: Infer bursts from consecutive continuous drive mode frames
     fn detect_by_drive_mode(
-        images: Vec<ExifData>, 
+        images: Vec<ExifData>,
         camera_serial: &str,
-        burst_id_counter: &mut usize,
-        _single_id_counter: &mut usize,
+        config: &BurstDetectorConfig,
     ) -> (Vec<BurstGroup>, Vec<ExifData>) {
         let mut bursts = Vec::new();
         let mut singles = Vec::new();
-        let mut current_burst: Vec<ExifData> = Vec::new();
+        let mut current_run: Vec<ExifData> = Vec::new();
 
+        // Collect runs of consecutive continuous-mode frames, then split each run
+        // on time gaps — drive mode alone can't tell two CH bursts apart.
         for image in images {
-            let should_extend_burst = if let Some(last_image) = current_burst.last() {
-                image.drive_mode.is_continuous() && last_image.drive_mode.is_continuous()
-            } else {
-                image.drive_mode.is_continuous()
-            };
+            if image.drive_mode.is_continuous() {
+                current_run.push(image);
+                continue;
+            }
 
-            if should_extend_burst {
-                current_burst.push(image);
-            } else {
-                if current_burst.len() >= 2 {
-                    let burst = BurstGroup::new(
-                        format!("burst_{}", burst_id_counter),
-                        camera_serial.to_string(),
-                        current_burst.clone(),
-                    );
-                    bursts.push(burst);
-                    *burst_id_counter += 1;
-                } else {
-                    for img in current_burst.drain(..) {
-                        singles.push(img);
-                    }
-                }
+            for segment in Self::split_by_time_gaps(std::mem::take(&mut current_run), config) {
+                Self::emit_segment(segment, camera_serial, None, BurstSource::DriveMode, config, &mut bursts, &mut singles);
+            }
+            singles.push(image);
+        }
+
+        // Handle final run
+        for segment in Self::split_by_time_gaps(current_run, config) {
+            Self::emit_segment(segment, camera_serial, None, BurstSource::DriveMode, config, &mut bursts, &mut singles);
+        }
+
+        (bursts, singles)
+    }
+
+    /// Strategy 3: Runs of frames that are close in time and look alike.
+    ///
+    /// `cluster_similar` already caps the gap between consecutive frames, so
+    /// the median-relative split isn't applied: a phone's "burst" is often a
+    /// few uneven taps, not a steady frame rate.
+    fn detect_by_similarity(
+        images: Vec<ExifData>,
+        camera_serial: &str,
+        config: &BurstDetectorConfig,
+        hashes: &HashMap<PathBuf, PerceptualHash>,
+    ) -> (Vec<BurstGroup>, Vec<ExifData>) {
+        let mut bursts = Vec::new();
+        let mut singles = Vec::new();
+        let similarity = SimilarityConfig {
+            max_distance: config.similarity_max_distance,
+            max_gap_ms: config.similarity_max_gap_ms,
+            min_frames: config.min_frames,
+        };
+
+        for cluster in cluster_similar(images, hashes, &similarity) {
+            Self::emit_segment(cluster, camera_serial, None, BurstSource::Similarity, config, &mut bursts, &mut singles);
+        }
+
+        (bursts, singles)
+    }
+
+    /// Split a time-sorted sequence wherever the gap between consecutive frames
+    /// exceeds `max_gap_ms` or `gap_factor` × the median gap of the sequence.
+    fn split_by_time_gaps(images: Vec<ExifData>, config: &BurstDetectorConfig) -> Vec<Vec<ExifData>> {
+        if images.len() < 2 {
+            return if images.is_empty() { Vec::new() } else { vec![images] };
+        }
+
+        let gaps: Vec<i64> = images.windows(2)
+            .map(|pair| pair[1].capture_time.signed_duration_since(pair[0].capture_time).num_milliseconds())
+            .collect();
 
-                current_burst.clear();
+        let mut sorted_gaps = gaps.clone();
+        sorted_gaps.sort_unstable();
+        let median_gap = sorted_gaps[sorted_gaps.len() / 2];
+
+        // A zero median (sub-millisecond timestamps or missing SubSec) would make
+        // every non-zero gap "relatively" huge, so only the hard ceiling applies.
+        let relative_limit = if median_gap > 0 {
+            median_gap as f64 * config.gap_factor
+        } else {
+            f64::INFINITY
+        };
 
-                if image.drive_mode.is_continuous() {
-                    current_burst.push(image);
-                } else {
-                    singles.push(image);
+        let mut segments = Vec::new();
+        let mut current = Vec::new();
+        for (i, image) in images.into_iter().enumerate() {
+            if i > 0 {
+                let gap = gaps[i - 1];
+                if gap > config.max_gap_ms || gap as f64 > relative_limit {
+                    segments.push(std::mem::take(&mut current));
                 }
             }
+            current.push(image);
         }
+        segments.push(current);
+
+        segments
+    }
 
-        // Handle final burst
-        if current_burst.len() >= 2 {
-            let burst = BurstGroup::new(
-                format!("burst_{}", burst_id_counter),
+    /// Turn a segment into a BurstGroup if it meets `min_frames`, otherwise into singles.
+    fn emit_segment(
+        segment: Vec<ExifData>,
+        camera_serial: &str,
+        native_id: Option<u64>,
+        source: BurstSource,
+        config: &BurstDetectorConfig,
+        bursts: &mut Vec<BurstGroup>,
+        singles: &mut Vec<ExifData>,
+    ) {
+        if segment.len() >= config.min_frames.max(2) {
+            bursts.push(BurstGroup::new(
+                stable_group_id("burst", camera_serial, &segment[0], native_id),
                 camera_serial.to_string(),
-                current_burst,
-            );
-            bursts.push(burst);
-            *burst_id_counter += 1;
+                segment,
+            ).with_source(source));
         } else {
-            for img in current_burst {
-                singles.push(img);
-            }
+            singles.extend(segment);
         }
+    }
+}
 
-        (bursts, singles)
+/// Sort frames by capture time, breaking ties by file path.
+///
+/// Plain `sort_by_key(capture_time)` is stable, so frames sharing a timestamp
+/// (no SubSec, or two frames in the same millisecond) would keep whatever order
+/// the filesystem scan produced — which is not guaranteed across runs.
+pub(crate) fn sort_by_capture_time(images: &mut [ExifData]) {
+    images.sort_by(|a, b| {
+        a.capture_time.cmp(&b.capture_time).then_with(|| a.file_path.cmp(&b.file_path))
+    });
+}
+
+/// Derive a group ID (`{prefix}_{hash}`) from its content so it is identical
+/// across re-imports.
+///
+/// Hashes the camera serial, the first frame's capture time as the camera
+/// recorded it (before clock correction, so changing a body's offset doesn't
+/// rename its bursts), and either the camera-native BurstGroupID or the first
+/// frame's file name. Only the file name
+/// is used (not the full path) so the same card mounted at a different location
+/// — or on another machine — still produces the same ID.
+///
+/// FNV-1a rather than `DefaultHasher`: std makes no stability guarantee for
+/// SipHash output across Rust releases.
+pub(crate) fn stable_group_id(prefix: &str, camera_serial: &str, first: &ExifData, native_id: Option<u64>) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325; // FNV offset basis
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3); // FNV prime
+        }
+        // Field separator so ("ab", "c") and ("a", "bc") hash differently
+        hash ^= 0xff;
+        hash = hash.wrapping_mul(0x100000001b3);
+    };
+
+    feed(camera_serial.as_bytes());
+    feed(&first.original_capture_time().timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+    match native_id {
+        Some(id) => {
+            feed(b"native");
+            feed(&id.to_le_bytes());
+        }
+        None => {
+            let file_name = first.file_path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            feed(b"path");
+            feed(file_name.as_bytes());
+        }
     }
+
+    format!("{}_{:016x}", prefix, hash)
 }
 
 #[cfg(test)]
@@ -317,7 +689,7 @@ mod tests {
     use super::*;
     use crate::exif::{ExifData, DriveMode};
     use chrono::{Utc, TimeZone};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     fn create_test_image(
         path: &str, 
@@ -345,6 +717,15 @@ mod tests {
         img
     }
 
+    fn create_test_image_ms(path: &str, serial: &str, drive_mode: DriveMode, timestamp_ms: i64) -> ExifData {
+        ExifData::new(
+            PathBuf::from(path),
+            serial.to_string(),
+            drive_mode,
+            Utc.timestamp_millis_opt(timestamp_ms).unwrap(),
+        )
+    }
+
     #[test]
     fn test_all_single_shot_images() {
         let images = vec![
@@ -540,4 +921,354 @@ mod tests {
         assert_eq!(camera_info.image_count, 3);
         assert_eq!(camera_info.burst_count, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_continuous_bursts_minutes_apart_split() {
+        // Shooter stays in CH between two bursts — drive mode never changes
+        let mut images = Vec::new();
+        for i in 0..5 {
+            images.push(create_test_image_ms(&format!("a{}.jpg", i), "cam1", DriveMode::ContinuousHigh, 1_000_000 + i * 100));
+        }
+        for i in 0..4 {
+            images.push(create_test_image_ms(&format!("b{}.jpg", i), "cam1", DriveMode::ContinuousHigh, 1_180_000 + i * 100));
+        }
+
+        let result = BurstDetector::detect(images).unwrap();
+
+        assert_eq!(result.bursts.len(), 2);
+        assert_eq!(result.bursts[0].frame_count, 5);
+        assert_eq!(result.bursts[1].frame_count, 4);
+        assert_eq!(result.singles.len(), 0);
+    }
+
+    #[test]
+    fn test_gap_relative_to_median_splits() {
+        // 10fps burst, 800ms pause (under max_gap_ms, but 8× the median gap), 10fps burst
+        let times = [0, 100, 200, 300, 1100, 1200, 1300];
+        let images: Vec<_> = times.iter().enumerate()
+            .map(|(i, t)| create_test_image_ms(&format!("img{}.jpg", i), "cam1", DriveMode::ContinuousHigh, 1_000_000 + t))
+            .collect();
+
+        let result = BurstDetector::detect(images).unwrap();
+
+        assert_eq!(result.bursts.len(), 2);
+        assert_eq!(result.bursts[0].frame_count, 4);
+        assert_eq!(result.bursts[1].frame_count, 3);
+    }
+
+    #[test]
+    fn test_min_frames_config() {
+        let images: Vec<_> = (0..3)
+            .map(|i| create_test_image_ms(&format!("img{}.jpg", i), "cam1", DriveMode::ContinuousHigh, 1_000_000 + i * 100))
+            .collect();
+
+        let config = BurstDetectorConfig { min_frames: 4, ..Default::default() };
+        let result = BurstDetector::detect_with_config(images, &config).unwrap();
+
+        assert_eq!(result.bursts.len(), 0);
+        assert_eq!(result.singles.len(), 3);
+    }
+
+    #[test]
+    fn test_max_gap_config() {
+        let images: Vec<_> = (0..4)
+            .map(|i| create_test_image_ms(&format!("img{}.jpg", i), "cam1", DriveMode::ContinuousLow, 1_000_000 + i * 500))
+            .collect();
+
+        let config = BurstDetectorConfig { max_gap_ms: 400, ..Default::default() };
+        let result = BurstDetector::detect_with_config(images, &config).unwrap();
+
+        assert_eq!(result.bursts.len(), 0);
+        assert_eq!(result.singles.len(), 4);
+    }
+
+    #[test]
+    fn test_native_burst_id_split_on_gap() {
+        // Same native ID but a 60s hole — treat as two bursts
+        let images = vec![
+            create_test_image_with_burst_id("img001.jpg", "cam1", DriveMode::ContinuousHigh, 1000, 100),
+            create_test_image_with_burst_id("img002.jpg", "cam1", DriveMode::ContinuousHigh, 1001, 100),
+            create_test_image_with_burst_id("img003.jpg", "cam1", DriveMode::ContinuousHigh, 1060, 100),
+            create_test_image_with_burst_id("img004.jpg", "cam1", DriveMode::ContinuousHigh, 1061, 100),
+            create_test_image_with_burst_id("img005.jpg", "cam1", DriveMode::ContinuousHigh, 1120, 100),
+        ];
+
+        let result = BurstDetector::detect(images).unwrap();
+
+        assert_eq!(result.bursts.len(), 2);
+        assert_eq!(result.singles.len(), 1);
+        assert_eq!(result.bursts[0].frame_count, 2);
+        assert_eq!(result.bursts[1].frame_count, 2);
+    }
+
+    #[test]
+    fn test_burst_ids_stable_across_shuffled_input() {
+        let mut images = vec![
+            create_test_image("img001.jpg", "camera1", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img002.jpg", "camera1", DriveMode::ContinuousHigh, 1001),
+            create_test_image("img003.jpg", "camera1", DriveMode::Single, 1010),
+            create_test_image("img004.jpg", "camera2", DriveMode::ContinuousLow, 1000),
+            create_test_image("img005.jpg", "camera2", DriveMode::ContinuousLow, 1001),
+            create_test_image("img006.jpg", "camera3", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img007.jpg", "camera3", DriveMode::ContinuousHigh, 1000), // same timestamp
+            create_test_image_with_burst_id("img008.jpg", "camera4", DriveMode::ContinuousHigh, 1000, 7),
+            create_test_image_with_burst_id("img009.jpg", "camera4", DriveMode::ContinuousHigh, 1001, 7),
+        ];
+
+        let first = BurstDetector::detect(images.clone()).unwrap();
+
+        images.reverse();
+        images.swap(1, 5);
+        let second = BurstDetector::detect(images).unwrap();
+
+        let ids = |r: &BurstResult| r.bursts.iter().map(|b| b.id.clone()).collect::<Vec<_>>();
+        let frames = |r: &BurstResult| r.bursts.iter()
+            .map(|b| b.images.iter().map(|i| i.file_path.clone()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(first.bursts.len(), 4);
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(frames(&first), frames(&second));
+        assert_eq!(
+            first.cameras.iter().map(|c| &c.serial).collect::<Vec<_>>(),
+            second.cameras.iter().map(|c| &c.serial).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_burst_ids_ignore_parent_directory() {
+        let on_card = vec![
+            create_test_image("/Volumes/CARD/DCIM/img001.NEF", "camera1", DriveMode::ContinuousHigh, 1000),
+            create_test_image("/Volumes/CARD/DCIM/img002.NEF", "camera1", DriveMode::ContinuousHigh, 1001),
+        ];
+        let copied = vec![
+            create_test_image("/home/me/shoot/img001.NEF", "camera1", DriveMode::ContinuousHigh, 1000),
+            create_test_image("/home/me/shoot/img002.NEF", "camera1", DriveMode::ContinuousHigh, 1001),
+        ];
+
+        let a = BurstDetector::detect(on_card).unwrap();
+        let b = BurstDetector::detect(copied).unwrap();
+
+        assert_eq!(a.bursts[0].id, b.bursts[0].id);
+    }
+
+    #[test]
+    fn test_burst_ids_survive_clock_offset_change() {
+        let images = vec![
+            create_test_image("img001.NEF", "camera1", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img002.NEF", "camera1", DriveMode::ContinuousHigh, 1001),
+            create_test_image("img003.NEF", "camera2", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img004.NEF", "camera2", DriveMode::ContinuousHigh, 1001),
+        ];
+        let before = BurstDetector::detect(images.clone()).unwrap();
+
+        let mut corrected = images;
+        crate::apply_clock_offsets(&mut corrected, &HashMap::from([("camera2".to_string(), 180_000)]));
+        let after = BurstDetector::detect(corrected).unwrap();
+
+        let ids = |r: &BurstResult| r.bursts.iter().map(|b| b.id.clone()).collect::<BTreeSet<_>>();
+        assert_eq!(ids(&before), ids(&after));
+    }
+
+    #[test]
+    fn test_burst_ids_unique_per_burst() {
+        let images = vec![
+            create_test_image("img001.jpg", "camera1", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img002.jpg", "camera1", DriveMode::ContinuousHigh, 1001),
+            create_test_image("img003.jpg", "camera1", DriveMode::Single, 1005),
+            create_test_image("img004.jpg", "camera1", DriveMode::ContinuousHigh, 1010),
+            create_test_image("img005.jpg", "camera1", DriveMode::ContinuousHigh, 1011),
+            create_test_image("img006.jpg", "camera2", DriveMode::ContinuousHigh, 1000),
+            create_test_image("img007.jpg", "camera2", DriveMode::ContinuousHigh, 1001),
+        ];
+
+        let result = BurstDetector::detect(images).unwrap();
+        let mut ids: Vec<_> = result.bursts.iter().map(|b| b.id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| id.starts_with("burst_")));
+    }
+
+    fn phone_session() -> (Vec<ExifData>, HashMap<PathBuf, PerceptualHash>) {
+        let scene = PerceptualHash { dhash: 0xF0F0_F0F0_0F0F_0F0F, phash: 0x1234_5678_9ABC_DEF0 };
+        let nudged = PerceptualHash { dhash: scene.dhash ^ 0b101, phash: scene.phash ^ 0b1 };
+        let other = PerceptualHash { dhash: !scene.dhash, phash: !scene.phash };
+        let images = vec![
+            create_test_image_ms("IMG_001.HEIC", "phone", DriveMode::Unknown, 10_000),
+            create_test_image_ms("IMG_002.HEIC", "phone", DriveMode::Unknown, 10_300),
+            create_test_image_ms("IMG_003.HEIC", "phone", DriveMode::Unknown, 10_550),
+            create_test_image_ms("IMG_004.HEIC", "phone", DriveMode::Unknown, 10_900), // different scene
+            create_test_image_ms("IMG_005.HEIC", "phone", DriveMode::Unknown, 20_000),
+            create_test_image_ms("IMG_006.HEIC", "phone", DriveMode::Unknown, 25_000), // same scene, too late
+        ];
+        let hashes = HashMap::from([
+            (PathBuf::from("IMG_001.HEIC"), scene),
+            (PathBuf::from("IMG_002.HEIC"), nudged),
+            (PathBuf::from("IMG_003.HEIC"), scene),
+            (PathBuf::from("IMG_004.HEIC"), other),
+            (PathBuf::from("IMG_005.HEIC"), scene),
+            (PathBuf::from("IMG_006.HEIC"), scene),
+        ]);
+        (images, hashes)
+    }
+
+    #[test]
+    fn test_unknown_drive_mode_uses_similarity() {
+        let (images, hashes) = phone_session();
+
+        let result = BurstDetector::detect_with_hashes(images, &BurstDetectorConfig::default(), &hashes).unwrap();
+
+        assert_eq!(result.bursts.len(), 1);
+        assert_eq!(result.bursts[0].frame_count, 3);
+        assert_eq!(result.bursts[0].source, BurstSource::Similarity);
+        assert_eq!(result.bursts[0].images[2].file_path, PathBuf::from("IMG_003.HEIC"));
+        assert_eq!(result.singles.len(), 3);
+        assert_eq!(result.cameras[0].burst_count, 1);
+    }
+
+    #[test]
+    fn test_confidence_by_source_and_regularity() {
+        let images = vec![
+            // Steady 100ms drive-mode burst
+            create_test_image_ms("a1.NEF", "z6", DriveMode::ContinuousHigh, 1_000),
+            create_test_image_ms("a2.NEF", "z6", DriveMode::ContinuousHigh, 1_100),
+            create_test_image_ms("a3.NEF", "z6", DriveMode::ContinuousHigh, 1_200),
+            // Ragged one: 100ms, then 350ms
+            create_test_image_ms("a4.NEF", "z6", DriveMode::ContinuousHigh, 10_000),
+            create_test_image_ms("a5.NEF", "z6", DriveMode::ContinuousHigh, 10_100),
+            create_test_image_ms("a6.NEF", "z6", DriveMode::ContinuousHigh, 10_450),
+            create_test_image_with_burst_id("b1.NEF", "z9", DriveMode::ContinuousHigh, 1, 7),
+            create_test_image_with_burst_id("b2.NEF", "z9", DriveMode::ContinuousHigh, 1, 7),
+        ];
+
+        let result = BurstDetector::detect(images).unwrap();
+
+        let steady = result.bursts.iter().find(|b| b.images[0].file_path == Path::new("a1.NEF")).unwrap();
+        let ragged = result.bursts.iter().find(|b| b.images[0].file_path == Path::new("a4.NEF")).unwrap();
+        assert!((steady.confidence - 0.8).abs() < 1e-9);
+        assert!(ragged.confidence < steady.confidence && ragged.confidence >= 0.4);
+
+        let z6 = result.camera_info("z6").unwrap();
+        assert_eq!(z6.burst_source, BurstSource::DriveMode);
+        assert!((z6.burst_confidence - (steady.confidence + ragged.confidence) / 2.0).abs() < 1e-9);
+        let z9 = result.camera_info("z9").unwrap();
+        assert_eq!(z9.burst_source, BurstSource::NativeId);
+        assert_eq!(z9.burst_confidence, 1.0);
+    }
+
+    #[test]
+    fn test_similarity_without_hashes_leaves_singles() {
+        let (images, _) = phone_session();
+
+        let result = BurstDetector::detect(images).unwrap();
+
+        assert!(result.bursts.is_empty());
+        assert_eq!(result.singles.len(), 6);
+        assert_eq!(result.cameras[0].burst_source, BurstSource::Similarity);
+        assert_eq!(result.cameras[0].burst_confidence, BurstSource::Similarity.base_confidence());
+    }
+
+    #[test]
+    fn test_strategy_chosen_per_body() {
+        let (mut images, hashes) = phone_session();
+        // Mostly known drive modes: drive mode inference, even with an Unknown frame
+        images.extend([
+            create_test_image("a1.NEF", "z6", DriveMode::ContinuousHigh, 1000),
+            create_test_image("a2.NEF", "z6", DriveMode::ContinuousHigh, 1001),
+            create_test_image("a3.NEF", "z6", DriveMode::Unknown, 1002),
+            create_test_image_with_burst_id("b1.NEF", "z9", DriveMode::Unknown, 1000, 7),
+            create_test_image_with_burst_id("b2.NEF", "z9", DriveMode::Unknown, 1001, 7),
+        ]);
+
+        let candidates: Vec<&str> = BurstDetector::similarity_candidates(&images, &BurstDetectorConfig::default())
+            .iter()
+            .map(|img| img.serial_number.as_str())
+            .collect();
+        assert_eq!(candidates, vec!["phone"; 6]);
+
+        let result = BurstDetector::detect_with_hashes(images, &BurstDetectorConfig::default(), &hashes).unwrap();
+        let source_of = |serial: &str| result.bursts.iter().find(|b| b.camera_serial == serial).unwrap().source;
+        assert_eq!(source_of("phone"), BurstSource::Similarity);
+        assert_eq!(source_of("z6"), BurstSource::DriveMode);
+        assert_eq!(source_of("z9"), BurstSource::NativeId);
+    }
+
+    #[test]
+    fn test_redetect_cameras_keeps_other_bodies() {
+        let body = |serial: &str, offset_ms: i64| -> Vec<ExifData> {
+            (0..4).map(|i| create_test_image_ms(
+                &format!("/{}/{}.NEF", serial, i), serial, DriveMode::ContinuousHigh, offset_ms + i * 100,
+            )).collect()
+        };
+        let mut images = body("A", 0);
+        images.extend(body("B", 0));
+        let mut previous = BurstDetector::detect(images).unwrap();
+        // Stands in for a user edit on body A, which a full re-detection would undo
+        let a = previous.bursts.iter().position(|b| b.camera_serial == "A").unwrap();
+        previous.bursts[a].id = "kept".to_string();
+
+        // Body B gains a second burst, shot ten seconds later
+        let mut b_images = body("B", 0);
+        b_images.extend((0..3).map(|i| create_test_image_ms(
+            &format!("/B/late{}.NEF", i), "B", DriveMode::ContinuousHigh, 10_000 + i * 100,
+        )));
+        let serials: BTreeSet<String> = ["B".to_string()].into();
+        let result = BurstDetector::redetect_cameras(
+            previous, b_images, &serials, &BurstDetectorConfig::default(), &HashMap::new(),
+        ).unwrap();
+
+        assert!(result.bursts.iter().any(|b| b.id == "kept"));
+        assert_eq!(result.camera_info("A").unwrap().burst_count, 1);
+        assert_eq!(result.camera_info("B").unwrap().burst_count, 2);
+        assert_eq!(result.total_images(), 11);
+        let serials: Vec<&str> = result.cameras.iter().map(|c| c.serial.as_str()).collect();
+        assert_eq!(serials, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_from_parts_summarizes_cameras() {
+        let images: Vec<ExifData> = (0..5).map(|i| create_test_image_ms(
+            &format!("/A/{}.NEF", i), "A", DriveMode::ContinuousHigh, i * 100,
+        )).collect();
+        let detected = BurstDetector::detect(images).unwrap();
+        let mut manual = detected.bursts[0].clone();
+        manual.source = BurstSource::Manual;
+
+        let rebuilt = BurstResult::from_parts(vec![manual], detected.singles.clone());
+
+        let camera = rebuilt.camera_info("A").unwrap();
+        assert_eq!((camera.image_count, camera.burst_count), (5, 1));
+        assert_eq!(camera.burst_source, BurstSource::DriveMode);
+        assert_eq!(camera.burst_confidence, BurstSource::DriveMode.base_confidence());
+        assert_eq!("Similarity".parse::<BurstSource>().unwrap(), BurstSource::Similarity);
+        assert!("Guess".parse::<BurstSource>().is_err());
+    }
+
+    #[test]
+    fn test_from_parts_takes_majority_source_of_detected_bursts() {
+        // Four bursts ten seconds apart
+        let images: Vec<ExifData> = (0..12).map(|i| create_test_image_ms(
+            &format!("/A/{}.NEF", i), "A", DriveMode::ContinuousHigh, i * 100 + (i / 3) * 10_000,
+        )).collect();
+        let mut bursts = BurstDetector::detect(images).unwrap().bursts;
+        assert_eq!(bursts.len(), 4);
+        for (burst, (source, confidence)) in bursts.iter_mut().zip([
+            (BurstSource::Manual, 1.0),
+            (BurstSource::DriveMode, 0.9),
+            (BurstSource::Similarity, 0.5),
+            (BurstSource::Similarity, 0.4),
+        ]) {
+            burst.source = source;
+            burst.confidence = confidence;
+        }
+
+        let rebuilt = BurstResult::from_parts(bursts, Vec::new());
+
+        let camera = rebuilt.camera_info("A").unwrap();
+        assert_eq!(camera.burst_count, 4);
+        assert_eq!(camera.burst_source, BurstSource::Similarity);
+        assert!((camera.burst_confidence - 0.6).abs() < 1e-9);
+    }
+}
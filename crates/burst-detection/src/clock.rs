@@ -0,0 +1,102 @@
+//! Per-body camera clock correction.
+//!
+//! Two bodies at a wedding are rarely in sync: one is set to home time, the
+//! other drifted three minutes over a season. Sorted by raw capture time the
+//! two cards interleave nonsensically, and a burst that straddles a drifted
+//! body's frames can't be aligned with the other shooter's.
+//!
+//! The fix is a per-serial offset (milliseconds) added to every frame from that
+//! body before sorting and burst detection. The photographer derives it by
+//! pairing one frame from each body that captured the same moment — the first
+//! kiss, a flash going off — and we take the difference.
+
+use std::collections::HashMap;
+use anyhow::{Result, bail};
+
+use crate::exif::ExifData;
+
+/// Apply per-serial clock offsets (milliseconds) to `images` in place.
+///
+/// Bodies without an entry are reset to no correction, so re-applying a new
+/// offset map never stacks on top of an old one.
+pub fn apply_clock_offsets(images: &mut [ExifData], offsets: &HashMap<String, i64>) {
+    for image in images {
+        let offset = offsets.get(&image.serial_number).copied().unwrap_or(0);
+        image.set_clock_offset(offset);
+    }
+}
+
+/// Clock offset (milliseconds) for `target`'s body such that `target` lines up
+/// with `reference`, given that both frames captured the same moment.
+///
+/// The reference keeps whatever correction it already has; the result replaces
+/// the target body's offset.
+pub fn clock_offset_from_pair(reference: &ExifData, target: &ExifData) -> Result<i64> {
+    if reference.serial_number == target.serial_number {
+        bail!(
+            "Both frames are from the same camera ({}); pick one frame from each body",
+            reference.serial_number
+        );
+    }
+    Ok((reference.capture_time - target.original_capture_time()).num_milliseconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exif::DriveMode;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::path::PathBuf;
+
+    fn frame(path: &str, serial: &str, secs: i64) -> ExifData {
+        let time = Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap();
+        ExifData::new(PathBuf::from(path), serial.to_string(), DriveMode::Single, time)
+    }
+
+    #[test]
+    fn test_pair_aligns_target_to_reference() {
+        let reference = frame("/a/DSC_0001.NEF", "A", 0);
+        let mut target = frame("/b/_DSC0042.NEF", "B", -185); // body B runs 3m05s slow
+
+        let offset = clock_offset_from_pair(&reference, &target).unwrap();
+        assert_eq!(offset, 185_000);
+
+        target.set_clock_offset(offset);
+        assert_eq!(target.capture_time, reference.capture_time);
+    }
+
+    #[test]
+    fn test_pair_uses_corrected_reference_and_original_target() {
+        let mut reference = frame("/a/DSC_0001.NEF", "A", 0);
+        reference.set_clock_offset(10_000);
+        let mut target = frame("/b/_DSC0042.NEF", "B", 5);
+        target.set_clock_offset(-1_000); // stale correction is replaced, not added to
+
+        let offset = clock_offset_from_pair(&reference, &target).unwrap();
+        target.set_clock_offset(offset);
+
+        assert_eq!(offset, 5_000);
+        assert_eq!(target.capture_time, reference.capture_time);
+    }
+
+    #[test]
+    fn test_pair_rejects_same_body() {
+        let a = frame("/a/DSC_0001.NEF", "A", 0);
+        let b = frame("/a/DSC_0002.NEF", "A", 1);
+        assert!(clock_offset_from_pair(&a, &b).is_err());
+    }
+
+    #[test]
+    fn test_apply_offsets_per_serial() {
+        let mut images = vec![frame("/a/1.NEF", "A", 0), frame("/b/1.NEF", "B", 0)];
+        images[0].set_clock_offset(700); // cleared: A has no entry in the new map
+        let offsets = HashMap::from([("B".to_string(), -2_500)]);
+
+        apply_clock_offsets(&mut images, &offsets);
+
+        assert_eq!(images[0].clock_offset_ms, 0);
+        assert_eq!(images[0].capture_time, images[0].original_capture_time());
+        assert_eq!(images[1].clock_offset_ms, -2_500);
+        assert_eq!(images[1].original_capture_time() - images[1].capture_time, Duration::milliseconds(2_500));
+    }
+}
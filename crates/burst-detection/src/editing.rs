@@ -0,0 +1,357 @@
+//! Manual burst corrections: split, merge, detach and attach frames.
+//!
+//! Detection is a heuristic and will sometimes chain two bursts together or
+//! break one apart. Edits operate on a `BurstResult`, recompute the affected
+//! groups' stats through `BurstGroup::new`, and return a `BurstEdit` listing
+//! what changed so the caller persists only that.
+//!
+//! Edited groups become `BurstSource::Manual` (confidence 1.0 — the user has
+//! looked at them). Detection re-runs on re-import and clock correction, so
+//! `restore_manual_bursts` re-applies the manual groups to a fresh result:
+//! a user's fix is never overwritten by the heuristic it corrected.
+//!
+//! A group left with fewer than two frames dissolves into singles.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, bail, Result};
+
+use crate::burst::{stable_group_id, BurstGroup, BurstResult, BurstSource};
+use crate::exif::ExifData;
+
+/// What an edit changed.
+#[derive(Debug, Clone, Default)]
+pub struct BurstEdit {
+    /// IDs of bursts that no longer exist
+    pub removed: Vec<String>,
+    /// Bursts created or changed, with recomputed stats
+    pub changed: Vec<BurstGroup>,
+    /// Frames that are now singles
+    pub new_singles: Vec<PathBuf>,
+}
+
+impl BurstResult {
+    /// Split a burst before the frame at `at_index`: frames `..at_index` keep
+    /// the burst's ID, frames `at_index..` become a new burst.
+    pub fn split_burst(&mut self, burst_id: &str, at_index: usize) -> Result<BurstEdit> {
+        let burst = self.burst(burst_id)?;
+        if at_index == 0 || at_index >= burst.images.len() {
+            bail!("Can't split {} frame burst {} at frame {}", burst.images.len(), burst_id, at_index);
+        }
+
+        let burst = self.take_burst(burst_id)?;
+        let mut first = burst.images;
+        let second = first.split_off(at_index);
+        let second_id = stable_group_id("burst", &burst.camera_serial, &second[0], None);
+
+        let mut edit = BurstEdit::default();
+        self.put_manual(burst.id, &burst.camera_serial, first, &mut edit);
+        self.put_manual(second_id, &burst.camera_serial, second, &mut edit);
+        self.refresh_burst_counts();
+        Ok(edit)
+    }
+
+    /// Merge two bursts from the same body with no frames of that body
+    /// between them. The merged burst keeps the earlier burst's ID.
+    pub fn merge_bursts(&mut self, first_id: &str, second_id: &str) -> Result<BurstEdit> {
+        if first_id == second_id {
+            bail!("Can't merge burst {} with itself", first_id);
+        }
+        let (a, b) = (self.burst(first_id)?, self.burst(second_id)?);
+        if a.camera_serial != b.camera_serial {
+            bail!("Bursts {} and {} are from different cameras", first_id, second_id);
+        }
+        let (earlier, later) = if a.images[0].capture_time <= b.images[0].capture_time { (a, b) } else { (b, a) };
+
+        let gap_start = earlier.images.last().unwrap().capture_time;
+        let gap_end = later.images[0].capture_time;
+        let serial = earlier.camera_serial.clone();
+        let in_gap = |img: &ExifData| img.serial_number == serial && img.capture_time > gap_start && img.capture_time < gap_end;
+        let between = self.singles.iter().any(in_gap)
+            || self.bursts.iter()
+                .filter(|burst| burst.id != earlier.id && burst.id != later.id)
+                .any(|burst| burst.images.iter().any(in_gap));
+        if between {
+            bail!("Bursts {} and {} aren't adjacent", first_id, second_id);
+        }
+
+        let (earlier_id, later_id) = (earlier.id.clone(), later.id.clone());
+        let mut images = self.take_burst(&earlier_id)?.images;
+        images.extend(self.take_burst(&later_id)?.images);
+
+        let mut edit = BurstEdit { removed: vec![later_id], ..BurstEdit::default() };
+        self.put_manual(earlier_id, &serial, images, &mut edit);
+        self.refresh_burst_counts();
+        Ok(edit)
+    }
+
+    /// Move a frame out of its burst into singles.
+    pub fn detach_frame(&mut self, file_path: &Path) -> Result<BurstEdit> {
+        let burst_id = self.bursts.iter()
+            .find(|burst| burst.images.iter().any(|img| img.file_path == file_path))
+            .map(|burst| burst.id.clone())
+            .ok_or_else(|| anyhow!("{} isn't in a burst", file_path.display()))?;
+
+        let burst = self.take_burst(&burst_id)?;
+        let (detached, rest): (Vec<ExifData>, Vec<ExifData>) = burst.images.into_iter()
+            .partition(|img| img.file_path == file_path);
+
+        let mut edit = BurstEdit::default();
+        edit.new_singles.extend(detached.iter().map(|img| img.file_path.clone()));
+        self.singles.extend(detached);
+        self.put_manual(burst_id, &burst.camera_serial, rest, &mut edit);
+        self.refresh_burst_counts();
+        Ok(edit)
+    }
+
+    /// Add a single to a burst from the same body.
+    pub fn attach_frame(&mut self, file_path: &Path, burst_id: &str) -> Result<BurstEdit> {
+        let serial = self.burst(burst_id)?.camera_serial.clone();
+        let position = self.singles.iter()
+            .position(|img| img.file_path == file_path)
+            .ok_or_else(|| anyhow!("{} isn't a single", file_path.display()))?;
+        if self.singles[position].serial_number != serial {
+            bail!("{} is from a different camera than burst {}", file_path.display(), burst_id);
+        }
+
+        let frame = self.singles.remove(position);
+        let mut images = self.take_burst(burst_id)?.images;
+        images.push(frame);
+
+        let mut edit = BurstEdit::default();
+        self.put_manual(burst_id.to_string(), &serial, images, &mut edit);
+        self.refresh_burst_counts();
+        Ok(edit)
+    }
+
+    /// Re-apply manual bursts (ID and frame paths) to a freshly detected
+    /// result. Frames are pulled out of whatever detection put them in;
+    /// detected bursts that lose frames are rebuilt. Frames no longer in the
+    /// result (deleted files) are skipped.
+    pub fn restore_manual_bursts(&mut self, manual: &[(String, Vec<PathBuf>)]) {
+        let wanted: HashSet<&Path> = manual.iter()
+            .flat_map(|(_, paths)| paths.iter().map(|p| p.as_path()))
+            .collect();
+        let reserved: HashSet<&str> = manual.iter().map(|(id, _)| id.as_str()).collect();
+        let mut frames: HashMap<PathBuf, ExifData> = self.take_frames(&wanted, &reserved)
+            .into_iter()
+            .map(|img| (img.file_path.clone(), img))
+            .collect();
+
+        for (burst_id, paths) in manual {
+            let images: Vec<ExifData> = paths.iter().filter_map(|path| frames.remove(path)).collect();
+            let Some(serial) = images.first().map(|img| img.serial_number.clone()) else {
+                continue;
+            };
+            self.put_manual(burst_id.clone(), &serial, images, &mut BurstEdit::default());
+        }
+        self.refresh_burst_counts();
+    }
+
+    fn burst(&self, burst_id: &str) -> Result<&BurstGroup> {
+        self.bursts.iter()
+            .find(|burst| burst.id == burst_id)
+            .ok_or_else(|| anyhow!("No burst {}", burst_id))
+    }
+
+    fn take_burst(&mut self, burst_id: &str) -> Result<BurstGroup> {
+        let position = self.bursts.iter()
+            .position(|burst| burst.id == burst_id)
+            .ok_or_else(|| anyhow!("No burst {}", burst_id))?;
+        Ok(self.bursts.remove(position))
+    }
+
+    /// Remove `wanted` frames from singles and bursts. Bursts that lose
+    /// frames are rebuilt with their own source, under a fresh ID if theirs
+    /// is `reserved` for a manual burst.
+    fn take_frames(&mut self, wanted: &HashSet<&Path>, reserved: &HashSet<&str>) -> Vec<ExifData> {
+        let (mut taken, singles): (Vec<ExifData>, Vec<ExifData>) = std::mem::take(&mut self.singles)
+            .into_iter()
+            .partition(|img| wanted.contains(img.file_path.as_path()));
+        self.singles = singles;
+
+        for burst in std::mem::take(&mut self.bursts) {
+            if !burst.images.iter().any(|img| wanted.contains(img.file_path.as_path())) {
+                self.bursts.push(burst);
+                continue;
+            }
+            let (frames, rest): (Vec<ExifData>, Vec<ExifData>) = burst.images.into_iter()
+                .partition(|img| wanted.contains(img.file_path.as_path()));
+            taken.extend(frames);
+            if rest.len() >= 2 {
+                let id = if reserved.contains(burst.id.as_str()) {
+                    stable_group_id("burst", &burst.camera_serial, &rest[0], None)
+                } else {
+                    burst.id
+                };
+                self.bursts.push(BurstGroup::new(id, burst.camera_serial, rest).with_source(burst.source));
+            } else {
+                self.singles.extend(rest);
+            }
+        }
+        taken
+    }
+
+    /// Store `images` as a manual burst, or as singles if fewer than two.
+    fn put_manual(&mut self, burst_id: String, camera_serial: &str, images: Vec<ExifData>, edit: &mut BurstEdit) {
+        if images.len() >= 2 {
+            let burst = BurstGroup::new(burst_id, camera_serial.to_string(), images).with_source(BurstSource::Manual);
+            edit.changed.push(burst.clone());
+            self.bursts.push(burst);
+        } else {
+            edit.removed.push(burst_id);
+            edit.new_singles.extend(images.iter().map(|img| img.file_path.clone()));
+            self.singles.extend(images);
+        }
+    }
+
+    fn refresh_burst_counts(&mut self) {
+        for camera in &mut self.cameras {
+            camera.burst_count = self.bursts.iter().filter(|b| b.camera_serial == camera.serial).count();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burst::BurstDetector;
+    use crate::exif::DriveMode;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn frame(path: &str, serial: &str, drive_mode: DriveMode, ms: i64) -> ExifData {
+        let base = Utc.with_ymd_and_hms(2025, 6, 21, 15, 0, 0).unwrap();
+        ExifData::new(PathBuf::from(path), serial.to_string(), drive_mode, base + Duration::milliseconds(ms))
+    }
+
+    /// Body A: one 6-frame burst (really two, 250ms apart), a single, a
+    /// 2-frame burst. Body B: one 2-frame burst.
+    fn session() -> Vec<ExifData> {
+        let ch = |path, serial, ms| frame(path, serial, DriveMode::ContinuousHigh, ms);
+        vec![
+            ch("/a/1.NEF", "A", 0),
+            ch("/a/2.NEF", "A", 100),
+            ch("/a/3.NEF", "A", 200),
+            ch("/a/4.NEF", "A", 450),
+            ch("/a/5.NEF", "A", 550),
+            ch("/a/6.NEF", "A", 650),
+            frame("/a/7.NEF", "A", DriveMode::Single, 5_000),
+            ch("/a/8.NEF", "A", 10_000),
+            ch("/a/9.NEF", "A", 10_100),
+            ch("/b/1.NEF", "B", 300),
+            ch("/b/2.NEF", "B", 400),
+        ]
+    }
+
+    fn burst_of<'a>(result: &'a BurstResult, path: &str) -> Option<&'a BurstGroup> {
+        result.bursts.iter().find(|b| b.images.iter().any(|img| img.file_path == Path::new(path)))
+    }
+
+    #[test]
+    fn test_split_recomputes_stats() {
+        let mut result = BurstDetector::detect(session()).unwrap();
+        let id = burst_of(&result, "/a/1.NEF").unwrap().id.clone();
+
+        let edit = result.split_burst(&id, 3).unwrap();
+
+        assert_eq!(edit.changed.len(), 2);
+        assert!(edit.removed.is_empty());
+        let first = burst_of(&result, "/a/1.NEF").unwrap();
+        let second = burst_of(&result, "/a/4.NEF").unwrap();
+        assert_eq!(first.id, id);
+        assert_ne!(second.id, id);
+        assert_eq!((first.frame_count, first.duration_ms), (3, 200));
+        assert_eq!((second.frame_count, second.duration_ms), (3, 200));
+        assert_eq!(second.estimated_fps, 10.0);
+        assert_eq!(second.source, BurstSource::Manual);
+        assert_eq!(result.camera_info("A").unwrap().burst_count, 3);
+
+        assert!(result.split_burst(&id, 0).is_err());
+        assert!(result.split_burst(&id, 3).is_err());
+    }
+
+    #[test]
+    fn test_merge_requires_adjacent_same_camera() {
+        let mut result = BurstDetector::detect(session()).unwrap();
+        let big = burst_of(&result, "/a/1.NEF").unwrap().id.clone();
+        result.split_burst(&big, 3).unwrap();
+        let second = burst_of(&result, "/a/4.NEF").unwrap().id.clone();
+        let last = burst_of(&result, "/a/8.NEF").unwrap().id.clone();
+        let other_body = burst_of(&result, "/b/1.NEF").unwrap().id.clone();
+
+        assert!(result.merge_bursts(&second, &last).is_err()); // /a/7.NEF is between
+        assert!(result.merge_bursts(&big, &other_body).is_err());
+
+        // Either order; the earlier ID survives
+        let edit = result.merge_bursts(&second, &big).unwrap();
+        assert_eq!(edit.removed, vec![second]);
+        let merged = burst_of(&result, "/a/6.NEF").unwrap();
+        assert_eq!((merged.id.as_str(), merged.frame_count), (big.as_str(), 6));
+        assert_eq!(merged.images[0].file_path, PathBuf::from("/a/1.NEF"));
+    }
+
+    #[test]
+    fn test_detach_and_attach() {
+        let mut result = BurstDetector::detect(session()).unwrap();
+        let pair = burst_of(&result, "/a/8.NEF").unwrap().id.clone();
+
+        // Detaching from a 2-frame burst dissolves it
+        let edit = result.detach_frame(Path::new("/a/9.NEF")).unwrap();
+        assert_eq!(edit.removed, vec![pair]);
+        assert_eq!(edit.new_singles.len(), 2);
+        assert!(burst_of(&result, "/a/8.NEF").is_none());
+
+        let big = burst_of(&result, "/a/1.NEF").unwrap().id.clone();
+        let edit = result.attach_frame(Path::new("/a/7.NEF"), &big).unwrap();
+        let grown = &edit.changed[0];
+        assert_eq!(grown.frame_count, 7);
+        assert_eq!(grown.images.last().unwrap().file_path, PathBuf::from("/a/7.NEF"));
+        assert_eq!(grown.duration_ms, 5_000);
+
+        assert!(result.attach_frame(Path::new("/a/7.NEF"), &big).is_err()); // no longer a single
+        assert!(result.attach_frame(Path::new("/a/8.NEF"), &burst_of(&result, "/b/1.NEF").unwrap().id.clone()).is_err());
+        assert!(result.detach_frame(Path::new("/a/8.NEF")).is_err());
+    }
+
+    #[test]
+    fn test_manual_bursts_survive_redetection() {
+        let mut result = BurstDetector::detect(session()).unwrap();
+        let big = burst_of(&result, "/a/1.NEF").unwrap().id.clone();
+        result.split_burst(&big, 3).unwrap();
+        let manual: Vec<(String, Vec<PathBuf>)> = result.bursts.iter()
+            .filter(|b| b.source == BurstSource::Manual)
+            .map(|b| (b.id.clone(), b.images.iter().map(|img| img.file_path.clone()).collect()))
+            .collect();
+
+        let mut redetected = BurstDetector::detect(session()).unwrap();
+        redetected.restore_manual_bursts(&manual);
+
+        assert_eq!(redetected.bursts.len(), result.bursts.len());
+        assert_eq!(burst_of(&redetected, "/a/3.NEF").unwrap().frame_count, 3);
+        assert_eq!(burst_of(&redetected, "/a/4.NEF").unwrap().source, BurstSource::Manual);
+        assert_eq!(redetected.total_images(), 11);
+        assert_eq!(redetected.camera_info("A").unwrap().burst_count, 3);
+    }
+
+    #[test]
+    fn test_restore_keeps_ids_unique() {
+        let mut result = BurstDetector::detect(session()).unwrap();
+        let big = burst_of(&result, "/a/1.NEF").unwrap().id.clone();
+        // Only frames 1-3 stay under the original ID; detection will still
+        // group 4-6 with them
+        result.split_burst(&big, 3).unwrap();
+        result.detach_frame(Path::new("/a/5.NEF")).unwrap();
+        result.detach_frame(Path::new("/a/6.NEF")).unwrap();
+        let manual = vec![(big.clone(), vec![PathBuf::from("/a/1.NEF"), PathBuf::from("/a/2.NEF"), PathBuf::from("/a/3.NEF")])];
+
+        let mut redetected = BurstDetector::detect(session()).unwrap();
+        redetected.restore_manual_bursts(&manual);
+
+        let mut ids: Vec<&str> = redetected.bursts.iter().map(|b| b.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), redetected.bursts.len());
+        assert_eq!(burst_of(&redetected, "/a/1.NEF").unwrap().id, big);
+        assert_ne!(burst_of(&redetected, "/a/4.NEF").unwrap().id, big);
+    }
+}
@@ -11,11 +11,24 @@
 //!
 //! We use `-fast` (not `-fast2`) because `-fast2` skips maker notes entirely,
 //! which would lose BurstGroupID — our primary burst detection signal.
+//!
+//! exiftool can hang or die on a malformed file. Every batch runs under a
+//! watchdog: if `{ready}` doesn't arrive by the batch's deadline (or stdout
+//! hits EOF), the process is killed and respawned and the batch is bisected until
+//! the poison file is isolated and reported as `ExifError::Crashed`.
+//!
+//! `ExifSource` abstracts over backends: `ExiftoolRunner` here, and the pure-Rust
+//! `NativeExifReader` (see `native_exif`) for machines without exiftool installed.
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio, Child};
 use std::io::{BufRead, BufReader, Write, BufWriter};
-use chrono::{DateTime, Utc, NaiveDateTime, Timelike};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Duration as ChronoDuration, Utc, NaiveDateTime, Timelike};
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, Context, bail};
 
@@ -34,6 +47,21 @@ impl DriveMode {
     }
 }
 
+/// Parses the `Debug` name the session database stores.
+impl std::str::FromStr for DriveMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Single" => Ok(DriveMode::Single),
+            "ContinuousLow" => Ok(DriveMode::ContinuousLow),
+            "ContinuousHigh" => Ok(DriveMode::ContinuousHigh),
+            "Unknown" => Ok(DriveMode::Unknown),
+            _ => bail!("Unknown drive mode: {}", s),
+        }
+    }
+}
+
 /// Normalized EXIF metadata for a single image file.
 ///
 /// This is the clean output after parsing exiftool's JSON — all brand-specific
@@ -47,7 +75,17 @@ pub struct ExifData {
     /// Drive mode at time of capture — primary signal for burst detection
     /// when native BurstGroupID is unavailable.
     pub drive_mode: DriveMode,
+    /// Capture time in UTC, with `clock_offset_ms` already applied. True UTC when
+    /// the camera recorded OffsetTimeOriginal; otherwise the body's wall clock
+    /// read as if it were UTC.
     pub capture_time: DateTime<Utc>,
+    /// OffsetTimeOriginal in seconds east of UTC, if the camera recorded it.
+    #[serde(default)]
+    pub utc_offset_secs: Option<i32>,
+    /// Per-body clock correction (milliseconds) applied to `capture_time`.
+    /// Set via `set_clock_offset`; 0 for an uncorrected body.
+    #[serde(default)]
+    pub clock_offset_ms: i64,
     pub make: Option<String>,
     pub model: Option<String>,
     pub lens: Option<String>,
@@ -61,6 +99,9 @@ pub struct ExifData {
     pub burst_group_id: Option<u64>,
     /// High frame rate mode tag (e.g., "CH" = continuous high, "CL" = continuous low).
     pub high_frame_rate: Option<String>,
+    /// JPEG written alongside this RAW (see `pairing`). `file_path` stays the primary.
+    #[serde(default)]
+    pub sidecar_path: Option<PathBuf>,
 }
 
 impl ExifData {
@@ -70,6 +111,8 @@ impl ExifData {
             serial_number,
             drive_mode,
             capture_time,
+            utc_offset_secs: None,
+            clock_offset_ms: 0,
             make: None,
             model: None,
             lens: None,
@@ -80,8 +123,21 @@ impl ExifData {
             file_path,
             burst_group_id: None,
             high_frame_rate: None,
+            sidecar_path: None,
         }
     }
+
+    /// Capture time as the camera recorded it, before clock correction.
+    pub fn original_capture_time(&self) -> DateTime<Utc> {
+        self.capture_time - ChronoDuration::milliseconds(self.clock_offset_ms)
+    }
+
+    /// Replace the clock correction for this frame. Re-bases from the original
+    /// time, so calling it repeatedly doesn't accumulate offsets.
+    pub fn set_clock_offset(&mut self, offset_ms: i64) {
+        self.capture_time = self.original_capture_time() + ChronoDuration::milliseconds(offset_ms);
+        self.clock_offset_ms = offset_ms;
+    }
 }
 
 /// Flexible deserializer for EXIF fields that exiftool returns as inconsistent types.
@@ -153,6 +209,8 @@ struct ExiftoolOutput {
     date_time_original: Option<String>,
     #[serde(rename = "SubSecTimeOriginal", deserialize_with = "deserialize_string_or_number", default)]
     subsec_time_original: Option<String>,
+    #[serde(rename = "OffsetTimeOriginal")]
+    offset_time_original: Option<String>,
     #[serde(rename = "Make")]
     make: Option<String>,
     #[serde(rename = "Model")]
@@ -171,20 +229,124 @@ struct ExiftoolOutput {
     burst_group_id: Option<u64>,
     #[serde(rename = "HighFrameRate")]
     high_frame_rate: Option<String>,
+    #[serde(rename = "Error")]
+    error: Option<String>,
     #[serde(rename = "SourceFile")]
     source_file: String,
 }
 
+/// Why a single file produced no `ExifData`.
+///
+/// Extraction is per-file: one corrupt frame on a 5,000-image card is reported
+/// here instead of failing the whole batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExifError {
+    /// No usable DateTimeOriginal. We refuse to guess — a fabricated timestamp
+    /// would silently sort the frame into the wrong place (or the wrong burst).
+    MissingDateTime { file_path: PathBuf },
+    /// The file exists in a supported format but couldn't be read or parsed.
+    Unreadable { file_path: PathBuf, reason: String },
+    /// The backend doesn't understand this file format.
+    Unsupported { file_path: PathBuf, reason: String },
+    /// exiftool hung or died while reading this file (isolated by bisection).
+    Crashed { file_path: PathBuf, reason: String },
+}
+
+impl ExifError {
+    /// Path of the file that failed.
+    pub fn file_path(&self) -> &Path {
+        match self {
+            ExifError::MissingDateTime { file_path }
+            | ExifError::Unreadable { file_path, .. }
+            | ExifError::Unsupported { file_path, .. }
+            | ExifError::Crashed { file_path, .. } => file_path,
+        }
+    }
+
+    /// Short machine-readable name of the error variant (for frontend payloads).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExifError::MissingDateTime { .. } => "MissingDateTime",
+            ExifError::Unreadable { .. } => "Unreadable",
+            ExifError::Unsupported { .. } => "Unsupported",
+            ExifError::Crashed { .. } => "Crashed",
+        }
+    }
+}
+
+impl std::fmt::Display for ExifError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExifError::MissingDateTime { file_path } => {
+                write!(f, "{}: no capture time (DateTimeOriginal) in metadata", file_path.display())
+            }
+            ExifError::Unreadable { file_path, reason } => {
+                write!(f, "{}: unreadable ({})", file_path.display(), reason)
+            }
+            ExifError::Unsupported { file_path, reason } => {
+                write!(f, "{}: unsupported ({})", file_path.display(), reason)
+            }
+            ExifError::Crashed { file_path, reason } => {
+                write!(f, "{}: exiftool failed on this file ({})", file_path.display(), reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExifError {}
+
+/// Per-file extraction outcome.
+pub type ExifResult = std::result::Result<ExifData, ExifError>;
+
+/// A backend that turns image file paths into normalized `ExifData`.
+pub trait ExifSource {
+    /// Extract EXIF data from multiple image files.
+    ///
+    /// Returns one result per input path, in input order. The outer `Err` is
+    /// reserved for failures of the backend itself (e.g., exiftool died).
+    fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>>;
+}
+
 pub struct ExiftoolRunner {
     child: Child,
     stdin: BufWriter<std::process::ChildStdin>,
-    stdout: BufReader<std::process::ChildStdout>,
+    /// stdout lines, fed by a reader thread so reads can time out.
+    /// Disconnects when exiftool closes stdout (exit or crash).
+    lines: Receiver<String>,
+    program: OsString,
+    timeout: Duration,
+    per_file_timeout: Duration,
 }
 
 impl ExiftoolRunner {
+    /// Watchdog floor used by `new()`: the deadline for a batch of any size.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Added to the deadline per file in the batch. A pool shard can hold
+    /// hundreds of files, and a flat deadline would kill a healthy shard on a
+    /// slow card; scaling also keeps bisection cheap, since halves get
+    /// proportionally shorter deadlines.
+    pub const DEFAULT_PER_FILE_TIMEOUT: Duration = Duration::from_millis(100);
+
     /// Create a new ExiftoolRunner with a persistent exiftool process
     pub fn new() -> Result<Self> {
-        let mut child = Command::new("exiftool")
+        Self::with_timeout(Self::DEFAULT_TIMEOUT)
+    }
+
+    /// Create a runner whose batches are killed and retried if exiftool doesn't
+    /// answer within `timeout` plus `DEFAULT_PER_FILE_TIMEOUT` per file.
+    pub fn with_timeout(timeout: Duration) -> Result<Self> {
+        Self::with_timeouts(timeout, Self::DEFAULT_PER_FILE_TIMEOUT)
+    }
+
+    /// Create a runner whose batch deadline is `timeout` plus `per_file_timeout`
+    /// for each file in the batch.
+    pub fn with_timeouts(timeout: Duration, per_file_timeout: Duration) -> Result<Self> {
+        Self::spawn(OsStr::new("exiftool"), timeout, per_file_timeout)
+    }
+
+    fn spawn(program: &OsStr, timeout: Duration, per_file_timeout: Duration) -> Result<Self> {
+        let mut child = Command::new(program)
             .args(["-stay_open", "True", "-@", "-"])
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -197,24 +359,131 @@ impl ExiftoolRunner {
                 .context("Failed to get stdin handle for exiftool process")?
         );
 
-        let stdout = BufReader::new(
+        let mut stdout = BufReader::new(
             child.stdout.take()
                 .context("Failed to get stdout handle for exiftool process")?
         );
 
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut line = String::new();
+            match stdout.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => if tx.send(line).is_err() { break },
+            }
+        });
+
         Ok(Self {
             child,
             stdin,
-            stdout,
+            lines,
+            program: program.to_os_string(),
+            timeout,
+            per_file_timeout,
         })
     }
 
-    /// Extract EXIF data from multiple image files
-    pub fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifData>> {
+    /// Watchdog deadline for a batch of `file_count` files.
+    pub fn batch_timeout(&self, file_count: usize) -> Duration {
+        self.timeout + self.per_file_timeout.saturating_mul(file_count.try_into().unwrap_or(u32::MAX))
+    }
+
+    /// Kill the current exiftool process and start a fresh one.
+    fn restart(&mut self) -> Result<()> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        *self = Self::spawn(&self.program, self.timeout, self.per_file_timeout)
+            .context("Failed to restart exiftool after it hung or crashed")?;
+        Ok(())
+    }
+
+    /// Extract EXIF data from multiple image files, one result per path in input order
+    pub fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>> {
+        let raw = self.extract_raw(paths)?;
+
+        // Convert to our ExifData format
+        let mut make_model_map: HashMap<String, usize> = HashMap::new();
+        Ok(paths.iter().zip(raw)
+            .map(|(path, output)| output.and_then(|data| convert_output(path, data, &mut make_model_map)))
+            .collect())
+    }
+
+    /// Run one batch through exiftool and split its JSON into per-file entries.
+    ///
+    /// If the batch's JSON as a whole fails to parse, each file is re-run on its
+    /// own so only the offending file is reported. If exiftool hangs or dies,
+    /// it is respawned and the batch bisected until the poison file is found.
+    /// The outer `Err` means exiftool couldn't be respawned.
+    fn extract_raw(&mut self, paths: &[PathBuf]) -> Result<Vec<std::result::Result<ExiftoolOutput, ExifError>>> {
         if paths.is_empty() {
             return Ok(Vec::new());
         }
 
+        let json_output = match self.execute(paths) {
+            Ok(output) => output,
+            Err(failure) => {
+                self.restart()?;
+                if let [path] = paths {
+                    eprintln!("exiftool failed on {}: {}", path.display(), failure);
+                    return Ok(vec![Err(ExifError::Crashed {
+                        file_path: path.clone(),
+                        reason: failure.to_string(),
+                    })]);
+                }
+                let (left, right) = paths.split_at(paths.len() / 2);
+                let mut results = self.extract_raw(left)?;
+                results.extend(self.extract_raw(right)?);
+                return Ok(results);
+            }
+        };
+
+        let entries: Vec<serde_json::Value> = match serde_json::from_str(&json_output) {
+            Ok(entries) => entries,
+            Err(_) if paths.len() > 1 => {
+                let mut results = Vec::with_capacity(paths.len());
+                for path in paths {
+                    results.extend(self.extract_raw(std::slice::from_ref(path))?);
+                }
+                return Ok(results);
+            }
+            Err(e) => {
+                return Ok(vec![Err(ExifError::Unreadable {
+                    file_path: paths[0].clone(),
+                    reason: format!("unparseable exiftool output: {}", e),
+                })]);
+            }
+        };
+
+        // exiftool echoes SourceFile exactly as given; use it to restore input order
+        let mut by_source: HashMap<String, serde_json::Value> = entries.into_iter()
+            .filter_map(|entry| {
+                let source = entry.get("SourceFile")?.as_str()?.to_string();
+                Some((source, entry))
+            })
+            .collect();
+
+        Ok(paths.iter().map(|path| {
+            let entry = by_source.remove(&path.display().to_string())
+                .ok_or_else(|| ExifError::Unreadable {
+                    file_path: path.clone(),
+                    reason: "exiftool returned no metadata".to_string(),
+                })?;
+            serde_json::from_value::<ExiftoolOutput>(entry)
+                .map_err(|e| ExifError::Unreadable {
+                    file_path: path.clone(),
+                    reason: format!("unexpected exiftool output: {}", e),
+                })
+        }).collect())
+    }
+
+    /// Send one `-execute` command and collect stdout up to the `{ready}` sentinel.
+    ///
+    /// Fails if exiftool exits, closes stdout, or misses the batch deadline.
+    /// After a failure the process is in an unknown state and must be restarted.
+    fn execute(&mut self, paths: &[PathBuf]) -> Result<String> {
+        let timeout = self.batch_timeout(paths.len());
+        let deadline = Instant::now() + timeout;
+
         // Write exiftool arguments
         writeln!(self.stdin, "-json")?;
         writeln!(self.stdin, "-fast")?;  // -fast not -fast2: we need maker notes for BurstGroupID
@@ -226,6 +495,7 @@ impl ExiftoolRunner {
         writeln!(self.stdin, "-HighFrameRate")?;
         writeln!(self.stdin, "-DateTimeOriginal")?;
         writeln!(self.stdin, "-SubSecTimeOriginal")?;
+        writeln!(self.stdin, "-OffsetTimeOriginal")?;
         writeln!(self.stdin, "-Make")?;
         writeln!(self.stdin, "-Model")?;
         writeln!(self.stdin, "-LensModel")?;
@@ -233,6 +503,7 @@ impl ExiftoolRunner {
         writeln!(self.stdin, "-Aperture")?;
         writeln!(self.stdin, "-ShutterSpeed")?;
         writeln!(self.stdin, "-ISO")?;
+        writeln!(self.stdin, "-Error")?;  // per-file read errors, e.g. "Unknown file type"
 
         // Write file paths
         for path in paths {
@@ -246,11 +517,17 @@ impl ExiftoolRunner {
         // Read JSON output until {ready} sentinel
         let mut json_output = String::new();
         loop {
-            let mut line = String::new();
-            let bytes_read = self.stdout.read_line(&mut line)?;
-            if bytes_read == 0 {
-                bail!("Unexpected EOF from exiftool process");
-            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(remaining) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    bail!("exiftool did not respond within {:?}", timeout);
+                }
+                Err(RecvTimeoutError::Disconnected) => match self.child.try_wait() {
+                    Ok(Some(status)) => bail!("exiftool exited unexpectedly ({})", status),
+                    _ => bail!("Unexpected EOF from exiftool process"),
+                },
+            };
 
             let trimmed = line.trim();
             if trimmed.starts_with("{ready") && trimmed.ends_with("}") {
@@ -259,88 +536,85 @@ impl ExiftoolRunner {
             json_output.push_str(&line);
         }
 
-        // Parse JSON output
-        let exiftool_data: Vec<ExiftoolOutput> = serde_json::from_str(&json_output)
-            .with_context(|| {
-                let preview = if json_output.len() > 500 {
-                    format!("{}...(truncated, {} bytes total)", &json_output[..500], json_output.len())
-                } else {
-                    json_output.clone()
-                };
-                format!("Failed to parse exiftool JSON output. First bytes: {}", preview)
-            })?;
+        Ok(json_output)
+    }
+}
 
-        // Convert to our ExifData format
-        let mut results = Vec::new();
-        let _unknown_serial_counter = 0;
-        let mut make_model_map: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-
-        for data in exiftool_data {
-            let file_path = PathBuf::from(data.source_file);
-            
-            // Determine serial number with fallback logic
-            let serial_number = if let Some(serial) = data.serial_number {
-                serial
-            } else if let Some(internal_serial) = data.internal_serial_number {
-                internal_serial
-            } else {
-                // Use Make+Model as fallback, with incrementing counter for uniqueness
-                let make_model = match (&data.make, &data.model) {
-                    (Some(make), Some(model)) => format!("{}_{}", make, model),
-                    (Some(make), None) => make.clone(),
-                    (None, Some(model)) => model.clone(),
-                    (None, None) => "unknown".to_string(),
-                };
-                
-                let counter = make_model_map.entry(make_model.clone()).or_insert(0);
-                *counter += 1;
-                format!("unknown_{}_{}", make_model, counter)
-            };
+/// Normalize one exiftool JSON entry into `ExifData`.
+fn convert_output(
+    path: &Path,
+    data: ExiftoolOutput,
+    make_model_map: &mut HashMap<String, usize>,
+) -> ExifResult {
+    if let Some(error) = data.error {
+        let file_path = path.to_path_buf();
+        return Err(if error.contains("Unknown file type") || error.contains("not supported") {
+            ExifError::Unsupported { file_path, reason: error }
+        } else {
+            ExifError::Unreadable { file_path, reason: error }
+        });
+    }
 
-            // Parse drive mode
-            let drive_mode = parse_drive_mode(
-                data.drive_mode.as_deref().unwrap_or(""),
-                data.shooting_mode.as_deref().unwrap_or("")
-            );
-
-            // Parse capture time
-            let capture_time = parse_capture_time(
-                data.date_time_original.as_deref(),
-                data.subsec_time_original.as_deref()
-            ).unwrap_or_else(|| Utc::now());
-
-            // Parse numeric fields
-            let focal_length = data.focal_length.as_ref()
-                .and_then(|f| f.split_whitespace().next())
-                .and_then(|f| f.parse().ok());
-
-            let aperture = data.aperture.as_ref()
-                .and_then(|a| a.parse().ok());
-
-            let iso = data.iso.and_then(|v| match v {
-                serde_json::Value::Number(n) => n.as_u64().map(|n| n as u32),
-                serde_json::Value::String(s) => s.parse().ok(),
-                _ => None,
-            });
-
-            results.push(ExifData {
-                serial_number,
-                drive_mode,
-                capture_time,
-                make: data.make,
-                model: data.model,
-                lens: data.lens_model,
-                focal_length,
-                aperture,
-                shutter_speed: data.shutter_speed,
-                iso,
-                file_path,
-                burst_group_id: data.burst_group_id,
-                high_frame_rate: data.high_frame_rate,
-            });
-        }
+    // Parse capture time — no capture time, no ExifData
+    let utc_offset_secs = data.offset_time_original.as_deref().and_then(parse_utc_offset);
+    let capture_time = parse_capture_time(
+        data.date_time_original.as_deref(),
+        data.subsec_time_original.as_deref()
+    ).map(|wall| wall_clock_to_utc(wall, utc_offset_secs))
+        .ok_or_else(|| ExifError::MissingDateTime { file_path: path.to_path_buf() })?;
+
+    // Determine serial number with fallback logic
+    let serial_number = resolve_serial(
+        data.serial_number,
+        data.internal_serial_number,
+        data.make.as_deref(),
+        data.model.as_deref(),
+        make_model_map,
+    );
+
+    // Parse drive mode
+    let drive_mode = parse_drive_mode(
+        data.drive_mode.as_deref().unwrap_or(""),
+        data.shooting_mode.as_deref().unwrap_or("")
+    );
+
+    // Parse numeric fields
+    let focal_length = data.focal_length.as_ref()
+        .and_then(|f| f.split_whitespace().next())
+        .and_then(|f| f.parse().ok());
+
+    let aperture = data.aperture.as_ref()
+        .and_then(|a| a.parse().ok());
+
+    let iso = data.iso.and_then(|v| match v {
+        serde_json::Value::Number(n) => n.as_u64().map(|n| n as u32),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    });
+
+    Ok(ExifData {
+        serial_number,
+        drive_mode,
+        capture_time,
+        utc_offset_secs,
+        clock_offset_ms: 0,
+        make: data.make,
+        model: data.model,
+        lens: data.lens_model,
+        focal_length,
+        aperture,
+        shutter_speed: data.shutter_speed,
+        iso,
+        file_path: PathBuf::from(data.source_file),
+        burst_group_id: data.burst_group_id,
+        high_frame_rate: data.high_frame_rate,
+        sidecar_path: None,
+    })
+}
 
-        Ok(results)
+impl ExifSource for ExiftoolRunner {
+    fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>> {
+        ExiftoolRunner::extract(self, paths)
     }
 }
 
@@ -354,8 +628,59 @@ impl Drop for ExiftoolRunner {
     }
 }
 
+/// Pick the camera serial number, falling back to InternalSerialNumber and then
+/// a Make_Model placeholder with an incrementing counter for uniqueness.
+///
+/// `make_model_map` holds the counters and should live for one extraction batch.
+pub(crate) fn resolve_serial(
+    serial: Option<String>,
+    internal_serial: Option<String>,
+    make: Option<&str>,
+    model: Option<&str>,
+    make_model_map: &mut HashMap<String, usize>,
+) -> String {
+    if let Some(serial) = serial {
+        return serial;
+    }
+    if let Some(internal_serial) = internal_serial {
+        return internal_serial;
+    }
+
+    let make_model = match (make, model) {
+        (Some(make), Some(model)) => format!("{}_{}", make, model),
+        (Some(make), None) => make.to_string(),
+        (None, Some(model)) => model.to_string(),
+        (None, None) => "unknown".to_string(),
+    };
+
+    let counter = make_model_map.entry(make_model.clone()).or_insert(0);
+    *counter += 1;
+    format!("unknown_{}_{}", make_model, counter)
+}
+
+/// Renumber `unknown_{make_model}_{n}` placeholder serials in input order.
+///
+/// `resolve_serial` counters are per batch; when a batch is split across
+/// workers, or merged from native and fallback results, each part starts
+/// counting at 1 and placeholders collide.
+pub(crate) fn renumber_unknown_serials(results: &mut [ExifResult]) {
+    let mut make_model_map: HashMap<String, usize> = HashMap::new();
+    for data in results.iter_mut().filter_map(|r| r.as_mut().ok()) {
+        let Some(make_model) = data.serial_number.strip_prefix("unknown_")
+            .and_then(|rest| rest.rsplit_once('_'))
+            .filter(|(_, n)| n.parse::<usize>().is_ok())
+            .map(|(make_model, _)| make_model.to_string())
+        else {
+            continue;
+        };
+        let counter = make_model_map.entry(make_model.clone()).or_insert(0);
+        *counter += 1;
+        data.serial_number = format!("unknown_{}_{}", make_model, counter);
+    }
+}
+
 /// Parse drive mode from raw exiftool output
-fn parse_drive_mode(drive_mode_raw: &str, shooting_mode_raw: &str) -> DriveMode {
+pub(crate) fn parse_drive_mode(drive_mode_raw: &str, shooting_mode_raw: &str) -> DriveMode {
     let combined = format!("{} {}", drive_mode_raw, shooting_mode_raw).to_lowercase();
     
     if combined.contains("continuous") {
@@ -375,7 +700,7 @@ fn parse_drive_mode(drive_mode_raw: &str, shooting_mode_raw: &str) -> DriveMode
 }
 
 /// Parse capture time with subsecond precision
-fn parse_capture_time(date_time_original: Option<&str>, subsec_time_original: Option<&str>) -> Option<DateTime<Utc>> {
+pub(crate) fn parse_capture_time(date_time_original: Option<&str>, subsec_time_original: Option<&str>) -> Option<DateTime<Utc>> {
     let date_str = date_time_original?;
     
     // Parse base datetime
@@ -398,6 +723,31 @@ fn parse_capture_time(date_time_original: Option<&str>, subsec_time_original: Op
     Some(DateTime::from_naive_utc_and_offset(dt_with_subsec, Utc))
 }
 
+/// Parse an EXIF 2.31 offset string ("+02:00", "-05:30") into seconds east of UTC.
+pub(crate) fn parse_utc_offset(offset: &str) -> Option<i32> {
+    let offset = offset.trim();
+    let (sign, rest) = match offset.as_bytes().first()? {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Convert a wall-clock time (parsed as if UTC) to true UTC when the offset is known.
+pub(crate) fn wall_clock_to_utc(wall: DateTime<Utc>, utc_offset_secs: Option<i32>) -> DateTime<Utc> {
+    match utc_offset_secs {
+        Some(offset) => wall - ChronoDuration::seconds(offset as i64),
+        None => wall,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,4 +800,185 @@ mod tests {
         assert_eq!(exif_data.drive_mode, drive_mode);
         assert_eq!(exif_data.capture_time, time);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_utc_offset() {
+        assert_eq!(parse_utc_offset("+02:00"), Some(7200));
+        assert_eq!(parse_utc_offset("-05:30"), Some(-19800));
+        assert_eq!(parse_utc_offset("+00:00"), Some(0));
+        assert_eq!(parse_utc_offset("   :  "), None);
+        assert_eq!(parse_utc_offset("02:00"), None);
+    }
+
+    #[test]
+    fn test_set_clock_offset_rebases() {
+        let time = Utc::now();
+        let mut exif_data = ExifData::new(PathBuf::from("a.jpg"), "1".to_string(), DriveMode::Single, time);
+
+        exif_data.set_clock_offset(5_000);
+        exif_data.set_clock_offset(-2_000);
+
+        assert_eq!(exif_data.capture_time, time - ChronoDuration::milliseconds(2_000));
+        assert_eq!(exif_data.original_capture_time(), time);
+    }
+
+    fn exiftool_entry(json: &str) -> ExiftoolOutput {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_convert_output_full_entry() {
+        let entry = exiftool_entry(r#"{
+            "SourceFile": "/photos/DSC_0001.NEF",
+            "SerialNumber": 3002851,
+            "ShootingMode": "Continuous",
+            "DateTimeOriginal": "2025:08:14 18:45:40",
+            "SubSecTimeOriginal": 25,
+            "OffsetTimeOriginal": "+02:00",
+            "Make": "NIKON CORPORATION",
+            "Model": "NIKON Z 9",
+            "FocalLength": "500.0 mm",
+            "Aperture": 4.5,
+            "ISO": 800,
+            "BurstGroupID": 17
+        }"#);
+
+        let path = PathBuf::from("/photos/DSC_0001.NEF");
+        let data = convert_output(&path, entry, &mut HashMap::new()).unwrap();
+
+        assert_eq!(data.serial_number, "3002851");
+        assert_eq!(data.drive_mode, DriveMode::ContinuousHigh);
+        assert_eq!(data.focal_length, Some(500.0));
+        assert_eq!(data.aperture, Some(4.5));
+        assert_eq!(data.iso, Some(800));
+        assert_eq!(data.burst_group_id, Some(17));
+        assert_eq!(data.capture_time.nanosecond(), 250_000_000);
+        assert_eq!(data.utc_offset_secs, Some(7200));
+        assert_eq!(data.capture_time.hour(), 16); // 18:45 local at +02:00
+    }
+
+    #[test]
+    fn test_convert_output_missing_date_is_error() {
+        let entry = exiftool_entry(r#"{"SourceFile": "/photos/scan.jpg", "Make": "EPSON"}"#);
+        let path = PathBuf::from("/photos/scan.jpg");
+
+        let err = convert_output(&path, entry, &mut HashMap::new()).unwrap_err();
+
+        assert_eq!(err, ExifError::MissingDateTime { file_path: path });
+        assert_eq!(err.kind(), "MissingDateTime");
+    }
+
+    #[test]
+    fn test_convert_output_exiftool_error() {
+        let path = PathBuf::from("/photos/notes.txt.jpg");
+
+        let unknown = exiftool_entry(r#"{"SourceFile": "/photos/notes.txt.jpg", "Error": "Unknown file type"}"#);
+        let err = convert_output(&path, unknown, &mut HashMap::new()).unwrap_err();
+        assert_eq!(err.kind(), "Unsupported");
+
+        let corrupt = exiftool_entry(r#"{"SourceFile": "/photos/notes.txt.jpg", "Error": "File format error"}"#);
+        let err = convert_output(&path, corrupt, &mut HashMap::new()).unwrap_err();
+        assert_eq!(err.kind(), "Unreadable");
+        assert_eq!(err.file_path(), path.as_path());
+    }
+
+    /// A stand-in for exiftool that speaks the `-stay_open` protocol, hangs on
+    /// paths containing "hang", exits on paths containing "crash" and takes
+    /// 50ms over paths containing "slow".
+    #[cfg(unix)]
+    fn fake_exiftool(dir: &Path) -> OsString {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-exiftool");
+        std::fs::write(&script, r#"#!/bin/sh
+files=""
+while IFS= read -r line; do
+  case "$line" in
+    -execute)
+      printf '['
+      sep=''
+      for f in $files; do
+        case "$f" in
+          *hang*) sleep 5 ;;
+          *slow*) sleep 0.05 ;;
+          *crash*) exit 1 ;;
+        esac
+        printf '%s{"SourceFile":"%s","DateTimeOriginal":"2025:08:14 18:45:40","SerialNumber":"42"}' "$sep" "$f"
+        sep=','
+      done
+      printf ']\n{ready}\n'
+      files="" ;;
+    False) exit 0 ;;
+    -*|True) ;;
+    *) files="$files $line" ;;
+  esac
+done
+"#).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.into_os_string()
+    }
+
+    #[cfg(unix)]
+    fn card(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| PathBuf::from(format!("/card/{}", n))).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runner_isolates_crashing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runner = ExiftoolRunner::spawn(&fake_exiftool(dir.path()), Duration::from_secs(10), Duration::ZERO).unwrap();
+        let paths = card(&["a.nef", "b.nef", "c_crash.nef", "d.nef", "e.nef"]);
+
+        let results = runner.extract(&paths).unwrap();
+
+        assert_eq!(results.len(), 5);
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.kind(), "Crashed");
+        assert_eq!(err.file_path(), paths[2].as_path());
+        for i in [0, 1, 3, 4] {
+            assert_eq!(results[i].as_ref().unwrap().file_path, paths[i]);
+        }
+
+        // The respawned process keeps serving batches
+        assert!(runner.extract(&card(&["f.nef"])).unwrap()[0].is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runner_times_out_on_hung_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runner = ExiftoolRunner::spawn(&fake_exiftool(dir.path()), Duration::from_millis(300), Duration::ZERO).unwrap();
+        let paths = card(&["a.nef", "b_hang.nef", "c.nef"]);
+
+        let started = Instant::now();
+        let results = runner.extract(&paths).unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(results[1].as_ref().unwrap_err().kind(), "Crashed");
+        assert!(results[0].is_ok());
+        assert!(results[2].is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runner_deadline_scales_with_batch_size() {
+        let dir = tempfile::tempdir().unwrap();
+        // 20 slow files take ~1s: well past the floor, within the per-file budget
+        let mut runner = ExiftoolRunner::spawn(
+            &fake_exiftool(dir.path()),
+            Duration::from_millis(300),
+            Duration::from_millis(150),
+        ).unwrap();
+        let names: Vec<String> = (0..20).map(|i| format!("{:02}_slow.nef", i)).collect();
+        let paths = card(&names.iter().map(String::as_str).collect::<Vec<_>>());
+
+        assert_eq!(runner.batch_timeout(paths.len()), Duration::from_millis(3300));
+        let pid = runner.child.id();
+        let results = runner.extract(&paths).unwrap();
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        // Bisection would also recover the files; the batch must not have been killed at all
+        assert_eq!(runner.child.id(), pid, "healthy batch hit the watchdog");
+    }
+}
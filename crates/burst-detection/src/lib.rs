@@ -5,9 +5,27 @@
 //! along with AI-powered image quality metrics.
 
 pub mod exif;
+pub mod native_exif;
+pub mod exiftool_pool;
 pub mod burst;
+pub mod clock;
+pub mod pairing;
+pub mod timeline;
+pub mod editing;
 pub mod quality;
+pub mod ranking;
+pub mod scoring;
+pub mod similarity;
 
-pub use exif::{ExifData, DriveMode, ExiftoolRunner};
-pub use burst::{BurstGroup, BurstDetector, BurstResult, CameraInfo};
-pub use quality::{QualityScore, QualityAnalyzer};
\ No newline at end of file
+pub use exif::{ExifData, ExifError, ExifResult, ExifSource, DriveMode, ExiftoolRunner};
+pub use native_exif::NativeExifReader;
+pub use exiftool_pool::ExiftoolPool;
+pub use burst::{BurstGroup, BurstDetector, BurstSource, BurstDetectorConfig, BurstResult, CameraInfo};
+pub use clock::{apply_clock_offsets, clock_offset_from_pair};
+pub use pairing::pair_raw_jpeg;
+pub use timeline::{Timeline, TimelineEntry};
+pub use editing::BurstEdit;
+pub use quality::{QualityScore, QualityAnalyzer, QualityMetric, MetricInput, WeightProfile};
+pub use ranking::{BurstRanker, BurstRanking, RankedFrame};
+pub use scoring::{ScoringJob, ScoringItem, ScoringProgress, ScoredImage};
+pub use similarity::{group_similar, hash_images, MicroSource, PerceptualHash, SimilarityConfig, SimilarityGroup};
\ No newline at end of file
@@ -0,0 +1,942 @@
+//! Pure-Rust EXIF reader for JPEG and TIFF-based RAW files.
+//!
+//! Exists so import works on machines without Perl/exiftool. Parses the TIFF
+//! IFD chain directly: IFD0 (Make, Model, DNG serial) → Exif IFD (capture time,
+//! exposure, lens, body serial) → maker notes where the layout is simple enough
+//! to decode without exiftool's tables.
+//!
+//! Supported containers:
+//! - JPEG: TIFF structure inside the APP1 "Exif" segment
+//! - NEF, CR2, ARW, DNG: plain TIFF header ("II*\0" / "MM\0*")
+//! - ORF: TIFF with Olympus' "IIRO" / "IIRS" magic
+//!
+//! Maker-note coverage is deliberately narrow: Nikon ShootingMode and serial,
+//! Canon ContinuousDrive and serial. Everything else (Sony/Olympus drive mode,
+//! Nikon BurstGroupID, CR3/RAF containers) is left to exiftool — when a fallback
+//! `ExifSource` is configured, files whose drive mode can't be decoded here, and
+//! Nikon files (whose BurstGroupID is the primary burst signal), are re-extracted
+//! through it.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context, bail};
+use rayon::prelude::*;
+use crate::exif::{
+    ExifData, ExifError, ExifResult, ExifSource, DriveMode,
+    parse_capture_time, parse_drive_mode, parse_utc_offset, renumber_unknown_serials, resolve_serial,
+    wall_clock_to_utc,
+};
+
+// IFD0 tags
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DNG_CAMERA_SERIAL: u16 = 0xC62F;
+
+// Exif IFD tags
+const TAG_EXPOSURE_TIME: u16 = 0x829A;
+const TAG_F_NUMBER: u16 = 0x829D;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920A;
+const TAG_MAKER_NOTE: u16 = 0x927C;
+const TAG_OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const TAG_SUBSEC_TIME_ORIGINAL: u16 = 0x9291;
+const TAG_BODY_SERIAL: u16 = 0xA431;
+const TAG_LENS_MODEL: u16 = 0xA434;
+
+// Maker note tags
+const NIKON_SERIAL: u16 = 0x001D;
+const NIKON_SHOOTING_MODE: u16 = 0x0089;
+const CANON_CAMERA_SETTINGS: u16 = 0x0001;
+const CANON_SERIAL: u16 = 0x000C;
+
+/// Upper bounds that keep a corrupt offset or count from allocating gigabytes.
+const MAX_IFD_ENTRIES: u16 = 1024;
+const MAX_VALUE_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, b: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::Little => u16::from_le_bytes(b),
+            ByteOrder::Big => u16::from_be_bytes(b),
+        }
+    }
+
+    fn u32(self, b: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(b),
+            ByteOrder::Big => u32::from_be_bytes(b),
+        }
+    }
+}
+
+/// One 12-byte IFD entry. Values of 4 bytes or less are stored inline.
+#[derive(Debug, Clone)]
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+impl IfdEntry {
+    fn type_size(&self) -> usize {
+        match self.field_type {
+            1 | 2 | 6 | 7 => 1,  // BYTE, ASCII, SBYTE, UNDEFINED
+            3 | 8 => 2,          // SHORT, SSHORT
+            4 | 9 | 11 | 13 => 4, // LONG, SLONG, FLOAT, IFD
+            5 | 10 | 12 => 8,    // RATIONAL, SRATIONAL, DOUBLE
+            _ => 1,
+        }
+    }
+
+    fn byte_len(&self) -> usize {
+        self.type_size().saturating_mul(self.count as usize)
+    }
+}
+
+/// Random-access reader over a TIFF structure. All IFD offsets are relative to `base`.
+struct TiffReader<R> {
+    inner: R,
+    base: u64,
+    order: ByteOrder,
+}
+
+impl<R: Read + Seek> TiffReader<R> {
+    /// Parse the 8-byte TIFF header at `base`, returning the reader and the IFD0 offset.
+    fn new(mut inner: R, base: u64) -> Result<(Self, u32)> {
+        let mut header = [0u8; 8];
+        inner.seek(SeekFrom::Start(base))?;
+        inner.read_exact(&mut header).context("Truncated TIFF header")?;
+
+        let order = match &header[0..2] {
+            b"II" => ByteOrder::Little,
+            b"MM" => ByteOrder::Big,
+            _ => bail!("Not a TIFF header"),
+        };
+
+        let magic = order.u16([header[2], header[3]]);
+        // 42 = standard TIFF; 0x4F52 ("RO") and 0x5352 ("RS") = Olympus ORF
+        if !matches!(magic, 42 | 0x4F52 | 0x5352) {
+            bail!("Unsupported TIFF magic: {:#06x}", magic);
+        }
+
+        let ifd0 = order.u32([header[4], header[5], header[6], header[7]]);
+        Ok((Self { inner, base, order }, ifd0))
+    }
+
+    fn read_ifd(&mut self, offset: u32) -> Result<Vec<IfdEntry>> {
+        self.inner.seek(SeekFrom::Start(self.base + offset as u64))?;
+        let mut count_bytes = [0u8; 2];
+        self.inner.read_exact(&mut count_bytes).context("Truncated IFD")?;
+        let count = self.order.u16(count_bytes);
+        if count > MAX_IFD_ENTRIES {
+            bail!("IFD at {} claims {} entries", offset, count);
+        }
+
+        let mut raw = vec![0u8; count as usize * 12];
+        self.inner.read_exact(&mut raw).context("Truncated IFD entries")?;
+
+        Ok(raw.chunks_exact(12).map(|e| IfdEntry {
+            tag: self.order.u16([e[0], e[1]]),
+            field_type: self.order.u16([e[2], e[3]]),
+            count: self.order.u32([e[4], e[5], e[6], e[7]]),
+            value: [e[8], e[9], e[10], e[11]],
+        }).collect())
+    }
+
+    fn value_bytes(&mut self, entry: &IfdEntry) -> Result<Vec<u8>> {
+        let len = entry.byte_len();
+        if len <= 4 {
+            return Ok(entry.value[..len].to_vec());
+        }
+        if len > MAX_VALUE_BYTES {
+            bail!("Tag {:#06x} value too large ({} bytes)", entry.tag, len);
+        }
+        let offset = self.order.u32(entry.value);
+        self.inner.seek(SeekFrom::Start(self.base + offset as u64))?;
+        let mut buf = vec![0u8; len];
+        self.inner.read_exact(&mut buf).context("Truncated tag value")?;
+        Ok(buf)
+    }
+
+    /// Offset stored in the entry's value field (for pointers and out-of-line data).
+    fn offset_of(&self, entry: &IfdEntry) -> u32 {
+        self.order.u32(entry.value)
+    }
+
+    fn ascii(&mut self, entry: &IfdEntry) -> Option<String> {
+        let bytes = self.value_bytes(entry).ok()?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let s = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+        if s.is_empty() { None } else { Some(s) }
+    }
+
+    /// All SHORT/LONG values of an entry, widened to u32.
+    fn uints(&mut self, entry: &IfdEntry) -> Option<Vec<u32>> {
+        let bytes = self.value_bytes(entry).ok()?;
+        match entry.field_type {
+            3 => Some(bytes.chunks_exact(2).map(|c| self.order.u16([c[0], c[1]]) as u32).collect()),
+            4 => Some(bytes.chunks_exact(4).map(|c| self.order.u32([c[0], c[1], c[2], c[3]])).collect()),
+            _ => None,
+        }
+    }
+
+    fn uint(&mut self, entry: &IfdEntry) -> Option<u32> {
+        self.uints(entry)?.first().copied()
+    }
+
+    /// First RATIONAL value as (numerator, denominator).
+    fn rational(&mut self, entry: &IfdEntry) -> Option<(u32, u32)> {
+        if entry.field_type != 5 {
+            return None;
+        }
+        let b = self.value_bytes(entry).ok()?;
+        if b.len() < 8 {
+            return None;
+        }
+        Some((self.order.u32([b[0], b[1], b[2], b[3]]), self.order.u32([b[4], b[5], b[6], b[7]])))
+    }
+}
+
+/// Raw fields pulled from one file, before serial resolution and type normalization.
+#[derive(Debug, Default)]
+struct NativeFields {
+    make: Option<String>,
+    model: Option<String>,
+    serial_number: Option<String>,
+    maker_serial: Option<String>,
+    date_time_original: Option<String>,
+    subsec_time_original: Option<String>,
+    offset_time_original: Option<String>,
+    /// Drive mode as exiftool would print it, so `parse_drive_mode` applies unchanged
+    drive_mode_raw: Option<String>,
+    lens: Option<String>,
+    focal_length: Option<f64>,
+    aperture: Option<f64>,
+    shutter_speed: Option<String>,
+    iso: Option<u32>,
+}
+
+impl NativeFields {
+    /// Whether exiftool would add anything we care about for burst detection.
+    fn needs_fallback(&self) -> bool {
+        self.date_time_original.is_none()
+            || self.drive_mode_raw.is_none()
+            || self.make.as_deref().is_some_and(has_undecoded_burst_id)
+    }
+}
+
+/// Whether `make` records a burst group ID (and frame rate) in maker-note data
+/// this reader doesn't decode. Nikon Z bodies do: without exiftool they fall
+/// back to drive-mode inference.
+fn has_undecoded_burst_id(make: &str) -> bool {
+    make.to_uppercase().starts_with("NIKON")
+}
+
+/// Pure-Rust `ExifSource`, optionally backed by another source for files it
+/// can't fully decode.
+pub struct NativeExifReader {
+    fallback: Option<Box<dyn ExifSource + Send>>,
+}
+
+impl NativeExifReader {
+    /// Create a reader with no fallback — undecodable maker notes yield `DriveMode::Unknown`.
+    pub fn new() -> Self {
+        Self { fallback: None }
+    }
+
+    /// Create a reader that re-extracts files with undecodable maker notes (or
+    /// unsupported containers) through `fallback`, typically an `ExiftoolRunner`.
+    pub fn with_fallback(fallback: Box<dyn ExifSource + Send>) -> Self {
+        Self { fallback: Some(fallback) }
+    }
+
+    /// Whether a fallback source is configured.
+    pub fn has_fallback(&self) -> bool {
+        self.fallback.is_some()
+    }
+
+    /// Read the raw EXIF fields of a single file.
+    fn read_file(path: &Path) -> std::result::Result<NativeFields, ExifError> {
+        let unreadable = |reason: String| ExifError::Unreadable { file_path: path.to_path_buf(), reason };
+
+        let file = File::open(path).map_err(|e| unreadable(e.to_string()))?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 2];
+        reader.read_exact(&mut magic).map_err(|_| unreadable("file too short".to_string()))?;
+
+        let parsed = match &magic {
+            [0xFF, 0xD8] => find_jpeg_exif(&mut reader).and_then(|tiff| parse_tiff(Cursor::new(tiff), 0)),
+            b"II" | b"MM" => parse_tiff(reader, 0),
+            _ => {
+                return Err(ExifError::Unsupported {
+                    file_path: path.to_path_buf(),
+                    reason: "not a JPEG or TIFF-based file".to_string(),
+                });
+            }
+        };
+
+        parsed.map_err(|e| unreadable(format!("{:#}", e)))
+    }
+
+    /// Normalize raw fields into `ExifData`.
+    fn to_exif_data(
+        path: &Path,
+        fields: NativeFields,
+        make_model_map: &mut HashMap<String, usize>,
+    ) -> ExifResult {
+        let utc_offset_secs = fields.offset_time_original.as_deref().and_then(parse_utc_offset);
+        let capture_time = parse_capture_time(
+            fields.date_time_original.as_deref(),
+            fields.subsec_time_original.as_deref(),
+        ).map(|wall| wall_clock_to_utc(wall, utc_offset_secs))
+            .ok_or_else(|| ExifError::MissingDateTime { file_path: path.to_path_buf() })?;
+
+        let serial_number = resolve_serial(
+            fields.serial_number.or(fields.maker_serial),
+            None,
+            fields.make.as_deref(),
+            fields.model.as_deref(),
+            make_model_map,
+        );
+
+        let drive_mode = match fields.drive_mode_raw.as_deref() {
+            Some(raw) => parse_drive_mode(raw, ""),
+            None => DriveMode::Unknown,
+        };
+
+        Ok(ExifData {
+            serial_number,
+            drive_mode,
+            capture_time,
+            utc_offset_secs,
+            clock_offset_ms: 0,
+            make: fields.make,
+            model: fields.model,
+            lens: fields.lens,
+            focal_length: fields.focal_length,
+            aperture: fields.aperture,
+            shutter_speed: fields.shutter_speed,
+            iso: fields.iso,
+            file_path: path.to_path_buf(),
+            burst_group_id: None,
+            high_frame_rate: None,
+            sidecar_path: None,
+        })
+    }
+}
+
+impl Default for NativeExifReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExifSource for NativeExifReader {
+    fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>> {
+        let parsed: Vec<std::result::Result<NativeFields, ExifError>> = paths.par_iter()
+            .map(|path| Self::read_file(path))
+            .collect();
+
+        // Ask the fallback about anything we couldn't fully decode
+        let mut fallback_results: HashMap<PathBuf, ExifResult> = HashMap::new();
+        if let Some(fallback) = self.fallback.as_mut() {
+            let retry: Vec<PathBuf> = paths.iter().zip(&parsed)
+                .filter(|(_, fields)| fields.as_ref().map_or(true, NativeFields::needs_fallback))
+                .map(|(path, _)| path.clone())
+                .collect();
+
+            if !retry.is_empty() {
+                match fallback.extract(&retry) {
+                    Ok(results) => fallback_results.extend(retry.into_iter().zip(results)),
+                    Err(e) => eprintln!("Fallback EXIF extraction failed, using native results: {}", e),
+                }
+            }
+        }
+
+        let mut make_model_map: HashMap<String, usize> = HashMap::new();
+
+        let mut results: Vec<ExifResult> = paths.iter().zip(parsed).map(|(path, fields)| {
+            match (fallback_results.remove(path), fields) {
+                (Some(Ok(data)), _) => Ok(data),
+                (_, Ok(fields)) => Self::to_exif_data(path, fields, &mut make_model_map),
+                // exiftool understands more formats, so its error is the more specific one
+                (Some(Err(fallback_err)), Err(_)) => Err(fallback_err),
+                (None, Err(native_err)) => Err(native_err),
+            }
+        }).collect();
+
+        // Native and fallback results each numbered serial-less bodies from 1
+        renumber_unknown_serials(&mut results);
+        Ok(results)
+    }
+}
+
+/// Walk JPEG markers until the APP1 "Exif" segment and return its TIFF payload.
+fn find_jpeg_exif<R: Read + Seek>(reader: &mut R) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(2))?;
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker).context("No EXIF segment in JPEG")?;
+        if marker[0] != 0xFF {
+            bail!("Malformed JPEG marker");
+        }
+        // Start of scan / end of image: metadata segments are all before this
+        if marker[1] == 0xDA || marker[1] == 0xD9 {
+            bail!("No EXIF segment in JPEG");
+        }
+
+        let mut len_bytes = [0u8; 2];
+        reader.read_exact(&mut len_bytes)?;
+        let len = u16::from_be_bytes(len_bytes) as usize;
+        if len < 2 {
+            bail!("Malformed JPEG segment length");
+        }
+
+        let mut segment = vec![0u8; len - 2];
+        reader.read_exact(&mut segment).context("Truncated JPEG segment")?;
+
+        if marker[1] == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Ok(segment.split_off(6));
+        }
+    }
+}
+
+/// Parse the TIFF structure at `base` into raw fields.
+fn parse_tiff<R: Read + Seek>(inner: R, base: u64) -> Result<NativeFields> {
+    let (mut tiff, ifd0_offset) = TiffReader::new(inner, base)?;
+    let mut fields = NativeFields::default();
+
+    let ifd0 = tiff.read_ifd(ifd0_offset)?;
+    let mut exif_offset = None;
+    for entry in &ifd0 {
+        match entry.tag {
+            TAG_MAKE => fields.make = tiff.ascii(entry),
+            TAG_MODEL => fields.model = tiff.ascii(entry),
+            TAG_DNG_CAMERA_SERIAL => fields.serial_number = tiff.ascii(entry),
+            TAG_EXIF_IFD => exif_offset = Some(tiff.offset_of(entry)),
+            _ => {}
+        }
+    }
+
+    let exif_offset = match exif_offset {
+        Some(offset) => offset,
+        None => return Ok(fields),
+    };
+
+    let mut maker_note = None;
+    for entry in &tiff.read_ifd(exif_offset)? {
+        match entry.tag {
+            TAG_DATE_TIME_ORIGINAL => fields.date_time_original = tiff.ascii(entry),
+            TAG_SUBSEC_TIME_ORIGINAL => fields.subsec_time_original = tiff.ascii(entry),
+            TAG_OFFSET_TIME_ORIGINAL => fields.offset_time_original = tiff.ascii(entry),
+            TAG_BODY_SERIAL => {
+                if let Some(serial) = tiff.ascii(entry) {
+                    fields.serial_number = Some(serial);
+                }
+            }
+            TAG_LENS_MODEL => fields.lens = tiff.ascii(entry),
+            TAG_ISO => fields.iso = tiff.uint(entry),
+            TAG_FOCAL_LENGTH => fields.focal_length = tiff.rational(entry).and_then(rational_to_f64),
+            TAG_F_NUMBER => fields.aperture = tiff.rational(entry).and_then(rational_to_f64),
+            TAG_EXPOSURE_TIME => {
+                fields.shutter_speed = tiff.rational(entry).and_then(|(n, d)| format_exposure_time(n, d));
+            }
+            TAG_MAKER_NOTE => maker_note = Some(entry.clone()),
+            _ => {}
+        }
+    }
+
+    if let Some(entry) = maker_note {
+        let make = fields.make.clone().unwrap_or_default().to_uppercase();
+        // Maker notes are best-effort: a layout we misjudge just leaves drive mode unknown
+        if make.starts_with("NIKON") {
+            let _ = parse_nikon_maker_note(&mut tiff, &entry, &mut fields);
+        } else if make.starts_with("CANON") {
+            let _ = parse_canon_maker_note(&mut tiff, &entry, &mut fields);
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Nikon type-3 maker note: "Nikon\0" + version, then a self-contained TIFF
+/// structure whose offsets are relative to its own header at byte 10.
+fn parse_nikon_maker_note<R: Read + Seek>(
+    tiff: &mut TiffReader<R>,
+    entry: &IfdEntry,
+    fields: &mut NativeFields,
+) -> Result<()> {
+    let note = tiff.value_bytes(entry)?;
+    if !note.starts_with(b"Nikon\0") || note.len() < 18 {
+        bail!("Unsupported Nikon maker note layout");
+    }
+
+    let (mut inner, ifd_offset) = TiffReader::new(Cursor::new(note), 10)?;
+    for e in &inner.read_ifd(ifd_offset)? {
+        match e.tag {
+            NIKON_SERIAL => fields.maker_serial = inner.ascii(e),
+            NIKON_SHOOTING_MODE => {
+                // Bit 0 = continuous; exiftool prints "Single-Frame" when no bits are set
+                fields.drive_mode_raw = inner.uint(e).map(|mode| {
+                    if mode & 1 != 0 { "Continuous" } else { "Single-Frame" }.to_string()
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Canon maker note: a bare IFD whose offsets are relative to the enclosing TIFF header.
+fn parse_canon_maker_note<R: Read + Seek>(
+    tiff: &mut TiffReader<R>,
+    entry: &IfdEntry,
+    fields: &mut NativeFields,
+) -> Result<()> {
+    let offset = tiff.offset_of(entry);
+    for e in &tiff.read_ifd(offset)? {
+        match e.tag {
+            CANON_SERIAL => fields.maker_serial = tiff.uint(e).map(|n| n.to_string()),
+            CANON_CAMERA_SETTINGS => {
+                // CameraSettings[5] = ContinuousDrive (index 0 is the array byte length)
+                let drive = tiff.uints(e).and_then(|settings| settings.get(5).copied());
+                fields.drive_mode_raw = drive.and_then(canon_continuous_drive).map(str::to_string);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Canon ContinuousDrive values, spelled the way exiftool prints them.
+fn canon_continuous_drive(value: u32) -> Option<&'static str> {
+    match value {
+        0 => Some("Single"),
+        1 => Some("Continuous"),
+        3 => Some("Continuous, Speed Priority"),
+        4 => Some("Continuous, Low"),
+        5 => Some("Continuous, High"),
+        6 => Some("Silent Single"),
+        8 => Some("Continuous, High+"),
+        9 => Some("Single, Silent"),
+        10 => Some("Continuous, Silent"),
+        _ => None, // 2 = Movie, plus anything newer than this table
+    }
+}
+
+fn rational_to_f64((num, den): (u32, u32)) -> Option<f64> {
+    if den == 0 { None } else { Some(num as f64 / den as f64) }
+}
+
+/// Format ExposureTime the way exiftool's ShutterSpeed does: "1/3200", "0.3", "2".
+fn format_exposure_time(num: u32, den: u32) -> Option<String> {
+    let secs = rational_to_f64((num, den))?;
+    if secs <= 0.0 {
+        return None;
+    }
+    if secs < 0.25001 {
+        return Some(format!("1/{}", (1.0 / secs).round() as u64));
+    }
+    let s = format!("{:.1}", secs);
+    Some(s.strip_suffix(".0").map(str::to_string).unwrap_or(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Tag value for the synthetic TIFF builder.
+    enum Val {
+        Ascii(&'static str),
+        Short(Vec<u16>),
+        Long(u32),
+        Rational(u32, u32),
+        Undefined(Vec<u8>),
+    }
+
+    impl Val {
+        fn encode(&self, order: ByteOrder) -> (u16, u32, Vec<u8>) {
+            let u16b = |v: u16| if order == ByteOrder::Little { v.to_le_bytes() } else { v.to_be_bytes() };
+            let u32b = |v: u32| if order == ByteOrder::Little { v.to_le_bytes() } else { v.to_be_bytes() };
+            match self {
+                Val::Ascii(s) => {
+                    let mut b = s.as_bytes().to_vec();
+                    b.push(0);
+                    (2, b.len() as u32, b)
+                }
+                Val::Short(v) => (3, v.len() as u32, v.iter().flat_map(|x| u16b(*x)).collect()),
+                Val::Long(v) => (4, 1, u32b(*v).to_vec()),
+                Val::Rational(n, d) => (5, 1, [u32b(*n), u32b(*d)].concat()),
+                Val::Undefined(b) => (7, b.len() as u32, b.clone()),
+            }
+        }
+    }
+
+    /// Minimal TIFF writer: IFDs are appended to `buf`, out-of-line values follow
+    /// each IFD, and all offsets are relative to the start of `buf`.
+    struct TiffBuilder {
+        buf: Vec<u8>,
+        order: ByteOrder,
+    }
+
+    impl TiffBuilder {
+        fn new(order: ByteOrder, magic: u16) -> Self {
+            let mut b = Self { buf: Vec::new(), order };
+            b.buf.extend_from_slice(if order == ByteOrder::Little { b"II" } else { b"MM" });
+            b.put_u16(magic);
+            b.put_u32(8);
+            b
+        }
+
+        fn put_u16(&mut self, v: u16) {
+            let bytes = if self.order == ByteOrder::Little { v.to_le_bytes() } else { v.to_be_bytes() };
+            self.buf.extend_from_slice(&bytes);
+        }
+
+        fn put_u32(&mut self, v: u32) {
+            let bytes = if self.order == ByteOrder::Little { v.to_le_bytes() } else { v.to_be_bytes() };
+            self.buf.extend_from_slice(&bytes);
+        }
+
+        fn patch_u32(&mut self, pos: usize, v: u32) {
+            let bytes = if self.order == ByteOrder::Little { v.to_le_bytes() } else { v.to_be_bytes() };
+            self.buf[pos..pos + 4].copy_from_slice(&bytes);
+        }
+
+        /// Write an IFD at the current end of the buffer. Returns the position of
+        /// each entry's value field so pointers can be patched afterwards.
+        fn write_ifd(&mut self, entries: &[(u16, Val)]) -> HashMap<u16, usize> {
+            let start = self.buf.len();
+            let mut data_pos = start + 2 + entries.len() * 12 + 4;
+            let mut value_fields = HashMap::new();
+            let mut out_of_line = Vec::new();
+
+            self.put_u16(entries.len() as u16);
+            for (tag, val) in entries {
+                let (ty, count, bytes) = val.encode(self.order);
+                self.put_u16(*tag);
+                self.put_u16(ty);
+                self.put_u32(count);
+                value_fields.insert(*tag, self.buf.len());
+                if bytes.len() <= 4 {
+                    let mut inline = bytes.clone();
+                    inline.resize(4, 0);
+                    self.buf.extend_from_slice(&inline);
+                } else {
+                    self.put_u32(data_pos as u32);
+                    data_pos += bytes.len() + bytes.len() % 2;
+                    out_of_line.push(bytes);
+                }
+            }
+            self.put_u32(0); // next IFD
+            for mut bytes in out_of_line {
+                if bytes.len() % 2 == 1 {
+                    bytes.push(0);
+                }
+                self.buf.extend_from_slice(&bytes);
+            }
+            value_fields
+        }
+    }
+
+    /// Build a TIFF with IFD0 (Make, Model, Exif pointer) followed by the Exif IFD.
+    fn build_tiff(order: ByteOrder, magic: u16, make: &'static str, exif: Vec<(u16, Val)>) -> Vec<u8> {
+        let mut b = TiffBuilder::new(order, magic);
+        let ifd0 = b.write_ifd(&[
+            (TAG_MAKE, Val::Ascii(make)),
+            (TAG_MODEL, Val::Ascii("Test Body")),
+            (TAG_EXIF_IFD, Val::Long(0)),
+        ]);
+        let exif_pos = b.buf.len() as u32;
+        b.patch_u32(ifd0[&TAG_EXIF_IFD], exif_pos);
+        b.write_ifd(&exif);
+        b.buf
+    }
+
+    fn standard_exif() -> Vec<(u16, Val)> {
+        vec![
+            (TAG_EXPOSURE_TIME, Val::Rational(1, 3200)),
+            (TAG_F_NUMBER, Val::Rational(45, 10)),
+            (TAG_ISO, Val::Short(vec![800])),
+            (TAG_DATE_TIME_ORIGINAL, Val::Ascii("2025:08:14 18:45:40")),
+            (TAG_FOCAL_LENGTH, Val::Rational(500, 1)),
+            (TAG_SUBSEC_TIME_ORIGINAL, Val::Ascii("25")),
+            (TAG_BODY_SERIAL, Val::Ascii("3002851")),
+            (TAG_LENS_MODEL, Val::Ascii("VR 500mm f/4E")),
+        ]
+    }
+
+    fn nikon_maker_note(shooting_mode: u16) -> Vec<u8> {
+        let mut inner = TiffBuilder::new(ByteOrder::Big, 42);
+        inner.write_ifd(&[
+            (NIKON_SERIAL, Val::Ascii("7654321")),
+            (NIKON_SHOOTING_MODE, Val::Short(vec![shooting_mode])),
+        ]);
+        let mut note = b"Nikon\0\x02\x10\0\0".to_vec();
+        note.extend(inner.buf);
+        note
+    }
+
+    fn write_fixture(dir: &Path, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
+
+    fn wrap_in_jpeg(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        // An APP0 segment first, as real JFIF files have
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x07]);
+        jpeg.extend_from_slice(b"JFIF\0");
+        let len = (tiff.len() + 8) as u16;
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&len.to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    /// Fake fallback that records which paths it was asked about.
+    struct RecordingSource {
+        seen: Arc<Mutex<Vec<PathBuf>>>,
+    }
+
+    impl ExifSource for RecordingSource {
+        fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>> {
+            self.seen.lock().unwrap().extend(paths.iter().cloned());
+            Ok(paths.iter().map(|p| {
+                let mut data = ExifData::new(p.clone(), "fallback".to_string(), DriveMode::ContinuousLow, chrono::Utc::now());
+                data.burst_group_id = Some(42);
+                Ok(data)
+            }).collect())
+        }
+    }
+
+    #[test]
+    fn test_nef_little_endian_with_nikon_maker_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut exif = standard_exif();
+        exif.push((TAG_OFFSET_TIME_ORIGINAL, Val::Ascii("-04:00")));
+        exif.push((TAG_MAKER_NOTE, Val::Undefined(nikon_maker_note(1))));
+        let path = write_fixture(dir.path(), "DSC_0001.NEF", &build_tiff(ByteOrder::Little, 42, "NIKON CORPORATION", exif));
+
+        let results = NativeExifReader::new().extract(std::slice::from_ref(&path)).unwrap();
+        let data = results[0].as_ref().unwrap();
+
+        assert_eq!(data.file_path, path);
+        assert_eq!(data.serial_number, "3002851"); // BodySerialNumber wins over maker note
+        assert_eq!(data.drive_mode, DriveMode::ContinuousHigh);
+        assert_eq!(data.make.as_deref(), Some("NIKON CORPORATION"));
+        assert_eq!(data.lens.as_deref(), Some("VR 500mm f/4E"));
+        assert_eq!(data.focal_length, Some(500.0));
+        assert_eq!(data.aperture, Some(4.5));
+        assert_eq!(data.shutter_speed.as_deref(), Some("1/3200"));
+        assert_eq!(data.iso, Some(800));
+        assert_eq!(data.capture_time.year(), 2025);
+        assert_eq!(data.capture_time.second(), 40);
+        assert_eq!(data.capture_time.nanosecond(), 250_000_000);
+        assert_eq!(data.utc_offset_secs, Some(-4 * 3600));
+        assert_eq!(data.capture_time.hour(), 22); // 18:45 local at -04:00
+    }
+
+    #[test]
+    fn test_big_endian_nikon_single_frame_uses_maker_serial() {
+        let dir = tempfile::tempdir().unwrap();
+        let exif = vec![
+            (TAG_DATE_TIME_ORIGINAL, Val::Ascii("2025:08:14 18:45:40")),
+            (TAG_MAKER_NOTE, Val::Undefined(nikon_maker_note(0))),
+        ];
+        let path = write_fixture(dir.path(), "DSC_0002.NEF", &build_tiff(ByteOrder::Big, 42, "NIKON CORPORATION", exif));
+
+        let results = NativeExifReader::new().extract(&[path]).unwrap();
+        let data = results[0].as_ref().unwrap();
+
+        assert_eq!(data.serial_number, "7654321");
+        assert_eq!(data.drive_mode, DriveMode::Single);
+    }
+
+    /// A CR2 with a Canon maker note. Its offsets are relative to the main
+    /// TIFF header, so it has to be written into the same buffer.
+    fn canon_cr2(serial: Option<u32>) -> Vec<u8> {
+        let mut b = TiffBuilder::new(ByteOrder::Little, 42);
+        let ifd0 = b.write_ifd(&[
+            (TAG_MAKE, Val::Ascii("Canon")),
+            (TAG_MODEL, Val::Ascii("Canon EOS R5")),
+            (TAG_EXIF_IFD, Val::Long(0)),
+        ]);
+        let exif_pos = b.buf.len() as u32;
+        b.patch_u32(ifd0[&TAG_EXIF_IFD], exif_pos);
+        let exif = b.write_ifd(&[
+            (TAG_DATE_TIME_ORIGINAL, Val::Ascii("2025:08:14 18:45:40")),
+            (TAG_MAKER_NOTE, Val::Long(0)),
+        ]);
+        let note_pos = b.buf.len() as u32;
+        b.patch_u32(exif[&TAG_MAKER_NOTE], note_pos);
+        let mut note = vec![(CANON_CAMERA_SETTINGS, Val::Short(vec![12, 0, 0, 0, 0, 4]))];
+        if let Some(serial) = serial {
+            note.push((CANON_SERIAL, Val::Long(serial)));
+        }
+        b.write_ifd(&note);
+        b.buf
+    }
+
+    #[test]
+    fn test_cr2_canon_continuous_drive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), "IMG_0001.CR2", &canon_cr2(Some(123456)));
+        let results = NativeExifReader::new().extract(&[path]).unwrap();
+        let data = results[0].as_ref().unwrap();
+
+        assert_eq!(data.drive_mode, DriveMode::ContinuousLow);
+        assert_eq!(data.serial_number, "123456");
+        assert_eq!(data.model.as_deref(), Some("Canon EOS R5"));
+    }
+
+    #[test]
+    fn test_orf_magic_and_dng_serial() {
+        let dir = tempfile::tempdir().unwrap();
+        let orf = write_fixture(dir.path(), "P1010001.ORF", &build_tiff(ByteOrder::Little, 0x4F52, "OM Digital Solutions", standard_exif()));
+
+        let mut b = TiffBuilder::new(ByteOrder::Little, 42);
+        let ifd0 = b.write_ifd(&[
+            (TAG_MAKE, Val::Ascii("Leica")),
+            (TAG_EXIF_IFD, Val::Long(0)),
+            (TAG_DNG_CAMERA_SERIAL, Val::Ascii("L998877")),
+        ]);
+        let exif_pos = b.buf.len() as u32;
+        b.patch_u32(ifd0[&TAG_EXIF_IFD], exif_pos);
+        b.write_ifd(&[(TAG_DATE_TIME_ORIGINAL, Val::Ascii("2025:08:14 18:45:40"))]);
+        let dng = write_fixture(dir.path(), "L1000001.DNG", &b.buf);
+
+        let results = NativeExifReader::new().extract(&[orf, dng]).unwrap();
+        let orf = results[0].as_ref().unwrap();
+        let dng = results[1].as_ref().unwrap();
+
+        assert_eq!(orf.make.as_deref(), Some("OM Digital Solutions"));
+        assert_eq!(orf.drive_mode, DriveMode::Unknown);
+        assert_eq!(dng.serial_number, "L998877");
+    }
+
+    #[test]
+    fn test_jpeg_exif_segment() {
+        let dir = tempfile::tempdir().unwrap();
+        let tiff = build_tiff(ByteOrder::Big, 42, "SONY", standard_exif());
+        let path = write_fixture(dir.path(), "DSC00001.JPG", &wrap_in_jpeg(&tiff));
+
+        let results = NativeExifReader::new().extract(&[path]).unwrap();
+        let data = results[0].as_ref().unwrap();
+
+        assert_eq!(data.make.as_deref(), Some("SONY"));
+        assert_eq!(data.serial_number, "3002851");
+        assert_eq!(data.iso, Some(800));
+    }
+
+    #[test]
+    fn test_fallback_only_for_undecoded_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut nikon_exif = standard_exif();
+        nikon_exif.push((TAG_MAKER_NOTE, Val::Undefined(nikon_maker_note(1))));
+        let nikon = write_fixture(dir.path(), "DSC_0001.NEF", &build_tiff(ByteOrder::Little, 42, "NIKON CORPORATION", nikon_exif));
+        let sony = write_fixture(dir.path(), "DSC00001.ARW", &build_tiff(ByteOrder::Little, 42, "SONY", standard_exif()));
+        let cr3 = write_fixture(dir.path(), "IMG_0001.CR3", b"\0\0\0\x18ftypcrx ");
+        let canon = write_fixture(dir.path(), "IMG_0001.CR2", &canon_cr2(Some(123456)));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut reader = NativeExifReader::with_fallback(Box::new(RecordingSource { seen: seen.clone() }));
+        let results: Vec<ExifData> = reader.extract(&[nikon.clone(), sony.clone(), cr3.clone(), canon.clone()])
+            .unwrap()
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+
+        // Nikon's drive mode decodes natively, but its BurstGroupID only comes from exiftool
+        assert_eq!(*seen.lock().unwrap(), vec![nikon.clone(), sony.clone(), cr3.clone()]);
+        // Results stay in input order
+        assert_eq!(results.iter().map(|d| d.file_path.clone()).collect::<Vec<_>>(), vec![nikon, sony, cr3, canon]);
+        assert_eq!(results[0].burst_group_id, Some(42));
+        assert_eq!(results[1].burst_group_id, Some(42));
+        assert_eq!(results[2].drive_mode, DriveMode::ContinuousLow);
+        assert_eq!(results[3].serial_number, "123456");
+        assert_eq!(results[3].drive_mode, DriveMode::ContinuousLow);
+    }
+
+    /// Fake fallback that, like a fresh exiftool batch, numbers serial-less bodies from 1.
+    struct PlaceholderSource;
+
+    impl ExifSource for PlaceholderSource {
+        fn extract(&mut self, paths: &[PathBuf]) -> Result<Vec<ExifResult>> {
+            Ok(paths.iter().enumerate().map(|(i, p)| {
+                let serial = format!("unknown_Canon_Canon EOS R5_{}", i + 1);
+                Ok(ExifData::new(p.clone(), serial, DriveMode::Single, chrono::Utc::now()))
+            }).collect())
+        }
+    }
+
+    #[test]
+    fn test_unknown_serials_unique_across_native_and_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let native = write_fixture(dir.path(), "IMG_0001.CR2", &canon_cr2(None));
+        let fallback = write_fixture(dir.path(), "IMG_0002.CR3", b"\0\0\0\x18ftypcrx ");
+
+        let mut reader = NativeExifReader::with_fallback(Box::new(PlaceholderSource));
+        let serials: Vec<String> = reader.extract(&[native, fallback])
+            .unwrap()
+            .into_iter()
+            .map(|r| r.unwrap().serial_number)
+            .collect();
+
+        assert_eq!(serials, vec!["unknown_Canon_Canon EOS R5_1", "unknown_Canon_Canon EOS R5_2"]);
+    }
+
+    #[test]
+    fn test_per_file_errors_without_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = write_fixture(dir.path(), "DSC00001.ARW", &build_tiff(ByteOrder::Little, 42, "SONY", standard_exif()));
+        let cr3 = write_fixture(dir.path(), "IMG_0001.CR3", b"\0\0\0\x18ftypcrx ");
+        let no_date = write_fixture(dir.path(), "scan.tif", &build_tiff(ByteOrder::Little, 42, "EPSON", vec![]));
+        let truncated = write_fixture(dir.path(), "DSC00002.ARW", b"II*\0\x08\0\0\0\x05\0");
+        let missing = dir.path().join("gone.NEF");
+
+        let paths = [good, cr3.clone(), no_date.clone(), truncated.clone(), missing.clone()];
+        let results = NativeExifReader::new().extract(&paths).unwrap();
+
+        assert!(results[0].is_ok());
+        let kinds: Vec<_> = results[1..].iter().map(|r| r.as_ref().unwrap_err().kind()).collect();
+        assert_eq!(kinds, vec!["Unsupported", "MissingDateTime", "Unreadable", "Unreadable"]);
+        let failed: Vec<_> = results[1..].iter().map(|r| r.as_ref().unwrap_err().file_path().to_path_buf()).collect();
+        assert_eq!(failed, vec![cr3, no_date, truncated, missing]);
+    }
+
+    #[test]
+    fn test_corrupt_ifd_count_rejected() {
+        let mut bytes = b"II*\0\x08\0\0\0".to_vec();
+        bytes.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        let (mut tiff, ifd0) = TiffReader::new(Cursor::new(bytes), 0).unwrap();
+        assert!(tiff.read_ifd(ifd0).is_err());
+    }
+
+    #[test]
+    fn test_format_exposure_time() {
+        assert_eq!(format_exposure_time(1, 3200).as_deref(), Some("1/3200"));
+        assert_eq!(format_exposure_time(10, 600).as_deref(), Some("1/60"));
+        assert_eq!(format_exposure_time(3, 10).as_deref(), Some("0.3"));
+        assert_eq!(format_exposure_time(2, 1).as_deref(), Some("2"));
+        assert_eq!(format_exposure_time(1, 0), None);
+    }
+}
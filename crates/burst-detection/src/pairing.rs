@@ -0,0 +1,148 @@
+//! RAW+JPEG pair detection.
+//!
+//! Bodies set to RAW+JPEG write two files per frame — often to different cards
+//! (`/CARD1/DSC_0001.NEF`, `/CARD2/DSC_0001.JPG`). Treated separately, every
+//! frame shows up twice in the grid, gets culled twice, and doubles burst frame
+//! counts. Pairing folds the JPEG into the RAW's `ExifData` as a sidecar so the
+//! frame is one logical image from detection onwards.
+//!
+//! A JPEG pairs with a RAW when all three match:
+//! - file stem (case-insensitive; directories are ignored because of dual cards)
+//! - camera serial (two bodies can both be on DSC_0001)
+//! - capture time within `PAIR_TOLERANCE_MS` (file counters wrap at 9999, and
+//!   some bodies drop SubSecTimeOriginal from the JPEG)
+//!
+//! The RAW is always the primary; an unpaired JPEG stays a standalone image.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::exif::ExifData;
+
+/// Max capture-time difference between the two halves of a pair.
+pub const PAIR_TOLERANCE_MS: i64 = 1000;
+
+const JPEG_EXTENSIONS: &[&str] = &["jpg", "jpeg"];
+
+fn is_jpeg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| JPEG_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+fn pair_key(image: &ExifData) -> Option<(String, String)> {
+    let stem = image.file_path.file_stem()?.to_str()?.to_lowercase();
+    Some((image.serial_number.clone(), stem))
+}
+
+/// Fold JPEGs into their matching RAW as `sidecar_path`.
+///
+/// Returns the input minus the paired JPEGs, otherwise in input order.
+pub fn pair_raw_jpeg(images: Vec<ExifData>) -> Vec<ExifData> {
+    let mut slots: Vec<Option<ExifData>> = images.into_iter().map(Some).collect();
+
+    // (serial, stem) -> indices of RAW candidates
+    let mut raws: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for (i, image) in slots.iter().enumerate() {
+        let image = image.as_ref().unwrap();
+        if !is_jpeg(&image.file_path) {
+            if let Some(key) = pair_key(image) {
+                raws.entry(key).or_default().push(i);
+            }
+        }
+    }
+
+    for j in 0..slots.len() {
+        let jpeg = slots[j].as_ref().unwrap();
+        if !is_jpeg(&jpeg.file_path) {
+            continue;
+        }
+        let Some(candidates) = pair_key(jpeg).and_then(|key| raws.get(&key)) else {
+            continue;
+        };
+
+        let best = candidates.iter()
+            .copied()
+            .filter(|&i| slots[i].as_ref().is_some_and(|raw| raw.sidecar_path.is_none()))
+            .map(|i| {
+                let raw = slots[i].as_ref().unwrap();
+                let gap = (raw.capture_time - jpeg.capture_time).num_milliseconds().abs();
+                (gap, i)
+            })
+            .filter(|&(gap, _)| gap <= PAIR_TOLERANCE_MS)
+            .min();
+
+        if let Some((_, i)) = best {
+            let jpeg = slots[j].take().unwrap();
+            slots[i].as_mut().unwrap().sidecar_path = Some(jpeg.file_path);
+        }
+    }
+
+    slots.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exif::DriveMode;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::path::PathBuf;
+
+    fn image(path: &str, serial: &str, ms: i64) -> ExifData {
+        let base = Utc.with_ymd_and_hms(2025, 6, 21, 15, 0, 0).unwrap();
+        ExifData::new(PathBuf::from(path), serial.to_string(), DriveMode::Single, base + Duration::milliseconds(ms))
+    }
+
+    #[test]
+    fn test_pairs_across_cards() {
+        let paired = pair_raw_jpeg(vec![
+            image("/CARD1/DSC_0001.NEF", "A", 0),
+            image("/CARD1/DSC_0002.NEF", "A", 500),
+            image("/CARD2/dsc_0001.jpg", "A", 0),
+            image("/CARD2/DSC_0002.JPG", "A", 500),
+        ]);
+
+        assert_eq!(paired.len(), 2);
+        assert_eq!(paired[0].file_path, PathBuf::from("/CARD1/DSC_0001.NEF"));
+        assert_eq!(paired[0].sidecar_path, Some(PathBuf::from("/CARD2/dsc_0001.jpg")));
+        assert_eq!(paired[1].sidecar_path, Some(PathBuf::from("/CARD2/DSC_0002.JPG")));
+    }
+
+    #[test]
+    fn test_different_serial_not_paired() {
+        let paired = pair_raw_jpeg(vec![
+            image("/a/DSC_0001.NEF", "A", 0),
+            image("/b/DSC_0001.JPG", "B", 0),
+        ]);
+
+        assert_eq!(paired.len(), 2);
+        assert!(paired.iter().all(|img| img.sidecar_path.is_none()));
+    }
+
+    #[test]
+    fn test_wrapped_counter_pairs_by_time() {
+        // Counter wrapped: two DSC_0001 frames hours apart on the same body
+        let paired = pair_raw_jpeg(vec![
+            image("/CARD1/DSC_0001.NEF", "A", 0),
+            image("/CARD1/100/DSC_0001.NEF", "A", 3 * 3_600_000),
+            image("/CARD2/100/DSC_0001.JPG", "A", 3 * 3_600_000 + 40),
+            image("/CARD2/DSC_0001.JPG", "A", 0),
+        ]);
+
+        assert_eq!(paired.len(), 2);
+        assert_eq!(paired[0].sidecar_path, Some(PathBuf::from("/CARD2/DSC_0001.JPG")));
+        assert_eq!(paired[1].sidecar_path, Some(PathBuf::from("/CARD2/100/DSC_0001.JPG")));
+    }
+
+    #[test]
+    fn test_unmatched_jpeg_kept() {
+        let paired = pair_raw_jpeg(vec![
+            image("/a/DSC_0001.NEF", "A", 0),
+            image("/a/DSC_0001.JPG", "A", 5_000), // same stem, too far apart
+            image("/a/DSC_0002.JPG", "A", 6_000),
+        ]);
+
+        assert_eq!(paired.len(), 3);
+        assert!(paired.iter().all(|img| img.sidecar_path.is_none()));
+    }
+}
@@ -2,12 +2,33 @@
 //! 
 //! This module provides AI-powered quality scoring for images,
 //! focusing on photography-specific metrics that matter to professionals.
+//!
+//! Scoring runs on the Preview-tier JPEG (1600px long edge), not the RAW:
+//! decoding a 45MP NEF per frame would make scoring a 2,000-frame burst
+//! session take longer than culling it by hand. The analyzer gets previews
+//! through `PreviewSource`, which thumbnail-cache implements — this crate
+//! can't depend on thumbnail-cache because thumbnail-cache depends on it.
 
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use image::{DynamicImage, GrayImage};
+use std::collections::BTreeMap;
+use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+use crate::exif::ExifData;
+
+/// Names of the built-in metrics, as they appear in `QualityScore::metrics`
+/// and in weight profiles.
+pub mod metric_names {
+    pub const SHARPNESS: &str = "sharpness";
+    pub const EXPOSURE: &str = "exposure";
+    pub const COMPOSITION: &str = "composition";
+    pub const TECHNICAL_QUALITY: &str = "technical_quality";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityScore {
     /// Overall quality score (0.0 - 1.0)
     pub overall_score: f64,
@@ -19,32 +40,41 @@ pub struct QualityScore {
     pub composition: f64,
     /// Technical quality (noise, artifacts, etc.) (0.0 - 1.0)
     pub technical_quality: f64,
+    /// Every metric the analyzer ran, by name (0.0 - 1.0), including the
+    /// built-ins above and any registered custom metrics
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
 }
 
 impl QualityScore {
     /// Create a new quality score with all components
     pub fn new(sharpness: f64, exposure: f64, composition: f64, technical_quality: f64) -> Self {
-        let overall_score = Self::calculate_overall_score(sharpness, exposure, composition, technical_quality);
+        let metrics = BTreeMap::from([
+            (metric_names::SHARPNESS.to_string(), sharpness),
+            (metric_names::EXPOSURE.to_string(), exposure),
+            (metric_names::COMPOSITION.to_string(), composition),
+            (metric_names::TECHNICAL_QUALITY.to_string(), technical_quality),
+        ]);
+        Self::from_metrics(metrics, &WeightProfile::default())
+    }
+
+    /// Build a score from named metric values, weighted by `weights`.
+    /// Built-in metrics missing from `metrics` read as 0.0.
+    pub fn from_metrics(metrics: BTreeMap<String, f64>, weights: &WeightProfile) -> Self {
+        let get = |name: &str| metrics.get(name).copied().unwrap_or(0.0);
         Self {
-            overall_score,
-            sharpness,
-            exposure,
-            composition,
-            technical_quality,
+            overall_score: weights.overall_score(&metrics),
+            sharpness: get(metric_names::SHARPNESS),
+            exposure: get(metric_names::EXPOSURE),
+            composition: get(metric_names::COMPOSITION),
+            technical_quality: get(metric_names::TECHNICAL_QUALITY),
+            metrics,
         }
     }
-    
-    /// Calculate overall score from component scores
-    fn calculate_overall_score(sharpness: f64, exposure: f64, composition: f64, technical_quality: f64) -> f64 {
-        // Weighted average with emphasis on sharpness for burst picking
-        let weights = [
-            (sharpness, 0.4),        // Sharpness is critical for burst selection
-            (exposure, 0.25),        // Proper exposure
-            (technical_quality, 0.25), // Low noise, no artifacts
-            (composition, 0.1),      // Nice to have, but less critical for bursts
-        ];
-        
-        weights.iter().map(|(score, weight)| score * weight).sum()
+
+    /// Value of a named metric, if the analyzer ran it.
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        self.metrics.get(name).copied()
     }
     
     /// Check if this image meets minimum quality thresholds
@@ -67,29 +97,243 @@ impl QualityScore {
     }
 }
 
+/// How much each metric counts towards `overall_score`, by metric name.
+///
+/// Weights are relative — the overall score is the weighted mean over the
+/// metrics a score actually has — so a profile doesn't need to sum to 1 and
+/// a weight of 0 switches a metric off without removing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightProfile {
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl WeightProfile {
+    /// Profile with the given weights. Fails on negative or non-finite weights.
+    pub fn new(weights: BTreeMap<String, f64>) -> Result<Self> {
+        if let Some((name, weight)) = weights.iter().find(|(_, w)| !w.is_finite() || **w < 0.0) {
+            bail!("Invalid weight {} for quality metric '{}': weights must be finite and non-negative", weight, name);
+        }
+        Ok(Self { weights })
+    }
+
+    fn from_pairs(pairs: &[(&str, f64)]) -> Self {
+        Self { weights: pairs.iter().map(|(name, w)| (name.to_string(), *w)).collect() }
+    }
+
+    /// Sports: the decisive moment has to be sharp; framing is often luck.
+    pub fn sports() -> Self {
+        Self::from_pairs(&[
+            (metric_names::SHARPNESS, 0.55),
+            (metric_names::EXPOSURE, 0.15),
+            (metric_names::TECHNICAL_QUALITY, 0.25),
+            (metric_names::COMPOSITION, 0.05),
+        ])
+    }
+
+    /// Portrait: soft focus fall-off is fine if the frame and light are right.
+    pub fn portrait() -> Self {
+        Self::from_pairs(&[
+            (metric_names::SHARPNESS, 0.25),
+            (metric_names::EXPOSURE, 0.3),
+            (metric_names::TECHNICAL_QUALITY, 0.15),
+            (metric_names::COMPOSITION, 0.3),
+        ])
+    }
+
+    /// Weight for a metric; unlisted metrics get `default`.
+    pub fn weight_or(&self, name: &str, default: f64) -> f64 {
+        self.weights.get(name).copied().unwrap_or(default)
+    }
+
+    /// This profile with `overrides` laid over it.
+    pub fn with_overrides(mut self, overrides: &WeightProfile) -> Self {
+        self.weights.extend(overrides.weights.iter().map(|(k, v)| (k.clone(), *v)));
+        self
+    }
+
+    /// Weighted mean of `metrics`. Metrics without a weight don't count.
+    pub fn overall_score(&self, metrics: &BTreeMap<String, f64>) -> f64 {
+        let (total, weight_sum) = metrics.iter()
+            .filter_map(|(name, value)| self.weights.get(name).map(|w| (value * w, *w)))
+            .fold((0.0, 0.0), |acc, (v, w)| (acc.0 + v, acc.1 + w));
+        if weight_sum > 0.0 { total / weight_sum } else { 0.0 }
+    }
+}
+
+impl Default for WeightProfile {
+    /// Emphasis on sharpness for burst picking.
+    fn default() -> Self {
+        Self::from_pairs(&[
+            (metric_names::SHARPNESS, 0.4),           // Sharpness is critical for burst selection
+            (metric_names::EXPOSURE, 0.25),           // Proper exposure
+            (metric_names::TECHNICAL_QUALITY, 0.25),  // Low noise, no artifacts
+            (metric_names::COMPOSITION, 0.1),         // Nice to have, but less critical for bursts
+        ])
+    }
+}
+
+/// What a metric gets to look at: the decoded preview, and the frame's EXIF
+/// when the analyzer was given it.
+pub struct MetricInput<'a> {
+    pub image: &'a DynamicImage,
+    pub luma: &'a GrayImage,
+    pub exif: Option<&'a ExifData>,
+}
+
+/// One named quality measurement, registered on a `QualityAnalyzer`.
+pub trait QualityMetric: Send + Sync {
+    /// Key in `QualityScore::metrics` and in weight profiles.
+    fn name(&self) -> &str;
+
+    /// Score the image, 0.0 (worst) - 1.0 (best).
+    fn compute(&self, input: &MetricInput) -> f64;
+
+    /// Weight used when the active profile doesn't list this metric.
+    fn default_weight(&self) -> f64;
+}
+
+/// Laplacian-variance sharpness; see `algorithms::laplacian_sharpness`.
+pub struct SharpnessMetric;
+
+impl QualityMetric for SharpnessMetric {
+    fn name(&self) -> &str { metric_names::SHARPNESS }
+    fn compute(&self, input: &MetricInput) -> f64 {
+        algorithms::laplacian_sharpness(input.luma).score()
+    }
+    fn default_weight(&self) -> f64 { 0.4 }
+}
+
+/// Histogram exposure; see `algorithms::ExposureMetrics`.
+pub struct ExposureMetric;
+
+impl QualityMetric for ExposureMetric {
+    fn name(&self) -> &str { metric_names::EXPOSURE }
+    fn compute(&self, input: &MetricInput) -> f64 {
+        algorithms::ExposureMetrics::from_histogram(algorithms::exposure_histogram(&input.image.to_rgb8())).score()
+    }
+    fn default_weight(&self) -> f64 { 0.25 }
+}
+
+/// Motion blur and noise, backed by ISO when noise can't be measured.
+pub struct TechnicalQualityMetric;
+
+impl QualityMetric for TechnicalQualityMetric {
+    fn name(&self) -> &str { metric_names::TECHNICAL_QUALITY }
+    fn compute(&self, input: &MetricInput) -> f64 {
+        let iso = input.exif.and_then(|exif| exif.iso);
+        (1.0 - algorithms::estimate_motion_blur(input.luma).penalty())
+            * algorithms::estimate_noise(input.luma, iso).score()
+    }
+    fn default_weight(&self) -> f64 { 0.25 }
+}
+
+/// Placeholder until there's a composition model: every frame scores neutral.
+pub struct CompositionMetric;
+
+impl QualityMetric for CompositionMetric {
+    fn name(&self) -> &str { metric_names::COMPOSITION }
+    fn compute(&self, _input: &MetricInput) -> f64 {
+        // TODO: Use CLIP or custom model for composition analysis
+        0.5
+    }
+    fn default_weight(&self) -> f64 { 0.1 }
+}
+
+/// Supplies the JPEG that quality analysis runs on (normally the Preview tier).
+pub trait PreviewSource: Send + Sync {
+    fn preview_jpeg(&self, path: &Path) -> Result<Vec<u8>>;
+}
+
 pub struct QualityAnalyzer {
+    previews: Option<Arc<dyn PreviewSource>>,
+    metrics: Vec<Box<dyn QualityMetric>>,
+    weights: WeightProfile,
     // Future: Will contain AI model handles and configuration
 }
 
 impl QualityAnalyzer {
+    /// Analyzer that decodes the image file itself. Only works for formats
+    /// the `image` crate reads (JPEG, PNG, TIFF, ...); use `with_previews`
+    /// for RAW files.
     pub fn new() -> Result<Self> {
         // TODO: Initialize AI models (ONNX Runtime, etc.)
-        Ok(Self {})
+        Ok(Self {
+            previews: None,
+            metrics: vec![
+                Box::new(SharpnessMetric),
+                Box::new(ExposureMetric),
+                Box::new(CompositionMetric),
+                Box::new(TechnicalQualityMetric),
+            ],
+            weights: WeightProfile { weights: BTreeMap::new() },
+        })
+    }
+
+    /// Analyzer that scores the preview JPEG supplied by `previews`.
+    pub fn with_previews(previews: Arc<dyn PreviewSource>) -> Self {
+        Self { previews: Some(previews), ..Self::default() }
+    }
+
+    /// Add a metric, replacing any registered metric with the same name.
+    pub fn register_metric(&mut self, metric: Box<dyn QualityMetric>) {
+        self.metrics.retain(|m| m.name() != metric.name());
+        self.metrics.push(metric);
+    }
+
+    /// Names of the registered metrics, in registration order.
+    pub fn metric_names(&self) -> Vec<&str> {
+        self.metrics.iter().map(|m| m.name()).collect()
+    }
+
+    /// Weigh metrics with `weights`; metrics it doesn't list keep their
+    /// default weight.
+    pub fn set_weights(&mut self, weights: WeightProfile) {
+        self.weights = weights;
+    }
+
+    /// Effective weight of every registered metric.
+    pub fn weights(&self) -> WeightProfile {
+        WeightProfile {
+            weights: self.metrics.iter()
+                .map(|m| (m.name().to_string(), self.weights.weight_or(m.name(), m.default_weight())))
+                .collect(),
+        }
     }
     
     /// Analyze image quality from file path
-    pub fn analyze_image<P: AsRef<Path>>(&self, _path: P) -> Result<QualityScore> {
-        // TODO: Implement actual AI-based quality analysis
-        // For now, return a placeholder score for testing
-        
-        // This will eventually:
-        // 1. Load image and extract thumbnail/preview
-        // 2. Run sharpness detection (Laplacian variance, etc.)
-        // 3. Analyze exposure histogram
-        // 4. Check for technical issues (noise, compression artifacts)
-        // 5. Use CLIP or custom model for composition analysis
-        
-        Ok(self.placeholder_score())
+    pub fn analyze_image<P: AsRef<Path>>(&self, path: P) -> Result<QualityScore> {
+        self.analyze_path(path.as_ref(), None)
+    }
+
+    /// Analyze an imported image; metrics also see its EXIF (e.g. ISO backs up
+    /// the noise estimate when the frame has no flat areas to measure).
+    pub fn analyze_exif(&self, image: &ExifData) -> Result<QualityScore> {
+        self.analyze_path(&image.file_path, Some(image))
+    }
+
+    fn analyze_path(&self, path: &Path, exif: Option<&ExifData>) -> Result<QualityScore> {
+        let data = match &self.previews {
+            Some(previews) => previews.preview_jpeg(path)?,
+            None => fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?,
+        };
+        self.analyze_bytes_with_exif(&data, exif)
+            .with_context(|| format!("Failed to analyze {}", path.display()))
+    }
+
+    /// Analyze an encoded image (normally a Preview-tier JPEG).
+    pub fn analyze_bytes(&self, image_data: &[u8]) -> Result<QualityScore> {
+        self.analyze_bytes_with_exif(image_data, None)
+    }
+
+    fn analyze_bytes_with_exif(&self, image_data: &[u8], exif: Option<&ExifData>) -> Result<QualityScore> {
+        let image = algorithms::decode_for_analysis(image_data)?;
+        let luma = image.to_luma8();
+        let input = MetricInput { image: &image, luma: &luma, exif };
+
+        let metrics = self.metrics.iter()
+            .map(|metric| (metric.name().to_string(), metric.compute(&input).clamp(0.0, 1.0)))
+            .collect();
+        Ok(QualityScore::from_metrics(metrics, &self.weights()))
     }
     
     /// Batch analyze multiple images efficiently
@@ -113,26 +357,7 @@ impl QualityAnalyzer {
             other => other,
         }
     }
-    
-    /// Generate placeholder quality score for development/testing
-    fn placeholder_score(&self) -> QualityScore {
-        // Generate deterministic but varied scores for testing
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        std::thread::current().id().hash(&mut hasher);
-        let hash = hasher.finish();
-        
-        let base = (hash % 100) as f64 / 100.0;
-        
-        QualityScore::new(
-            (0.4 + base * 0.5).min(1.0),          // Sharpness: 0.4-0.9
-            (0.3 + base * 0.6).min(1.0),          // Exposure: 0.3-0.9  
-            (0.5 + base * 0.4).min(1.0),          // Composition: 0.5-0.9
-            (0.4 + base * 0.5).min(1.0),          // Technical: 0.4-0.9
-        )
-    }
+
 }
 
 impl Default for QualityAnalyzer {
@@ -143,28 +368,260 @@ impl Default for QualityAnalyzer {
 
 /// Specialized algorithms for photography-specific quality metrics
 pub mod algorithms {
-    #[allow(unused_imports)]
     use super::*;
+    use image::imageops::FilterType;
+    use image::RgbImage;
+
+    /// Long edge analysis runs at — the Preview tier's size. Larger inputs are
+    /// downscaled so variances are comparable whatever the source.
+    pub const ANALYSIS_MAX_DIMENSION: u32 = 1600;
+
+    /// Tiles per side of the sharpness grid.
+    pub const SHARPNESS_GRID: u32 = 4;
+
+    /// Laplacian variance that maps to a sharpness of 0.5. The usual "blurry
+    /// below 100" rule of thumb, which holds up at preview resolution.
+    const SHARPNESS_HALF_POINT: f64 = 100.0;
+
+    /// Weight of the sharpest tile vs the whole frame. A portrait with a
+    /// creamy background has a low global variance but a sharp subject, and
+    /// the subject is what the photographer is judging.
+    const MAX_TILE_WEIGHT: f64 = 0.7;
+
+    /// Raw Laplacian variances for one image.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SharpnessMetrics {
+        /// Variance over the whole frame
+        pub global_variance: f64,
+        /// Per-tile variance, row-major, `SHARPNESS_GRID` x `SHARPNESS_GRID`
+        pub tile_variances: Vec<f64>,
+        /// Sharpest tile: a proxy for subject sharpness
+        pub max_tile_variance: f64,
+    }
+
+    impl SharpnessMetrics {
+        /// Normalized sharpness (0.0 - 1.0)
+        pub fn score(&self) -> f64 {
+            MAX_TILE_WEIGHT * normalize_sharpness(self.max_tile_variance)
+                + (1.0 - MAX_TILE_WEIGHT) * normalize_sharpness(self.global_variance)
+        }
+    }
+
+    /// Map a Laplacian variance onto 0.0 - 1.0.
+    pub fn normalize_sharpness(variance: f64) -> f64 {
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        variance / (variance + SHARPNESS_HALF_POINT)
+    }
+
+    /// Decode an encoded image at analysis resolution.
+    pub fn decode_for_analysis(image_data: &[u8]) -> Result<DynamicImage> {
+        let img = image::load_from_memory(image_data).context("Failed to decode image")?;
+        Ok(if img.width().max(img.height()) > ANALYSIS_MAX_DIMENSION {
+            img.resize(ANALYSIS_MAX_DIMENSION, ANALYSIS_MAX_DIMENSION, FilterType::Triangle)
+        } else {
+            img
+        })
+    }
+
+    /// Decode an encoded image to 8-bit luma at analysis resolution.
+    pub fn decode_luma(image_data: &[u8]) -> Result<GrayImage> {
+        Ok(decode_for_analysis(image_data)?.to_luma8())
+    }
+
+    /// Variance of the 4-neighbour Laplacian, globally and per tile.
+    pub fn laplacian_sharpness(luma: &GrayImage) -> SharpnessMetrics {
+        let (width, height) = luma.dimensions();
+        let tiles = (SHARPNESS_GRID * SHARPNESS_GRID) as usize;
+        // (sum, sum of squares, count) per tile
+        let mut acc = vec![(0.0f64, 0.0f64, 0u64); tiles];
+
+        if width >= 3 && height >= 3 {
+            let px = |x: u32, y: u32| luma.get_pixel(x, y)[0] as i32;
+            for y in 1..height - 1 {
+                let row = (y * SHARPNESS_GRID / height) as usize;
+                for x in 1..width - 1 {
+                    let lap = (4 * px(x, y) - px(x - 1, y) - px(x + 1, y) - px(x, y - 1) - px(x, y + 1)) as f64;
+                    let tile = &mut acc[row * SHARPNESS_GRID as usize + (x * SHARPNESS_GRID / width) as usize];
+                    tile.0 += lap;
+                    tile.1 += lap * lap;
+                    tile.2 += 1;
+                }
+            }
+        }
+
+        let variance = |(sum, sum_sq, n): (f64, f64, u64)| {
+            if n == 0 {
+                return 0.0;
+            }
+            let mean = sum / n as f64;
+            (sum_sq / n as f64 - mean * mean).max(0.0)
+        };
+
+        let tile_variances: Vec<f64> = acc.iter().copied().map(variance).collect();
+        let total = acc.iter().fold((0.0, 0.0, 0), |t, a| (t.0 + a.0, t.1 + a.1, t.2 + a.2));
+
+        SharpnessMetrics {
+            global_variance: variance(total),
+            max_tile_variance: tile_variances.iter().copied().fold(0.0, f64::max),
+            tile_variances,
+        }
+    }
     
-    /// Calculate sharpness using Laplacian variance method
-    pub fn calculate_laplacian_sharpness(_image_data: &[u8]) -> f64 {
-        // TODO: Implement Laplacian variance sharpness detection
-        // This is a standard computer vision technique:
-        // 1. Convert to grayscale
-        // 2. Apply Laplacian kernel
-        // 3. Calculate variance of result
-        // Higher variance = sharper image
-        0.8 // Placeholder
+    /// Calculate sharpness (0.0 - 1.0) using Laplacian variance method
+    pub fn calculate_laplacian_sharpness(image_data: &[u8]) -> Result<f64> {
+        Ok(laplacian_sharpness(&decode_luma(image_data)?).score())
     }
     
-    /// Analyze exposure quality from histogram
-    pub fn analyze_exposure_histogram(_image_data: &[u8]) -> f64 {
-        // TODO: Implement histogram-based exposure analysis
-        // 1. Generate luminance histogram
-        // 2. Check for clipping (pure black/white)
-        // 3. Evaluate distribution (avoid spikes at extremes)
-        // 4. Consider rule of thirds for tonality
-        0.7 // Placeholder
+    /// A channel value at or above this is treated as blown. Not 255: JPEG
+    /// ringing scatters clipped areas across 250-255.
+    pub const HIGHLIGHT_CLIP_LEVEL: u8 = 250;
+
+    /// Luma at or below this is treated as crushed black.
+    pub const SHADOW_CLIP_LEVEL: u8 = 5;
+
+    /// Highlight clipping (percent of pixels) above which a frame is blown out.
+    pub const BLOWN_OUT_PERCENT: f64 = 15.0;
+
+    /// Clipping that costs nothing: specular highlights, a sliver of black.
+    const HIGHLIGHT_CLIP_ALLOWANCE: f64 = 1.0;
+    const SHADOW_CLIP_ALLOWANCE: f64 = 5.0;
+
+    /// Clipping (percent past the allowance) at which the exposure score hits 0.
+    const HIGHLIGHT_CLIP_RANGE: f64 = 20.0;
+    const SHADOW_CLIP_RANGE: f64 = 40.0;
+
+    /// Median EV offset at which the exposure score halves. Generous, because
+    /// high-key and low-key frames are often deliberate.
+    const EV_HALF_POINT: f64 = 1.5;
+
+    /// Linear reflectance of middle grey.
+    const MIDDLE_GREY: f64 = 0.18;
+
+    /// 256-bin histograms of an image, for scoring and for the UI to draw.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct ExposureHistogram {
+        pub luma: Vec<u32>,
+        pub red: Vec<u32>,
+        pub green: Vec<u32>,
+        pub blue: Vec<u32>,
+        pub pixel_count: u64,
+        /// Pixels with any channel at or above `HIGHLIGHT_CLIP_LEVEL`. Counted
+        /// separately because channels clip independently — a red sunset
+        /// blows red long before luma — and the histograms can't recover it.
+        pub highlight_clipped_pixels: u64,
+    }
+
+    /// Exposure analysis of one image.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct ExposureMetrics {
+        pub histogram: ExposureHistogram,
+        /// Percent of pixels with any channel at or above `HIGHLIGHT_CLIP_LEVEL`
+        pub highlight_clipping_percent: f64,
+        /// Percent of pixels with luma at or below `SHADOW_CLIP_LEVEL`
+        pub shadow_clipping_percent: f64,
+        /// Stops between mean scene luminance and middle grey (+ = bright)
+        pub mean_ev_offset: f64,
+        /// Stops between median luma and middle grey (+ = bright)
+        pub median_ev_offset: f64,
+    }
+
+    impl ExposureMetrics {
+        /// Compute clipping and EV offsets from a histogram.
+        pub fn from_histogram(histogram: ExposureHistogram) -> Self {
+            let total = histogram.pixel_count.max(1) as f64;
+            let shadow_pixels: u64 = histogram.luma[..=SHADOW_CLIP_LEVEL as usize].iter().map(|&n| n as u64).sum();
+            let linear_sum: f64 = histogram.luma.iter().enumerate()
+                .map(|(value, &n)| srgb_to_linear(value as u8) * n as f64)
+                .sum();
+
+            // pixel_count - 1 so an even split lands on the lower bin
+            let median_rank = histogram.pixel_count.saturating_sub(1) / 2;
+            let mut seen = 0u64;
+            let median = histogram.luma.iter()
+                .position(|&n| {
+                    seen += n as u64;
+                    seen > median_rank
+                })
+                .unwrap_or(0) as u8;
+
+            Self {
+                highlight_clipping_percent: histogram.highlight_clipped_pixels as f64 / total * 100.0,
+                shadow_clipping_percent: shadow_pixels as f64 / total * 100.0,
+                mean_ev_offset: ev_offset(linear_sum / total),
+                median_ev_offset: ev_offset(srgb_to_linear(median)),
+                histogram,
+            }
+        }
+
+        /// Exposure quality (0.0 - 1.0)
+        pub fn score(&self) -> f64 {
+            let ev = 1.0 / (1.0 + (self.median_ev_offset / EV_HALF_POINT).powi(2));
+            let highlights = 1.0 - ((self.highlight_clipping_percent - HIGHLIGHT_CLIP_ALLOWANCE) / HIGHLIGHT_CLIP_RANGE).clamp(0.0, 1.0);
+            let shadows = 1.0 - ((self.shadow_clipping_percent - SHADOW_CLIP_ALLOWANCE) / SHADOW_CLIP_RANGE).clamp(0.0, 1.0);
+            ev * highlights * shadows
+        }
+
+        /// Enough of the frame is blown that it can be rejected outright.
+        pub fn is_blown_out(&self) -> bool {
+            self.highlight_clipping_percent >= BLOWN_OUT_PERCENT
+        }
+    }
+
+    fn srgb_to_linear(value: u8) -> f64 {
+        let v = value as f64 / 255.0;
+        if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    /// Stops from middle grey. Pure black is floored at half a code value
+    /// rather than reported as -inf.
+    fn ev_offset(linear: f64) -> f64 {
+        (linear.max(0.5 / 255.0 / 12.92) / MIDDLE_GREY).log2()
+    }
+
+    /// Rec. 709 luma on the gamma-encoded values, as `image::to_luma8` does.
+    fn luma(r: u8, g: u8, b: u8) -> u8 {
+        ((2126 * r as u32 + 7152 * g as u32 + 722 * b as u32 + 5000) / 10000) as u8
+    }
+
+    /// Build luma and per-channel histograms.
+    pub fn exposure_histogram(rgb: &RgbImage) -> ExposureHistogram {
+        let mut histogram = ExposureHistogram {
+            luma: vec![0; 256],
+            red: vec![0; 256],
+            green: vec![0; 256],
+            blue: vec![0; 256],
+            pixel_count: 0,
+            highlight_clipped_pixels: 0,
+        };
+        for pixel in rgb.pixels() {
+            let [r, g, b] = pixel.0;
+            histogram.red[r as usize] += 1;
+            histogram.green[g as usize] += 1;
+            histogram.blue[b as usize] += 1;
+            histogram.luma[luma(r, g, b) as usize] += 1;
+            histogram.pixel_count += 1;
+            if r.max(g).max(b) >= HIGHLIGHT_CLIP_LEVEL {
+                histogram.highlight_clipped_pixels += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Analyze an encoded image's exposure.
+    pub fn exposure_metrics(image_data: &[u8]) -> Result<ExposureMetrics> {
+        let img = decode_for_analysis(image_data)?;
+        Ok(ExposureMetrics::from_histogram(exposure_histogram(&img.to_rgb8())))
+    }
+
+    /// Analyze exposure quality (0.0 - 1.0) from histogram
+    pub fn analyze_exposure_histogram(image_data: &[u8]) -> Result<f64> {
+        Ok(exposure_metrics(image_data)?.score())
     }
     
     /// Detect eyes and check if they're open/closed
@@ -175,20 +632,591 @@ pub mod algorithms {
         Some(true) // Placeholder: eyes open
     }
     
+    /// Side of the blocks noise is sampled in.
+    const NOISE_BLOCK: u32 = 8;
+
+    /// Share of blocks, least detailed first, treated as flat.
+    const FLAT_BLOCK_SHARE: f64 = 0.25;
+
+    /// Fewer flat pixels than this and the measurement is too thin to trust.
+    const MIN_FLAT_PIXELS: usize = 1024;
+
+    /// Blocks averaging outside this range are clipped, and clipped areas
+    /// have no noise left to measure.
+    const UNCLIPPED_RANGE: std::ops::RangeInclusive<f64> = 8.0..=247.0;
+
+    /// Noise sigma (luma code values at preview size) at which the noise
+    /// score halves. Downscaling to the preview averages much of the noise
+    /// away, so this is lower than it would be at full resolution.
+    const NOISE_HALF_POINT: f64 = 4.0;
+
+    /// Sigma a typical body shows in the preview at base ISO 100; it scales
+    /// with the square root of ISO (photon shot noise).
+    const BASE_ISO_SIGMA: f64 = 0.5;
+
+    /// Noise estimate for one image, with the ISO it was shot at.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct NoiseEstimate {
+        /// Measured noise standard deviation in luma code values, or `None`
+        /// when the frame has too little flat area to measure it
+        pub sigma: Option<f64>,
+        /// Share of the frame the measurement came from
+        pub flat_fraction: f64,
+        pub iso: Option<u32>,
+    }
+
+    impl NoiseEstimate {
+        /// Measured sigma, else the sigma expected at this ISO.
+        pub fn effective_sigma(&self) -> Option<f64> {
+            self.sigma.or_else(|| self.iso.map(|iso| BASE_ISO_SIGMA * (iso as f64 / 100.0).sqrt()))
+        }
+
+        /// Low-noise quality (0.0 - 1.0). Neutral 1.0 when nothing is known.
+        pub fn score(&self) -> f64 {
+            match self.effective_sigma() {
+                Some(sigma) => 1.0 / (1.0 + (sigma / NOISE_HALF_POINT).powi(2)),
+                None => 1.0,
+            }
+        }
+    }
+
+    fn median(values: &mut [f64]) -> f64 {
+        values.sort_unstable_by(f64::total_cmp);
+        values[values.len() / 2]
+    }
+
+    /// Estimate sensor noise from the high-pass residual in flat regions.
+    ///
+    /// Immerkaer's kernel cancels smooth gradients, so in flat areas what it
+    /// leaves is noise scaled by 6. The median absolute deviation of that
+    /// residual is a robust sigma: a stray edge in a "flat" block barely
+    /// moves it, where a variance would be dominated by it.
+    pub fn estimate_noise(luma: &GrayImage, iso: Option<u32>) -> NoiseEstimate {
+        let (width, height) = luma.dimensions();
+        let px = |x: u32, y: u32| luma.get_pixel(x, y)[0] as f64;
+
+        // (activity, x, y) of every unclipped interior block
+        let mut blocks = Vec::new();
+        for by in (1..height.saturating_sub(NOISE_BLOCK)).step_by(NOISE_BLOCK as usize) {
+            for bx in (1..width.saturating_sub(NOISE_BLOCK)).step_by(NOISE_BLOCK as usize) {
+                let (mut activity, mut sum) = (0.0, 0.0);
+                for y in by..by + NOISE_BLOCK {
+                    for x in bx..bx + NOISE_BLOCK {
+                        activity += (px(x + 1, y) - px(x - 1, y)).abs() + (px(x, y + 1) - px(x, y - 1)).abs();
+                        sum += px(x, y);
+                    }
+                }
+                if UNCLIPPED_RANGE.contains(&(sum / (NOISE_BLOCK * NOISE_BLOCK) as f64)) {
+                    blocks.push((activity, bx, by));
+                }
+            }
+        }
+
+        blocks.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        let flat_count = (blocks.len() as f64 * FLAT_BLOCK_SHARE).ceil() as usize;
+
+        let mut residuals = Vec::new();
+        for &(_, bx, by) in &blocks[..flat_count] {
+            for y in by..by + NOISE_BLOCK {
+                for x in bx..bx + NOISE_BLOCK {
+                    residuals.push(
+                        px(x - 1, y - 1) + px(x + 1, y - 1) + px(x - 1, y + 1) + px(x + 1, y + 1)
+                            - 2.0 * (px(x, y - 1) + px(x - 1, y) + px(x + 1, y) + px(x, y + 1))
+                            + 4.0 * px(x, y),
+                    );
+                }
+            }
+        }
+
+        let flat_fraction = residuals.len() as f64 / (width as f64 * height as f64).max(1.0);
+        let sigma = (residuals.len() >= MIN_FLAT_PIXELS).then(|| {
+            let center = median(&mut residuals);
+            let mut deviations: Vec<f64> = residuals.iter().map(|r| (r - center).abs()).collect();
+            1.4826 * median(&mut deviations) / 6.0
+        });
+
+        NoiseEstimate { sigma, flat_fraction, iso }
+    }
+
+    /// Tiles per side of the motion blur grid.
+    pub const MOTION_GRID: u32 = 4;
+
+    /// Gradient coherence of ordinary sharp scenes; anything up to here is
+    /// just the scene's own edge structure.
+    const COHERENCE_FLOOR: f64 = 0.15;
+
+    /// Coherence at which blur magnitude saturates at 1.0.
+    const COHERENCE_FULL: f64 = 0.75;
+
+    /// Tile magnitude above which a tile counts as streaked.
+    const STREAKED_TILE: f64 = 0.3;
+
+    /// Tile magnitude below which a tile counts as sharp (a tracked subject).
+    const STILL_TILE: f64 = 0.15;
+
+    /// Max angle between a tile's streak and the frame's to count as aligned.
+    const ALIGNED_DEGREES: f64 = 20.0;
+
+    /// Share of textured tiles that must be streaked in the same direction
+    /// for the whole frame to have moved.
+    const FRAME_MOTION_SHARE: f64 = 0.75;
+
+    /// Tiles with less gradient energy than this share of the mean are
+    /// featureless (sky, backdrop) and say nothing about motion.
+    const TEXTURED_TILE_ENERGY: f64 = 0.25;
+
+    /// What moved during the exposure.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum MotionBlurKind {
+        /// No directional blur
+        None,
+        /// The whole frame streaked in one direction
+        CameraShake,
+        /// The frame streaked except for a sharp region: a tracked subject
+        Panning,
+        /// Part of the frame streaked against a still background
+        SubjectMotion,
+    }
+
+    /// Motion blur estimate for one image.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct MotionBlur {
+        /// Frame-wide blur amount (0.0 = none, 1.0 = heavy)
+        pub magnitude: f64,
+        /// Streak direction in degrees, 0 = horizontal, 90 = vertical,
+        /// counter-clockwise as seen on screen
+        pub direction_degrees: f64,
+        /// Blur amount of the stillest textured tile — the subject, for a pan
+        pub subject_magnitude: f64,
+        pub kind: MotionBlurKind,
+    }
+
+    impl MotionBlur {
+        /// Blur that counts against technical quality. A pan is judged on its
+        /// subject alone: streaked background is the point of the shot.
+        pub fn penalty(&self) -> f64 {
+            match self.kind {
+                MotionBlurKind::None => 0.0,
+                MotionBlurKind::Panning => self.subject_magnitude,
+                MotionBlurKind::CameraShake | MotionBlurKind::SubjectMotion => self.magnitude,
+            }
+        }
+    }
+
+    /// Sums of the gradient structure tensor over a region.
+    #[derive(Debug, Clone, Copy, Default)]
+    struct StructureTensor {
+        xx: f64,
+        yy: f64,
+        xy: f64,
+    }
+
+    impl StructureTensor {
+        fn add(&mut self, other: &StructureTensor) {
+            self.xx += other.xx;
+            self.yy += other.yy;
+            self.xy += other.xy;
+        }
+
+        fn energy(&self) -> f64 {
+            self.xx + self.yy
+        }
+
+        /// (lambda1 - lambda2) / (lambda1 + lambda2): 0 when gradients point
+        /// every way, 1 when they all point one way.
+        fn coherence(&self) -> f64 {
+            let energy = self.energy();
+            if energy <= 0.0 {
+                return 0.0;
+            }
+            ((self.xx - self.yy).powi(2) + 4.0 * self.xy * self.xy).sqrt() / energy
+        }
+
+        fn magnitude(&self) -> f64 {
+            ((self.coherence() - COHERENCE_FLOOR) / (COHERENCE_FULL - COHERENCE_FLOOR)).clamp(0.0, 1.0)
+        }
+
+        /// Streak direction: perpendicular to the dominant gradient. The y
+        /// term is negated because image rows run downwards.
+        fn direction_degrees(&self) -> f64 {
+            let gradient = 0.5 * (-2.0 * self.xy).atan2(self.xx - self.yy);
+            (gradient.to_degrees() + 90.0).rem_euclid(180.0)
+        }
+    }
+
+    /// Smallest angle between two undirected directions, in degrees.
+    pub(crate) fn angle_between(a: f64, b: f64) -> f64 {
+        let diff = (a - b).rem_euclid(180.0);
+        diff.min(180.0 - diff)
+    }
+
+    /// Estimate motion blur from gradient-orientation energy.
+    ///
+    /// Motion blur smears edges along the direction of travel, so gradients
+    /// along it vanish while those across it survive: the structure tensor
+    /// turns anisotropic. Doing this per tile separates camera shake (every
+    /// tile streaked the same way) from a pan (all streaked but the subject)
+    /// and from subject motion (only part of the frame streaked).
+    pub fn estimate_motion_blur(luma: &GrayImage) -> MotionBlur {
+        let (width, height) = luma.dimensions();
+        let mut tiles = vec![StructureTensor::default(); (MOTION_GRID * MOTION_GRID) as usize];
+
+        if width >= 3 && height >= 3 {
+            let px = |x: u32, y: u32| luma.get_pixel(x, y)[0] as i32;
+            for y in 1..height - 1 {
+                let row = (y * MOTION_GRID / height) as usize;
+                for x in 1..width - 1 {
+                    // Sobel
+                    let gx = (px(x + 1, y - 1) + 2 * px(x + 1, y) + px(x + 1, y + 1)
+                        - px(x - 1, y - 1) - 2 * px(x - 1, y) - px(x - 1, y + 1)) as f64;
+                    let gy = (px(x - 1, y + 1) + 2 * px(x, y + 1) + px(x + 1, y + 1)
+                        - px(x - 1, y - 1) - 2 * px(x, y - 1) - px(x + 1, y - 1)) as f64;
+                    let tile = &mut tiles[row * MOTION_GRID as usize + (x * MOTION_GRID / width) as usize];
+                    tile.xx += gx * gx;
+                    tile.yy += gy * gy;
+                    tile.xy += gx * gy;
+                }
+            }
+        }
+
+        let mean_energy = tiles.iter().map(StructureTensor::energy).sum::<f64>() / tiles.len() as f64;
+        let textured: Vec<&StructureTensor> = tiles.iter()
+            .filter(|t| t.energy() > 0.0 && t.energy() >= TEXTURED_TILE_ENERGY * mean_energy)
+            .collect();
+
+        let mut frame = StructureTensor::default();
+        for tile in &textured {
+            frame.add(tile);
+        }
+
+        let magnitude = frame.magnitude();
+        let direction_degrees = frame.direction_degrees();
+        let subject_magnitude = textured.iter().map(|t| t.magnitude()).fold(1.0, f64::min);
+        let streaked = textured.iter()
+            .filter(|t| t.magnitude() >= STREAKED_TILE
+                && angle_between(t.direction_degrees(), direction_degrees) <= ALIGNED_DEGREES)
+            .count();
+
+        let kind = if textured.is_empty() || streaked == 0 {
+            MotionBlurKind::None
+        } else if streaked as f64 >= FRAME_MOTION_SHARE * textured.len() as f64 {
+            if subject_magnitude < STILL_TILE {
+                MotionBlurKind::Panning
+            } else {
+                MotionBlurKind::CameraShake
+            }
+        } else {
+            MotionBlurKind::SubjectMotion
+        };
+
+        MotionBlur {
+            magnitude,
+            direction_degrees,
+            subject_magnitude: if textured.is_empty() { 0.0 } else { subject_magnitude },
+            kind,
+        }
+    }
+
+    /// Estimate motion blur in an encoded image.
+    pub fn motion_blur_metrics(image_data: &[u8]) -> Result<MotionBlur> {
+        Ok(estimate_motion_blur(&decode_luma(image_data)?))
+    }
+
     /// Detect motion blur in the image
-    pub fn detect_motion_blur(_image_data: &[u8]) -> f64 {
-        // TODO: Implement motion blur detection
-        // 1. Edge detection
-        // 2. Analyze edge width/sharpness
-        // 3. Look for directional blur patterns
-        // Return blur amount (0.0 = no blur, 1.0 = heavy blur)
-        0.1 // Placeholder: minimal blur
+    /// Returns blur amount (0.0 = no blur, 1.0 = heavy blur) that counts
+    /// against the frame; see `MotionBlur::penalty`.
+    pub fn detect_motion_blur(image_data: &[u8]) -> Result<f64> {
+        Ok(motion_blur_metrics(image_data)?.penalty())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::algorithms::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+    use std::io::Write;
+
+    /// Deterministic 4px-block noise texture; every block edge is a hard edge.
+    fn texture(width: u32, height: u32) -> GrayImage {
+        let mut state: u32 = 0x1234_5678;
+        let mut blocks = Vec::new();
+        for _ in 0..(width / 4 + 1) * (height / 4 + 1) {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            blocks.push((state >> 24) as u8);
+        }
+        GrayImage::from_fn(width, height, |x, y| Luma([blocks[((y / 4) * (width / 4 + 1) + x / 4) as usize]]))
+    }
+
+    fn encode_jpeg(img: &GrayImage) -> Vec<u8> {
+        let mut data = Vec::new();
+        JpegEncoder::new_with_quality(&mut data, 90).encode_image(img).unwrap();
+        data
+    }
+
+    struct FakePreviews(Vec<u8>);
+
+    impl PreviewSource for FakePreviews {
+        fn preview_jpeg(&self, _path: &Path) -> Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_sharpness_drops_with_blur() {
+        let sharp = texture(256, 256);
+        let soft = image::imageops::blur(&sharp, 1.0);
+        let softer = image::imageops::blur(&sharp, 3.0);
+
+        let scores: Vec<f64> = [&sharp, &soft, &softer].iter()
+            .map(|img| calculate_laplacian_sharpness(&encode_jpeg(img)).unwrap())
+            .collect();
+
+        assert!(scores[0] > 0.9, "sharp scored {}", scores[0]);
+        assert!(scores[0] > scores[1] && scores[1] > scores[2], "{:?}", scores);
+        assert!(scores[2] < 0.3, "heavily blurred scored {}", scores[2]);
+    }
+
+    #[test]
+    fn test_flat_image_has_no_sharpness() {
+        let flat = GrayImage::from_pixel(64, 64, Luma([128]));
+        let metrics = laplacian_sharpness(&flat);
+
+        assert_eq!(metrics.global_variance, 0.0);
+        assert_eq!(metrics.score(), 0.0);
+    }
+
+    #[test]
+    fn test_max_tile_finds_sharp_subject() {
+        // Sharp subject in the top-left tile, defocused everywhere else
+        let sharp = texture(256, 256);
+        let mut img = image::imageops::blur(&sharp, 4.0);
+        image::imageops::replace(&mut img, &image::imageops::crop_imm(&sharp, 0, 0, 64, 64).to_image(), 0, 0);
+
+        let metrics = laplacian_sharpness(&img);
+
+        assert_eq!(metrics.tile_variances.len(), (SHARPNESS_GRID * SHARPNESS_GRID) as usize);
+        assert_eq!(metrics.max_tile_variance, metrics.tile_variances[0]);
+        assert!(metrics.max_tile_variance > 4.0 * metrics.global_variance);
+        assert!(metrics.score() > normalize_sharpness(metrics.global_variance));
+    }
+
+    #[test]
+    fn test_large_input_downscaled_to_preview_size() {
+        let luma = decode_luma(&encode_jpeg(&GrayImage::new(2400, 1200))).unwrap();
+        assert_eq!(luma.dimensions(), (ANALYSIS_MAX_DIMENSION, 800));
+    }
+
+    fn exposure_of(rgb: &RgbImage) -> ExposureMetrics {
+        ExposureMetrics::from_histogram(exposure_histogram(rgb))
+    }
+
+    #[test]
+    fn test_middle_grey_is_well_exposed() {
+        let metrics = exposure_of(&RgbImage::from_pixel(64, 64, Rgb([118, 118, 118])));
+
+        assert!(metrics.median_ev_offset.abs() < 0.05, "{}", metrics.median_ev_offset);
+        assert!(metrics.mean_ev_offset.abs() < 0.05);
+        assert_eq!(metrics.highlight_clipping_percent, 0.0);
+        assert_eq!(metrics.shadow_clipping_percent, 0.0);
+        assert!(metrics.score() > 0.99);
+    }
+
+    #[test]
+    fn test_ev_offset_in_stops() {
+        // sRGB 161 is ~one stop over middle grey, 85 ~one stop under
+        let over = exposure_of(&RgbImage::from_pixel(8, 8, Rgb([161, 161, 161])));
+        let under = exposure_of(&RgbImage::from_pixel(8, 8, Rgb([85, 85, 85])));
+
+        assert!((over.median_ev_offset - 1.0).abs() < 0.1, "{}", over.median_ev_offset);
+        assert!((under.median_ev_offset + 1.0).abs() < 0.1, "{}", under.median_ev_offset);
+        assert!(over.score() < 0.8 && under.score() < 0.8);
+    }
+
+    #[test]
+    fn test_blown_frame_detected() {
+        // Top 40% blown sky, rest mid-tone
+        let blown = RgbImage::from_fn(100, 100, |_, y| if y < 40 { Rgb([255, 255, 255]) } else { Rgb([110, 110, 110]) });
+        let metrics = exposure_of(&blown);
+
+        assert!((metrics.highlight_clipping_percent - 40.0).abs() < 1e-9);
+        assert!(metrics.is_blown_out());
+        assert_eq!(metrics.score(), 0.0);
+        assert_eq!(metrics.histogram.luma[255], 4000);
+        assert_eq!(metrics.histogram.pixel_count, 10_000);
+    }
+
+    #[test]
+    fn test_single_channel_clipping_counts() {
+        // Saturated red sunset: luma is mid-tone, but red is gone
+        let sunset = exposure_of(&RgbImage::from_pixel(10, 10, Rgb([255, 90, 40])));
+
+        assert_eq!(sunset.histogram.luma[255], 0);
+        assert_eq!(sunset.histogram.red[255], 100);
+        assert_eq!(sunset.highlight_clipping_percent, 100.0);
+    }
+
+    #[test]
+    fn test_crushed_shadows_penalised() {
+        let dark = RgbImage::from_fn(100, 100, |x, _| if x < 50 { Rgb([0, 0, 0]) } else { Rgb([118, 118, 118]) });
+        let metrics = exposure_of(&dark);
+
+        assert!((metrics.shadow_clipping_percent - 50.0).abs() < 1e-9);
+        assert!(!metrics.is_blown_out());
+        assert!(metrics.score() < 0.2);
+    }
+
+    /// Box-blur along (dx, dy) over `length` samples, like a streak.
+    fn streak(img: &GrayImage, dx: i32, dy: i32, length: i32) -> GrayImage {
+        let (w, h) = img.dimensions();
+        GrayImage::from_fn(w, h, |x, y| {
+            let sum: u32 = (0..length).map(|i| {
+                let sx = (x as i32 + (i - length / 2) * dx).clamp(0, w as i32 - 1) as u32;
+                let sy = (y as i32 + (i - length / 2) * dy).clamp(0, h as i32 - 1) as u32;
+                img.get_pixel(sx, sy)[0] as u32
+            }).sum();
+            Luma([(sum / length as u32) as u8])
+        })
+    }
+
+    #[test]
+    fn test_motion_blur_magnitude_and_direction() {
+        let sharp = texture(256, 256);
+        let still = estimate_motion_blur(&sharp);
+        let short = estimate_motion_blur(&streak(&sharp, 1, 0, 5));
+        let long = estimate_motion_blur(&streak(&sharp, 1, 0, 9));
+        let vertical = estimate_motion_blur(&streak(&sharp, 0, 1, 9));
+        let diagonal = estimate_motion_blur(&streak(&sharp, 1, -1, 9)); // up and to the right
+
+        assert_eq!(still.kind, MotionBlurKind::None);
+        assert_eq!(still.penalty(), 0.0);
+        assert!(0.0 < short.magnitude && short.magnitude < long.magnitude, "{:?} {:?}", short, long);
+        assert_eq!(long.kind, MotionBlurKind::CameraShake);
+        assert!(angle_between(long.direction_degrees, 0.0) < 5.0, "{:?}", long);
+        assert!(angle_between(vertical.direction_degrees, 90.0) < 5.0, "{:?}", vertical);
+        assert!(angle_between(diagonal.direction_degrees, 45.0) < 5.0, "{:?}", diagonal);
+    }
+
+    #[test]
+    fn test_defocus_is_not_motion() {
+        let defocused = image::imageops::blur(&texture(256, 256), 3.0);
+        assert_eq!(estimate_motion_blur(&defocused).kind, MotionBlurKind::None);
+    }
+
+    #[test]
+    fn test_pan_judged_on_subject() {
+        // Background streaked horizontally, tracked subject sharp in one tile
+        let sharp = texture(256, 256);
+        let mut pan = streak(&sharp, 1, 0, 15);
+        image::imageops::replace(&mut pan, &image::imageops::crop_imm(&sharp, 64, 64, 64, 64).to_image(), 64, 64);
+        let shake = streak(&sharp, 1, 0, 15);
+
+        let pan_blur = estimate_motion_blur(&pan);
+        let shake_blur = estimate_motion_blur(&shake);
+
+        assert_eq!(pan_blur.kind, MotionBlurKind::Panning, "{:?}", pan_blur);
+        assert_eq!(shake_blur.kind, MotionBlurKind::CameraShake);
+        assert!(pan_blur.penalty() < 0.15);
+        assert!(shake_blur.penalty() > 0.9);
+
+        let analyzer = QualityAnalyzer::default();
+        let pan_score = analyzer.analyze_bytes(&encode_jpeg(&pan)).unwrap();
+        let shake_score = analyzer.analyze_bytes(&encode_jpeg(&shake)).unwrap();
+        assert!(pan_score.technical_quality > shake_score.technical_quality + 0.5);
+    }
+
+    #[test]
+    fn test_subject_motion_against_still_background() {
+        // One quadrant streaked, the rest sharp
+        let sharp = texture(256, 256);
+        let mut img = sharp.clone();
+        let moving = streak(&image::imageops::crop_imm(&sharp, 0, 0, 128, 128).to_image(), 1, 0, 15);
+        image::imageops::replace(&mut img, &moving, 0, 0);
+
+        let blur = estimate_motion_blur(&img);
+        assert_eq!(blur.kind, MotionBlurKind::SubjectMotion, "{:?}", blur);
+        assert!(blur.magnitude < estimate_motion_blur(&streak(&sharp, 1, 0, 15)).magnitude);
+    }
+
+    /// Mid-grey plus Gaussian noise of `sigma` (Box-Muller over an LCG).
+    fn add_noise(img: &GrayImage, sigma: f64) -> GrayImage {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut uniform = move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+        GrayImage::from_fn(img.width(), img.height(), |x, y| {
+            let n = (-2.0 * uniform().ln()).sqrt() * (2.0 * std::f64::consts::PI * uniform()).cos();
+            Luma([(img.get_pixel(x, y)[0] as f64 + n * sigma).round().clamp(0.0, 255.0) as u8])
+        })
+    }
+
+    #[test]
+    fn test_noise_sigma_measured() {
+        let grey = GrayImage::from_pixel(256, 256, Luma([128]));
+        for sigma in [2.0, 5.0, 10.0] {
+            let estimate = estimate_noise(&add_noise(&grey, sigma), Some(3200));
+            let measured = estimate.sigma.unwrap();
+            assert!((measured - sigma).abs() < 0.25 * sigma, "sigma {} measured {}", sigma, measured);
+            assert_eq!(estimate.iso, Some(3200));
+        }
+        assert!(estimate_noise(&grey, None).sigma.unwrap() < 0.1);
+    }
+
+    #[test]
+    fn test_noise_ignores_texture() {
+        // Detailed subject on the left half, flat noisy backdrop on the right
+        let mut img = add_noise(&GrayImage::from_pixel(256, 256, Luma([128])), 3.0);
+        image::imageops::replace(&mut img, &image::imageops::crop_imm(&texture(256, 256), 0, 0, 128, 256).to_image(), 0, 0);
+
+        let measured = estimate_noise(&img, None).sigma.unwrap();
+        assert!((measured - 3.0).abs() < 0.75, "measured {}", measured);
+    }
+
+    #[test]
+    fn test_noisier_frame_ranks_lower() {
+        let grey = GrayImage::from_pixel(256, 256, Luma([128]));
+        let clean = estimate_noise(&add_noise(&grey, 1.0), Some(6400));
+        let noisy = estimate_noise(&add_noise(&grey, 6.0), Some(6400));
+
+        assert!(clean.score() > 0.9);
+        assert!(noisy.score() < clean.score());
+    }
+
+    #[test]
+    fn test_noise_falls_back_to_iso() {
+        // Blown frame: no unclipped flat blocks to measure
+        let blown = GrayImage::from_pixel(256, 256, Luma([255]));
+        let base = estimate_noise(&blown, Some(100));
+        let high = estimate_noise(&blown, Some(12_800));
+
+        assert_eq!(base.sigma, None);
+        assert!(base.score() > high.score());
+        assert_eq!(estimate_noise(&blown, None).score(), 1.0);
+    }
+
+    #[test]
+    fn test_analyze_image_from_file() {
+        let mut file = tempfile::Builder::new().suffix(".jpg").tempfile().unwrap();
+        file.write_all(&encode_jpeg(&texture(128, 128))).unwrap();
+
+        let score = QualityAnalyzer::default().analyze_image(file.path()).unwrap();
+        assert!(score.sharpness > 0.9);
+
+        let mut not_image = tempfile::NamedTempFile::new().unwrap();
+        not_image.write_all(b"not an image").unwrap();
+        assert!(QualityAnalyzer::default().analyze_image(not_image.path()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_image_uses_preview_source() {
+        let blurred = image::imageops::blur(&texture(128, 128), 3.0);
+        let analyzer = QualityAnalyzer::with_previews(Arc::new(FakePreviews(encode_jpeg(&blurred))));
+
+        let score = analyzer.analyze_image("/card/DSC_0001.NEF").unwrap();
+        assert!(score.sharpness < 0.3);
+    }
     
     #[test]
     fn test_quality_score_calculation() {
@@ -217,6 +1245,69 @@ mod tests {
         assert!(!bad.meets_minimum_quality());
     }
     
+    /// Custom metric: how much of the frame is warm (red-dominant).
+    struct WarmthMetric;
+
+    impl QualityMetric for WarmthMetric {
+        fn name(&self) -> &str { "warmth" }
+        fn compute(&self, input: &MetricInput) -> f64 {
+            let rgb = input.image.to_rgb8();
+            rgb.pixels().filter(|p| p[0] > p[2]).count() as f64 / rgb.pixels().len() as f64
+        }
+        fn default_weight(&self) -> f64 { 1.0 }
+    }
+
+    #[test]
+    fn test_registered_metric_scored_and_weighted() {
+        let warm = RgbImage::from_pixel(64, 64, Rgb([200, 120, 60]));
+        let mut data = Vec::new();
+        JpegEncoder::new_with_quality(&mut data, 90).encode_image(&warm).unwrap();
+
+        let mut analyzer = QualityAnalyzer::default();
+        let before = analyzer.analyze_bytes(&data).unwrap();
+        analyzer.register_metric(Box::new(WarmthMetric));
+        let after = analyzer.analyze_bytes(&data).unwrap();
+
+        assert_eq!(before.metric("warmth"), None);
+        assert_eq!(after.metric("warmth"), Some(1.0));
+        assert_eq!(after.metric(metric_names::SHARPNESS), Some(after.sharpness));
+        assert_eq!(analyzer.metric_names().len(), 5);
+        assert!(after.overall_score > before.overall_score);
+
+        // Weight 0 switches it off again
+        analyzer.set_weights(WeightProfile::new(BTreeMap::from([("warmth".to_string(), 0.0)])).unwrap());
+        let off = analyzer.analyze_bytes(&data).unwrap();
+        assert!((off.overall_score - before.overall_score).abs() < 1e-9);
+        assert_eq!(analyzer.weights().weights["sharpness"], 0.4);
+    }
+
+    #[test]
+    fn test_profiles_rank_differently() {
+        // Tack sharp but badly framed vs soft but beautifully framed
+        let sharp = BTreeMap::from([
+            ("sharpness".to_string(), 0.95), ("exposure".to_string(), 0.7),
+            ("composition".to_string(), 0.3), ("technical_quality".to_string(), 0.8),
+        ]);
+        let framed = BTreeMap::from([
+            ("sharpness".to_string(), 0.6), ("exposure".to_string(), 0.7),
+            ("composition".to_string(), 0.95), ("technical_quality".to_string(), 0.8),
+        ]);
+        let analyzer = QualityAnalyzer::default();
+        let rank = |profile: &WeightProfile| analyzer.compare_scores(
+            &QualityScore::from_metrics(sharp.clone(), profile),
+            &QualityScore::from_metrics(framed.clone(), profile),
+        );
+
+        assert_eq!(rank(&WeightProfile::sports()), std::cmp::Ordering::Greater);
+        assert_eq!(rank(&WeightProfile::portrait()), std::cmp::Ordering::Less);
+    }
+
+    #[test]
+    fn test_invalid_weights_rejected() {
+        assert!(WeightProfile::new(BTreeMap::from([("sharpness".to_string(), -0.1)])).is_err());
+        assert!(WeightProfile::new(BTreeMap::from([("sharpness".to_string(), f64::NAN)])).is_err());
+    }
+
     #[test]
     fn test_score_comparison() {
         let analyzer = QualityAnalyzer::default();
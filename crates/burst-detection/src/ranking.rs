@@ -0,0 +1,242 @@
+//! Best-pick suggestion within a burst.
+//!
+//! A 20 fps burst of a goal celebration is 40 near-identical frames; the
+//! photographer keeps one or two. Ranking scores every frame with the
+//! `QualityAnalyzer` and orders them best first, so the UI can highlight the
+//! suggested pick and let the photographer confirm it with one keystroke.
+//!
+//! Order: `compare_scores` (overall score, then sharpness), then position in
+//! the burst — on a true tie the earlier frame wins, since it's closer to the
+//! moment the shutter was pressed for. Frames that fail to score (corrupt
+//! preview, unreadable file) rank last rather than failing the burst.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::burst::BurstGroup;
+use crate::exif::ExifData;
+use crate::quality::{QualityAnalyzer, QualityScore};
+
+/// One frame's place in its burst's ranking.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedFrame {
+    /// Position in the burst (capture order)
+    pub burst_index: usize,
+    pub file_path: PathBuf,
+    /// `None` if the frame couldn't be scored
+    pub score: Option<QualityScore>,
+}
+
+/// Frames of one burst, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurstRanking {
+    pub burst_id: String,
+    pub frames: Vec<RankedFrame>,
+}
+
+impl BurstRanking {
+    /// The suggested pick, if the burst has any frames.
+    pub fn best(&self) -> Option<&RankedFrame> {
+        self.frames.first()
+    }
+
+    /// Burst index of the suggested pick.
+    pub fn suggested_index(&self) -> Option<usize> {
+        self.best().map(|frame| frame.burst_index)
+    }
+
+    /// Burst indices, best first.
+    pub fn order(&self) -> Vec<usize> {
+        self.frames.iter().map(|frame| frame.burst_index).collect()
+    }
+}
+
+/// Scores and ranks the frames of bursts.
+pub struct BurstRanker {
+    analyzer: QualityAnalyzer,
+}
+
+impl BurstRanker {
+    pub fn new(analyzer: QualityAnalyzer) -> Self {
+        Self { analyzer }
+    }
+
+    pub fn analyzer(&self) -> &QualityAnalyzer {
+        &self.analyzer
+    }
+
+    /// Score every frame of `burst` (in parallel) and rank them.
+    pub fn rank(&self, burst: &BurstGroup) -> BurstRanking {
+        let frames = burst.images.par_iter()
+            .enumerate()
+            .map(|(burst_index, image)| RankedFrame {
+                burst_index,
+                file_path: image.file_path.clone(),
+                score: self.score(image),
+            })
+            .collect();
+        self.rank_frames(burst.id.clone(), frames)
+    }
+
+    /// Like `rank`, but frames with a score in `known` (e.g. from the
+    /// background scoring job) aren't re-analyzed.
+    pub fn rank_with_scores(&self, burst: &BurstGroup, known: &HashMap<PathBuf, QualityScore>) -> BurstRanking {
+        let frames = burst.images.par_iter()
+            .enumerate()
+            .map(|(burst_index, image)| RankedFrame {
+                burst_index,
+                file_path: image.file_path.clone(),
+                score: known.get(&image.file_path).cloned().or_else(|| self.score(image)),
+            })
+            .collect();
+        self.rank_frames(burst.id.clone(), frames)
+    }
+
+    /// Rank every burst in `bursts`.
+    pub fn rank_all(&self, bursts: &[BurstGroup]) -> Vec<BurstRanking> {
+        bursts.iter().map(|burst| self.rank(burst)).collect()
+    }
+
+    /// Order already-scored frames best first.
+    pub fn rank_frames(&self, burst_id: String, mut frames: Vec<RankedFrame>) -> BurstRanking {
+        frames.sort_by(|a, b| self.compare_frames(a, b));
+        BurstRanking { burst_id, frames }
+    }
+
+    fn score(&self, image: &ExifData) -> Option<QualityScore> {
+        match self.analyzer.analyze_exif(image) {
+            Ok(score) => Some(score),
+            Err(e) => {
+                eprintln!("Could not score {}: {:#}", image.file_path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Better frame first: score (scored beat unscored), then burst position.
+    fn compare_frames(&self, a: &RankedFrame, b: &RankedFrame) -> Ordering {
+        let by_score = match (&a.score, &b.score) {
+            (Some(sa), Some(sb)) => self.analyzer.compare_scores(sb, sa),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        by_score.then(a.burst_index.cmp(&b.burst_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exif::DriveMode;
+    use crate::quality::PreviewSource;
+    use anyhow::Result;
+    use chrono::{Duration, TimeZone, Utc};
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{GrayImage, Luma};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    fn frame(path: &str, ms: i64) -> ExifData {
+        let base = Utc.with_ymd_and_hms(2025, 6, 21, 15, 0, 0).unwrap();
+        ExifData::new(PathBuf::from(path), "A".to_string(), DriveMode::ContinuousHigh, base + Duration::milliseconds(ms))
+    }
+
+    fn ranked(burst_index: usize, score: Option<QualityScore>) -> RankedFrame {
+        RankedFrame { burst_index, file_path: PathBuf::from(format!("/b/{}.NEF", burst_index)), score }
+    }
+
+    /// Previews keyed by path; unknown paths fail like a corrupt file.
+    struct FakePreviews(HashMap<PathBuf, Vec<u8>>);
+
+    impl PreviewSource for FakePreviews {
+        fn preview_jpeg(&self, path: &Path) -> Result<Vec<u8>> {
+            self.0.get(path).cloned().ok_or_else(|| anyhow::anyhow!("no preview"))
+        }
+    }
+
+    /// 4px checkerboard subject on a plain backdrop, optionally defocused.
+    fn textured_jpeg(blur: f32) -> Vec<u8> {
+        let img = GrayImage::from_fn(128, 128, |x, y| {
+            let subject = (32..96).contains(&x) && (32..96).contains(&y);
+            Luma([if !subject { 120 } else if (x / 4 + y / 4) % 2 == 0 { 60 } else { 190 }])
+        });
+        let img = if blur > 0.0 { image::imageops::blur(&img, blur) } else { img };
+        let mut data = Vec::new();
+        JpegEncoder::new_with_quality(&mut data, 90).encode_image(&img).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_sharpest_frame_suggested() {
+        let images = vec![frame("/b/1.NEF", 0), frame("/b/2.NEF", 50), frame("/b/3.NEF", 100)];
+        let previews = FakePreviews(HashMap::from([
+            (images[0].file_path.clone(), textured_jpeg(2.5)),
+            (images[1].file_path.clone(), textured_jpeg(0.0)),
+            (images[2].file_path.clone(), textured_jpeg(1.0)),
+        ]));
+        let burst = BurstGroup::new("burst_1".to_string(), "A".to_string(), images);
+        let ranker = BurstRanker::new(QualityAnalyzer::with_previews(Arc::new(previews)));
+
+        let ranking = ranker.rank(&burst);
+
+        assert_eq!(ranking.order(), vec![1, 2, 0]);
+        assert_eq!(ranking.suggested_index(), Some(1));
+        assert_eq!(ranking.best().unwrap().file_path, PathBuf::from("/b/2.NEF"));
+    }
+
+    #[test]
+    fn test_unscorable_frame_ranks_last() {
+        let images = vec![frame("/b/1.NEF", 0), frame("/b/2.NEF", 50)];
+        let previews = FakePreviews(HashMap::from([(images[1].file_path.clone(), textured_jpeg(3.0))]));
+        let burst = BurstGroup::new("burst_1".to_string(), "A".to_string(), images);
+
+        let ranking = BurstRanker::new(QualityAnalyzer::with_previews(Arc::new(previews))).rank(&burst);
+
+        assert_eq!(ranking.order(), vec![1, 0]);
+        assert!(ranking.frames[1].score.is_none());
+    }
+
+    #[test]
+    fn test_ties_broken_by_sharpness_then_position() {
+        let ranker = BurstRanker::new(QualityAnalyzer::default());
+        // Same overall score; frame 2 is sharper. Frames 0 and 3 are identical.
+        let even = QualityScore::new(0.7, 0.7, 0.5, 0.7);
+        let mut sharper = even.clone();
+        sharper.sharpness = 0.9;
+
+        let ranking = ranker.rank_frames("burst_1".to_string(), vec![
+            ranked(3, Some(even.clone())),
+            ranked(1, None),
+            ranked(0, Some(even)),
+            ranked(2, Some(sharper)),
+        ]);
+
+        assert_eq!(ranking.order(), vec![2, 0, 3, 1]);
+    }
+
+    #[test]
+    fn test_known_scores_not_reanalyzed() {
+        let images = vec![frame("/b/1.NEF", 0), frame("/b/2.NEF", 50)];
+        // No previews at all: anything not in `known` fails to score
+        let ranker = BurstRanker::new(QualityAnalyzer::with_previews(Arc::new(FakePreviews(HashMap::new()))));
+        let known = HashMap::from([(images[1].file_path.clone(), QualityScore::new(0.8, 0.7, 0.5, 0.7))]);
+        let burst = BurstGroup::new("burst_1".to_string(), "A".to_string(), images);
+
+        let ranking = ranker.rank_with_scores(&burst, &known);
+
+        assert_eq!(ranking.order(), vec![1, 0]);
+        assert!(ranking.frames[0].score.is_some());
+        assert!(ranking.frames[1].score.is_none());
+    }
+
+    #[test]
+    fn test_empty_burst_has_no_pick() {
+        let ranker = BurstRanker::new(QualityAnalyzer::default());
+        let ranking = ranker.rank_frames("burst_1".to_string(), Vec::new());
+        assert_eq!(ranking.suggested_index(), None);
+    }
+}
@@ -0,0 +1,317 @@
+//! Background quality scoring for a whole session.
+//!
+//! Scoring decodes a Preview-tier JPEG per frame; a 7,000-frame wedding takes
+//! minutes even spread across every core. So it runs on its own thread after
+//! import, reports progress as it goes, hands each finished batch to the
+//! caller to persist, and can be cancelled when the user imports another
+//! folder or closes the session.
+//!
+//! Frames are keyed by file path plus cache hash (size + mtime). A frame whose
+//! hash matches an already-stored score is skipped, so re-opening a session
+//! only scores files that are new or were edited since.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::exif::ExifData;
+use crate::quality::{QualityAnalyzer, QualityScore};
+
+/// Frames scored between cancellation checks and persistence calls.
+const BATCH_SIZE: usize = 32;
+
+/// One frame to score.
+#[derive(Debug, Clone)]
+pub struct ScoringItem {
+    pub image: ExifData,
+    /// Changes whenever the file does; see module docs
+    pub cache_hash: String,
+}
+
+/// A finished score, ready to persist.
+#[derive(Debug, Clone)]
+pub struct ScoredImage {
+    pub file_path: PathBuf,
+    pub cache_hash: String,
+    pub score: QualityScore,
+}
+
+/// Progress of a scoring job.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScoringProgress {
+    pub total: usize,
+    pub scored: usize,
+    /// Already scored and unchanged
+    pub skipped: usize,
+    pub failed: usize,
+    pub is_cancelled: bool,
+    pub is_finished: bool,
+}
+
+impl ScoringProgress {
+    pub fn completion_percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            ((self.scored + self.skipped + self.failed) as f64 / self.total as f64) * 100.0
+        }
+    }
+}
+
+/// Handle to a running scoring job. Dropping it cancels the job.
+pub struct ScoringJob {
+    cancel_flag: Arc<AtomicBool>,
+    progress: Arc<Mutex<ScoringProgress>>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+}
+
+impl ScoringJob {
+    /// Score `items` on a background thread.
+    ///
+    /// Items whose path maps to the same cache hash in `already_scored` are
+    /// skipped. Each finished batch goes to `on_batch` (on the job thread, in
+    /// order); if it fails the job stops, since scores that can't be stored
+    /// would only be recomputed next time. `on_progress` is called after every
+    /// batch and once more when the job ends.
+    pub fn start<B, P>(
+        analyzer: QualityAnalyzer,
+        items: Vec<ScoringItem>,
+        already_scored: &HashMap<PathBuf, String>,
+        mut on_batch: B,
+        on_progress: P,
+    ) -> Self
+    where
+        B: FnMut(&[ScoredImage]) -> Result<()> + Send + 'static,
+        P: Fn(&ScoringProgress) + Send + 'static,
+    {
+        let total = items.len();
+        let pending: Vec<ScoringItem> = items.into_iter()
+            .filter(|item| already_scored.get(&item.image.file_path) != Some(&item.cache_hash))
+            .collect();
+
+        let progress = Arc::new(Mutex::new(ScoringProgress {
+            total,
+            skipped: total - pending.len(),
+            ..ScoringProgress::default()
+        }));
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let thread_progress = Arc::clone(&progress);
+        let thread_cancel = Arc::clone(&cancel_flag);
+        let thread_handle = thread::spawn(move || {
+            let report = |update: &dyn Fn(&mut ScoringProgress)| {
+                let snapshot = {
+                    let mut p = thread_progress.lock().unwrap();
+                    update(&mut p);
+                    p.clone()
+                };
+                on_progress(&snapshot);
+            };
+
+            for batch in pending.chunks(BATCH_SIZE) {
+                if thread_cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let results: Vec<Option<ScoredImage>> = batch.par_iter()
+                    .map(|item| {
+                        if thread_cancel.load(Ordering::Relaxed) {
+                            return None;
+                        }
+                        match analyzer.analyze_exif(&item.image) {
+                            Ok(score) => Some(ScoredImage {
+                                file_path: item.image.file_path.clone(),
+                                cache_hash: item.cache_hash.clone(),
+                                score,
+                            }),
+                            Err(e) => {
+                                eprintln!("Could not score {}: {:#}", item.image.file_path.display(), e);
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+
+                // Items skipped by a mid-batch cancel aren't failures
+                if thread_cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let scored: Vec<ScoredImage> = results.into_iter().flatten().collect();
+                let failed = batch.len() - scored.len();
+                if let Err(e) = on_batch(&scored) {
+                    eprintln!("Failed to store quality scores, stopping scoring job: {:#}", e);
+                    break;
+                }
+                report(&|p| {
+                    p.scored += scored.len();
+                    p.failed += failed;
+                });
+            }
+
+            report(&|p| {
+                p.is_cancelled = thread_cancel.load(Ordering::Relaxed);
+                p.is_finished = true;
+            });
+        });
+
+        Self {
+            cancel_flag,
+            progress,
+            thread_handle: Some(thread_handle),
+        }
+    }
+
+    /// Ask the job to stop after the frames it is currently scoring.
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn progress(&self) -> ScoringProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.progress().is_finished
+    }
+
+    /// Block until the job ends and return its final progress.
+    pub fn wait(mut self) -> ScoringProgress {
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        self.progress()
+    }
+}
+
+impl Drop for ScoringJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exif::DriveMode;
+    use crate::quality::PreviewSource;
+    use chrono::Utc;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{GrayImage, Luma};
+    use std::path::Path;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Serves a checkerboard for every path except `*.bad`; counts requests
+    /// and can be made to stall so a test can cancel mid-job.
+    struct FakePreviews {
+        jpeg: Vec<u8>,
+        requests: Arc<AtomicUsize>,
+        delay: Duration,
+    }
+
+    impl PreviewSource for FakePreviews {
+        fn preview_jpeg(&self, path: &Path) -> Result<Vec<u8>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            if path.extension().is_some_and(|e| e == "bad") {
+                anyhow::bail!("corrupt preview");
+            }
+            Ok(self.jpeg.clone())
+        }
+    }
+
+    fn analyzer(delay: Duration) -> (QualityAnalyzer, Arc<AtomicUsize>) {
+        let img = GrayImage::from_fn(64, 64, |x, y| Luma([if (x / 4 + y / 4) % 2 == 0 { 60 } else { 190 }]));
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, 90).encode_image(&img).unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let previews = FakePreviews { jpeg, requests: Arc::clone(&requests), delay };
+        (QualityAnalyzer::with_previews(Arc::new(previews)), requests)
+    }
+
+    fn items(n: usize) -> Vec<ScoringItem> {
+        (0..n).map(|i| ScoringItem {
+            image: ExifData::new(PathBuf::from(format!("/card/DSC_{:04}.NEF", i)), "A".to_string(), DriveMode::Single, Utc::now()),
+            cache_hash: format!("hash{}", i),
+        }).collect()
+    }
+
+    #[test]
+    fn test_scores_everything_and_reports_progress() {
+        let (analyzer, _) = analyzer(Duration::ZERO);
+        let mut input = items(70);
+        input[5].image.file_path = PathBuf::from("/card/DSC_0005.bad");
+        let (batch_tx, batch_rx) = mpsc::channel();
+        let (progress_tx, progress_rx) = mpsc::channel();
+
+        let job = ScoringJob::start(
+            analyzer,
+            input,
+            &HashMap::new(),
+            move |batch| { batch_tx.send(batch.len()).unwrap(); Ok(()) },
+            move |p| progress_tx.send(p.clone()).unwrap(),
+        );
+        let done = job.wait();
+
+        assert_eq!(batch_rx.iter().collect::<Vec<_>>(), vec![31, 32, 6]);
+        assert_eq!((done.scored, done.failed, done.skipped), (69, 1, 0));
+        assert!(done.is_finished && !done.is_cancelled);
+        assert_eq!(done.completion_percentage(), 100.0);
+        let updates: Vec<ScoringProgress> = progress_rx.iter().collect();
+        assert_eq!(updates.len(), 4); // 3 batches + final
+        assert!(updates.last().unwrap().is_finished);
+    }
+
+    #[test]
+    fn test_unchanged_files_skipped() {
+        let (analyzer, requests) = analyzer(Duration::ZERO);
+        let input = items(10);
+        let mut already_scored: HashMap<PathBuf, String> = input[..6].iter()
+            .map(|item| (item.image.file_path.clone(), item.cache_hash.clone()))
+            .collect();
+        // Edited since it was scored
+        already_scored.insert(input[0].image.file_path.clone(), "stale".to_string());
+
+        let done = ScoringJob::start(analyzer, input, &already_scored, |_| Ok(()), |_| {}).wait();
+
+        assert_eq!((done.scored, done.skipped), (5, 5));
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_cancel_stops_job() {
+        let (analyzer, requests) = analyzer(Duration::from_millis(20));
+        let job = ScoringJob::start(analyzer, items(500), &HashMap::new(), |_| Ok(()), |_| {});
+        thread::sleep(Duration::from_millis(30));
+
+        job.cancel();
+        let done = job.wait();
+
+        assert!(done.is_cancelled && done.is_finished);
+        assert!(done.scored < 500);
+        assert!(requests.load(Ordering::SeqCst) < 500);
+    }
+
+    #[test]
+    fn test_storage_failure_stops_job() {
+        let (analyzer, requests) = analyzer(Duration::ZERO);
+        let done = ScoringJob::start(
+            analyzer,
+            items(100),
+            &HashMap::new(),
+            |_| anyhow::bail!("disk full"),
+            |_| {},
+        ).wait();
+
+        assert_eq!(done.scored, 0);
+        assert!(done.is_finished);
+        assert_eq!(requests.load(Ordering::SeqCst), BATCH_SIZE);
+    }
+}
@@ -0,0 +1,331 @@
+//! Near-duplicate detection with perceptual hashes.
+//!
+//! Burst detection only sees what EXIF says: a photographer hammering the
+//! shutter in single-shot mode produces ten near-identical "singles", and a
+//! body whose drive mode we can't decode never produces bursts at all. Both
+//! look like bursts to the eye, so we compare the pictures themselves.
+//!
+//! Each frame gets two 64-bit hashes from its Micro-tier thumbnail (300px —
+//! the hashes only look at an 8x8 / 9x8 / 32x32 downsample anyway):
+//! - **dHash**: brightness gradient between horizontal neighbours. Cheap and
+//!   robust to exposure shifts.
+//! - **pHash**: sign of the low-frequency DCT coefficients against their
+//!   median. Robust to small shifts and recompression; catches what dHash
+//!   misses on low-contrast frames.
+//!
+//! Two frames are similar when both Hamming distances are within
+//! `SimilarityConfig::max_distance`. Grouping runs per camera body on
+//! time-sorted frames: a frame joins the current group when it's similar to,
+//! and within `max_gap_ms` of, the group's previous frame. Chaining (rather
+//! than comparing with the first frame) follows a subject that drifts slowly
+//! across a sequence, the same way burst gaps are measured frame to frame.
+//! `BurstDetector` reuses the same clustering as its similarity strategy.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::burst::{sort_by_capture_time, stable_group_id};
+use crate::exif::ExifData;
+
+/// dHash and pHash of one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerceptualHash {
+    pub dhash: u64,
+    pub phash: u64,
+}
+
+impl PerceptualHash {
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let luma = image.to_luma8();
+        Self {
+            dhash: dhash(&luma),
+            phash: phash(&luma),
+        }
+    }
+
+    /// Hash an encoded image (normally a Micro-tier JPEG).
+    pub fn from_jpeg(data: &[u8]) -> Result<Self> {
+        let image = image::load_from_memory(data).context("Failed to decode image")?;
+        Ok(Self::from_image(&image))
+    }
+
+    /// The larger of the two Hamming distances (0 = identical, 64 = inverse).
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        let d = (self.dhash ^ other.dhash).count_ones();
+        let p = (self.phash ^ other.phash).count_ones();
+        d.max(p)
+    }
+}
+
+/// Difference hash: bit set where a pixel is brighter than its right-hand
+/// neighbour, on a 9x8 downsample.
+pub fn dhash(luma: &GrayImage) -> u64 {
+    let small = image::imageops::resize(luma, 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    hash
+}
+
+/// DCT hash: bit set where an 8x8 low-frequency DCT coefficient of a 32x32
+/// downsample is above the median of the 63 AC coefficients (the DC term is
+/// left out so overall brightness doesn't skew it; with an odd count the
+/// median is the middle value).
+pub fn phash(luma: &GrayImage) -> u64 {
+    const N: usize = 32;
+    const K: usize = 8;
+    let small = image::imageops::resize(luma, N as u32, N as u32, FilterType::Triangle);
+
+    // cos_table[k][n] = cos(pi * (2n + 1) * k / 2N), DCT-II basis
+    let mut cos_table = [[0.0f64; N]; K];
+    for (k, row) in cos_table.iter_mut().enumerate() {
+        for (n, value) in row.iter_mut().enumerate() {
+            *value = (std::f64::consts::PI * (2 * n + 1) as f64 * k as f64 / (2 * N) as f64).cos();
+        }
+    }
+
+    // Rows first, then columns; only the K lowest frequencies are needed
+    let mut rows = [[0.0f64; K]; N];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (k, coeff) in row.iter_mut().enumerate() {
+            *coeff = (0..N).map(|x| small.get_pixel(x as u32, y as u32)[0] as f64 * cos_table[k][x]).sum();
+        }
+    }
+    let mut coeffs = [0.0f64; K * K];
+    for v in 0..K {
+        for u in 0..K {
+            coeffs[v * K + u] = (0..N).map(|y| rows[y][u] * cos_table[v][y]).sum();
+        }
+    }
+
+    let mut ac: Vec<f64> = coeffs[1..].to_vec();
+    ac.sort_by(|a, b| a.total_cmp(b));
+    let median = ac[ac.len() / 2];
+
+    coeffs.iter().fold(0u64, |hash, &c| (hash << 1) | (c > median) as u64)
+}
+
+/// Supplies the small JPEG perceptual hashes are computed from (normally the
+/// Micro tier).
+pub trait MicroSource: Send + Sync {
+    fn micro_jpeg(&self, path: &Path) -> Result<Vec<u8>>;
+}
+
+/// Hash many frames in parallel. Results are in input order.
+pub fn hash_images(source: &dyn MicroSource, paths: &[PathBuf]) -> Vec<(PathBuf, Result<PerceptualHash>)> {
+    paths.par_iter()
+        .map(|path| {
+            let hash = source.micro_jpeg(path).and_then(|jpeg| PerceptualHash::from_jpeg(&jpeg));
+            (path.clone(), hash)
+        })
+        .collect()
+}
+
+/// Thresholds for grouping near-duplicates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityConfig {
+    /// Max Hamming distance (of 64 bits, see `PerceptualHash::distance`)
+    /// for two frames to count as the same picture.
+    pub max_distance: u32,
+    /// Max time between consecutive frames of a group (milliseconds).
+    pub max_gap_ms: i64,
+    /// Minimum frames for a group; smaller clusters stay ungrouped.
+    pub min_frames: usize,
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 10,
+            // Single-shot mode fired rapidly is slower than any drive mode
+            max_gap_ms: 3000,
+            min_frames: 2,
+        }
+    }
+}
+
+/// Visually similar frames from one body, close together in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityGroup {
+    /// Content-derived like burst IDs (`similar_{hash}`)
+    pub id: String,
+    pub camera_serial: String,
+    /// Sorted by capture time
+    pub images: Vec<ExifData>,
+}
+
+/// Group near-duplicates among `images`, per camera body.
+///
+/// Frames without an entry in `hashes` are never grouped. Returns the groups
+/// (ordered by camera, then time) and the frames left ungrouped.
+pub fn group_similar(
+    images: Vec<ExifData>,
+    hashes: &HashMap<PathBuf, PerceptualHash>,
+    config: &SimilarityConfig,
+) -> (Vec<SimilarityGroup>, Vec<ExifData>) {
+    let mut partitions: BTreeMap<String, Vec<ExifData>> = BTreeMap::new();
+    for image in images {
+        partitions.entry(image.serial_number.clone()).or_default().push(image);
+    }
+
+    let mut groups = Vec::new();
+    let mut ungrouped = Vec::new();
+    for (serial, mut camera_images) in partitions {
+        sort_by_capture_time(&mut camera_images);
+        for cluster in cluster_similar(camera_images, hashes, config) {
+            if cluster.len() >= config.min_frames.max(2) {
+                groups.push(SimilarityGroup {
+                    id: stable_group_id("similar", &serial, &cluster[0], None),
+                    camera_serial: serial.clone(),
+                    images: cluster,
+                });
+            } else {
+                ungrouped.extend(cluster);
+            }
+        }
+    }
+
+    (groups, ungrouped)
+}
+
+/// Split time-sorted frames of one body into runs of similar frames (see
+/// module docs). Every frame lands in exactly one run; unhashed frames are
+/// runs of one.
+pub(crate) fn cluster_similar(
+    images: Vec<ExifData>,
+    hashes: &HashMap<PathBuf, PerceptualHash>,
+    config: &SimilarityConfig,
+) -> Vec<Vec<ExifData>> {
+    let mut clusters: Vec<Vec<ExifData>> = Vec::new();
+    for image in images {
+        let joins_previous = clusters.last()
+            .and_then(|cluster| cluster.last())
+            .is_some_and(|prev| {
+                let gap = image.capture_time.signed_duration_since(prev.capture_time).num_milliseconds();
+                let distance = hashes.get(&prev.file_path)
+                    .zip(hashes.get(&image.file_path))
+                    .map(|(a, b)| a.distance(b));
+                gap <= config.max_gap_ms && distance.is_some_and(|d| d <= config.max_distance)
+            });
+
+        if joins_previous {
+            clusters.last_mut().unwrap().push(image);
+        } else {
+            clusters.push(vec![image]);
+        }
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exif::DriveMode;
+    use chrono::{Duration, TimeZone, Utc};
+    use image::Luma;
+
+    fn frame(path: &str, serial: &str, ms: i64) -> ExifData {
+        let base = Utc.with_ymd_and_hms(2025, 6, 21, 15, 0, 0).unwrap();
+        ExifData::new(PathBuf::from(path), serial.to_string(), DriveMode::Single, base + Duration::milliseconds(ms))
+    }
+
+    /// A bright disc on a gradient, centred at (`cx`, 60).
+    fn scene(cx: f32, brightness: i16) -> GrayImage {
+        GrayImage::from_fn(160, 120, |x, y| {
+            let (dx, dy) = (x as f32 - cx, y as f32 - 60.0);
+            let base = if dx * dx + dy * dy < 900.0 { 220 } else { 40 + (x / 4) as i16 };
+            Luma([(base + brightness).clamp(0, 255) as u8])
+        })
+    }
+
+    fn other_scene() -> GrayImage {
+        GrayImage::from_fn(160, 120, |x, y| Luma([if (x / 20 + y / 20) % 2 == 0 { 30 } else { 200 }]))
+    }
+
+    fn hash(img: &GrayImage) -> PerceptualHash {
+        PerceptualHash::from_image(&DynamicImage::ImageLuma8(img.clone()))
+    }
+
+    #[test]
+    fn test_near_duplicates_are_close_and_different_scenes_far() {
+        let original = hash(&scene(60.0, 0));
+        let nudged = hash(&scene(63.0, 12));
+        let different = hash(&other_scene());
+
+        assert_eq!(original.distance(&original), 0);
+        assert!(original.distance(&nudged) <= 6, "distance {}", original.distance(&nudged));
+        assert!(original.distance(&different) > 20, "distance {}", original.distance(&different));
+    }
+
+    #[test]
+    fn test_phash_splits_ac_coefficients_at_their_median() {
+        // Of 63 distinct AC coefficients, 31 lie above the middle one; the DC
+        // term (top bit) is excluded from the count
+        let bits = phash(&scene(60.0, 0));
+        assert_eq!((bits & !(1 << 63)).count_ones(), 31);
+    }
+
+    #[test]
+    fn test_hash_survives_jpeg_round_trip() {
+        let img = scene(80.0, 0);
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 70).encode_image(&img).unwrap();
+
+        assert!(PerceptualHash::from_jpeg(&jpeg).unwrap().distance(&hash(&img)) <= 4);
+    }
+
+    #[test]
+    fn test_groups_similar_frames_within_window() {
+        let images = vec![
+            frame("/a/1.JPG", "A", 0),
+            frame("/a/2.JPG", "A", 800),
+            frame("/a/3.JPG", "A", 1_500),
+            frame("/a/4.JPG", "A", 2_000),    // different scene
+            frame("/a/5.JPG", "A", 60_000),   // same scene, a minute later
+        ];
+        let hashes = HashMap::from([
+            (PathBuf::from("/a/1.JPG"), hash(&scene(60.0, 0))),
+            (PathBuf::from("/a/2.JPG"), hash(&scene(62.0, 5))),
+            (PathBuf::from("/a/3.JPG"), hash(&scene(64.0, 10))),
+            (PathBuf::from("/a/4.JPG"), hash(&other_scene())),
+            (PathBuf::from("/a/5.JPG"), hash(&scene(60.0, 0))),
+        ]);
+
+        let (groups, ungrouped) = group_similar(images, &hashes, &SimilarityConfig::default());
+
+        assert_eq!(groups.len(), 1);
+        let paths: Vec<&Path> = groups[0].images.iter().map(|img| img.file_path.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("/a/1.JPG"), Path::new("/a/2.JPG"), Path::new("/a/3.JPG")]);
+        assert!(groups[0].id.starts_with("similar_"));
+        assert_eq!(ungrouped.len(), 2);
+    }
+
+    #[test]
+    fn test_bodies_and_unhashed_frames_not_grouped() {
+        let same = hash(&scene(60.0, 0));
+        let images = vec![
+            frame("/a/1.JPG", "A", 0),
+            frame("/b/1.JPG", "B", 100),
+            frame("/a/2.JPG", "A", 200), // no hash
+        ];
+        let hashes = HashMap::from([
+            (PathBuf::from("/a/1.JPG"), same),
+            (PathBuf::from("/b/1.JPG"), same),
+        ]);
+
+        let (groups, ungrouped) = group_similar(images, &hashes, &SimilarityConfig::default());
+
+        assert!(groups.is_empty());
+        assert_eq!(ungrouped.len(), 3);
+    }
+}
@@ -0,0 +1,200 @@
+//! Cross-camera timeline: one chronological story from every body.
+//!
+//! `BurstResult` is organized per camera — detection runs on each body's
+//! partition independently. A multi-shooter event needs the opposite view:
+//! the ceremony from both angles, interleaved in the order things happened.
+//!
+//! The timeline treats each burst as a single entry positioned at its first
+//! frame, so a burst from body A is never split by frames from body B that
+//! fall inside it. Ordering uses corrected capture time (after per-body clock
+//! offsets), with camera serial and then burst ID / file path as tie-breakers,
+//! so the same result always produces the same positions.
+//!
+//! session-db's `load_images` mirrors this ordering in SQL; keep them in sync.
+
+use std::cmp::Ordering;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+
+use crate::burst::{BurstGroup, BurstResult};
+use crate::exif::ExifData;
+
+/// One position on the timeline: a whole burst, or a single frame.
+#[derive(Debug, Clone, Copy)]
+pub enum TimelineEntry<'a> {
+    Burst(&'a BurstGroup),
+    Single(&'a ExifData),
+}
+
+impl<'a> TimelineEntry<'a> {
+    /// Corrected capture time of the entry's first frame.
+    pub fn start_time(&self) -> DateTime<Utc> {
+        match self {
+            TimelineEntry::Burst(burst) => burst.images.first()
+                .map(|img| img.capture_time)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC),
+            TimelineEntry::Single(image) => image.capture_time,
+        }
+    }
+
+    /// Serial of the body that shot this entry.
+    pub fn camera_serial(&self) -> &'a str {
+        match self {
+            TimelineEntry::Burst(burst) => &burst.camera_serial,
+            TimelineEntry::Single(image) => &image.serial_number,
+        }
+    }
+
+    /// Burst ID, or the file path of a single.
+    pub fn key(&self) -> String {
+        match self {
+            TimelineEntry::Burst(burst) => burst.id.clone(),
+            TimelineEntry::Single(image) => image.file_path.display().to_string(),
+        }
+    }
+
+    /// Frames in this entry, in capture order.
+    pub fn images(&self) -> &'a [ExifData] {
+        match self {
+            TimelineEntry::Burst(burst) => &burst.images,
+            TimelineEntry::Single(image) => std::slice::from_ref(*image),
+        }
+    }
+
+    fn cmp_position(&self, other: &Self) -> Ordering {
+        self.start_time().cmp(&other.start_time())
+            .then_with(|| self.camera_serial().cmp(other.camera_serial()))
+            .then_with(|| self.key().cmp(&other.key()))
+    }
+}
+
+/// Bursts and singles from all bodies, interleaved by corrected capture time.
+#[derive(Debug, Clone)]
+pub struct Timeline<'a> {
+    entries: Vec<TimelineEntry<'a>>,
+}
+
+impl<'a> Timeline<'a> {
+    /// Build the timeline for a detection result.
+    pub fn new(result: &'a BurstResult) -> Self {
+        let mut entries: Vec<TimelineEntry<'a>> = result.bursts.iter()
+            .map(TimelineEntry::Burst)
+            .chain(result.singles.iter().map(TimelineEntry::Single))
+            .collect();
+        entries.sort_by(|a, b| a.cmp_position(b));
+        Self { entries }
+    }
+
+    /// Entries in chronological order; an entry's index is its position.
+    pub fn entries(&self) -> &[TimelineEntry<'a>] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Position of a burst on the timeline.
+    pub fn position_of_burst(&self, burst_id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| matches!(entry, TimelineEntry::Burst(b) if b.id == burst_id))
+    }
+
+    /// Position of the entry containing an image (its burst, or the single itself).
+    pub fn position_of_image(&self, file_path: &Path) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.images().iter().any(|img| img.file_path == file_path))
+    }
+
+    /// Every frame in timeline order (burst frames stay contiguous).
+    pub fn images(&self) -> impl Iterator<Item = &'a ExifData> + '_ {
+        self.entries.iter().flat_map(|entry| entry.images().iter())
+    }
+}
+
+impl BurstResult {
+    /// Chronological view across all camera bodies.
+    pub fn timeline(&self) -> Timeline<'_> {
+        Timeline::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burst::BurstDetector;
+    use crate::exif::DriveMode;
+    use chrono::{Duration, TimeZone};
+    use std::path::PathBuf;
+
+    fn frame(path: &str, serial: &str, drive_mode: DriveMode, ms: i64) -> ExifData {
+        let base = Utc.with_ymd_and_hms(2025, 6, 21, 15, 0, 0).unwrap();
+        ExifData::new(PathBuf::from(path), serial.to_string(), drive_mode, base + Duration::milliseconds(ms))
+    }
+
+    fn two_body_wedding() -> BurstResult {
+        BurstDetector::detect(vec![
+            // Body A: single, then a 3-frame burst at +10s
+            frame("/a/A_0001.NEF", "A", DriveMode::Single, 0),
+            frame("/a/A_0002.NEF", "A", DriveMode::ContinuousHigh, 10_000),
+            frame("/a/A_0003.NEF", "A", DriveMode::ContinuousHigh, 10_100),
+            frame("/a/A_0004.NEF", "A", DriveMode::ContinuousHigh, 10_200),
+            // Body B: single inside A's burst window, single before everything
+            frame("/b/B_0001.NEF", "B", DriveMode::Single, 10_150),
+            frame("/b/B_0002.NEF", "B", DriveMode::Single, -5_000),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn test_interleaves_bodies_chronologically() {
+        let result = two_body_wedding();
+        let timeline = result.timeline();
+
+        let keys: Vec<String> = timeline.entries().iter().map(|e| e.key()).collect();
+        let burst_id = result.bursts[0].id.clone();
+        assert_eq!(keys, vec![
+            "/b/B_0002.NEF".to_string(),
+            "/a/A_0001.NEF".to_string(),
+            burst_id.clone(),
+            "/b/B_0001.NEF".to_string(),
+        ]);
+        assert_eq!(timeline.position_of_burst(&burst_id), Some(2));
+        assert_eq!(timeline.position_of_image(Path::new("/a/A_0003.NEF")), Some(2));
+        assert_eq!(timeline.position_of_image(Path::new("/b/B_0001.NEF")), Some(3));
+    }
+
+    #[test]
+    fn test_burst_frames_stay_contiguous() {
+        let result = two_body_wedding();
+        let paths: Vec<String> = result.timeline().images()
+            .map(|img| img.file_path.display().to_string())
+            .collect();
+
+        assert_eq!(&paths[2..6], &["/a/A_0002.NEF", "/a/A_0003.NEF", "/a/A_0004.NEF", "/b/B_0001.NEF"]);
+    }
+
+    #[test]
+    fn test_positions_stable_regardless_of_input_order() {
+        let mut result = two_body_wedding();
+        let before: Vec<String> = result.timeline().entries().iter().map(|e| e.key()).collect();
+
+        result.singles.reverse();
+        result.bursts.reverse();
+        let after: Vec<String> = result.timeline().entries().iter().map(|e| e.key()).collect();
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_simultaneous_frames_break_ties_by_serial() {
+        let result = BurstDetector::detect(vec![
+            frame("/b/B_0001.NEF", "B", DriveMode::Single, 0),
+            frame("/a/A_0001.NEF", "A", DriveMode::Single, 0),
+        ]).unwrap();
+
+        let serials: Vec<&str> = result.timeline().entries().iter().map(|e| e.camera_serial()).collect();
+        assert_eq!(serials, vec!["A", "B"]);
+    }
+}
@@ -0,0 +1,65 @@
+-- A session database as written by the first release: the v1 tables, but
+-- user_version left at 0, since that release predates schema versioning.
+-- Used by the migration tests; do not change it when the schema changes.
+
+CREATE TABLE images (
+    file_path TEXT PRIMARY KEY,
+    filename TEXT NOT NULL,
+    file_size INTEGER NOT NULL DEFAULT 0,
+    file_mtime INTEGER NOT NULL DEFAULT 0,
+    cache_hash TEXT NOT NULL DEFAULT '',
+    serial_number TEXT NOT NULL DEFAULT '',
+    drive_mode TEXT NOT NULL DEFAULT 'Single',
+    capture_time TEXT NOT NULL DEFAULT '',
+    make TEXT,
+    model TEXT,
+    lens TEXT,
+    focal_length REAL,
+    aperture REAL,
+    shutter_speed TEXT,
+    iso INTEGER,
+    rating INTEGER NOT NULL DEFAULT 0,
+    flag TEXT NOT NULL DEFAULT 'none',
+    color_label TEXT NOT NULL DEFAULT 'none',
+    burst_group_id TEXT,
+    burst_index INTEGER,
+    micro_cached INTEGER NOT NULL DEFAULT 0,
+    preview_cached INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE burst_groups (
+    id TEXT PRIMARY KEY,
+    camera_serial TEXT NOT NULL,
+    frame_count INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL DEFAULT 0,
+    avg_gap_ms REAL NOT NULL DEFAULT 0,
+    estimated_fps REAL NOT NULL DEFAULT 0
+);
+
+CREATE TABLE session_meta (
+    key TEXT PRIMARY KEY,
+    value TEXT
+);
+
+CREATE INDEX idx_images_burst ON images(burst_group_id);
+CREATE INDEX idx_images_serial ON images(serial_number);
+CREATE INDEX idx_images_capture ON images(capture_time);
+CREATE INDEX idx_images_flag ON images(flag);
+CREATE INDEX idx_images_rating ON images(rating);
+
+INSERT INTO images (file_path, filename, file_size, file_mtime, cache_hash, serial_number, drive_mode,
+                    capture_time, make, model, rating, flag, color_label, burst_group_id, burst_index)
+VALUES
+    ('/card/DSC_0001.NEF', 'DSC_0001.NEF', 25000000, 1700000000000, 'a1', 'A', 'ContinuousHigh',
+     '2024-06-01T10:00:00.000+00:00', 'NIKON CORPORATION', 'NIKON Z 9', 0, 'none', 'none', 'burst_A_1', 0),
+    ('/card/DSC_0002.NEF', 'DSC_0002.NEF', 25000000, 1700000000050, 'a2', 'A', 'ContinuousHigh',
+     '2024-06-01T10:00:00.050+00:00', 'NIKON CORPORATION', 'NIKON Z 9', 0, 'reject', 'none', 'burst_A_1', 1),
+    ('/card/DSC_0003.NEF', 'DSC_0003.NEF', 25000000, 1700000000100, 'a3', 'A', 'ContinuousHigh',
+     '2024-06-01T10:00:00.100+00:00', 'NIKON CORPORATION', 'NIKON Z 9', 0, 'none', 'none', 'burst_A_1', 2),
+    ('/card/DSC_0010.NEF', 'DSC_0010.NEF', 24000000, 1700000060000, 'a10', 'A', 'Single',
+     '2024-06-01T10:01:00.000+00:00', 'NIKON CORPORATION', 'NIKON Z 9', 4, 'pick', 'green', NULL, NULL);
+
+INSERT INTO burst_groups (id, camera_serial, frame_count, duration_ms, avg_gap_ms, estimated_fps)
+VALUES ('burst_A_1', 'A', 3, 100, 50.0, 20.0);
+
+INSERT INTO session_meta (key, value) VALUES ('root_folder', '/card');
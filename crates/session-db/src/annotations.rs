@@ -0,0 +1,159 @@
+//! Typed annotation values.
+//!
+//! Flags and color labels used to travel as free-form strings from the UI
+//! into SQLite, so a typo was stored as-is and silently read back as
+//! "unflagged". These enums are what `ImageRecord` holds; they serialize (to
+//! JSON and to SQLite) as the same lowercase names the frontend and the
+//! existing databases use, and the schema's CHECK constraints reject anything
+//! else.
+
+use anyhow::{anyhow, bail, Result};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Highest star rating; 0 means unrated.
+pub const MAX_RATING: i32 = 5;
+
+/// Reject a rating outside 0–`MAX_RATING`.
+pub fn validate_rating(rating: i32) -> Result<()> {
+    if !(0..=MAX_RATING).contains(&rating) {
+        bail!("Rating must be between 0 and {}, got {}", MAX_RATING, rating);
+    }
+    Ok(())
+}
+
+/// Pick/reject flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flag {
+    #[default]
+    None,
+    Pick,
+    Reject,
+}
+
+/// Color label.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorLabel {
+    #[default]
+    None,
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 3] = [Flag::None, Flag::Pick, Flag::Reject];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Flag::None => "none",
+            Flag::Pick => "pick",
+            Flag::Reject => "reject",
+        }
+    }
+}
+
+impl ColorLabel {
+    pub const ALL: [ColorLabel; 6] = [
+        ColorLabel::None,
+        ColorLabel::Red,
+        ColorLabel::Yellow,
+        ColorLabel::Green,
+        ColorLabel::Blue,
+        ColorLabel::Purple,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorLabel::None => "none",
+            ColorLabel::Red => "red",
+            ColorLabel::Yellow => "yellow",
+            ColorLabel::Green => "green",
+            ColorLabel::Blue => "blue",
+            ColorLabel::Purple => "purple",
+        }
+    }
+}
+
+/// `ToSql`, `FromSql`, `FromStr` and `Display` via `as_str`, for an enum with
+/// an `ALL` list.
+macro_rules! string_enum {
+    ($ty:ident, $what:literal) => {
+        impl FromStr for $ty {
+            type Err = anyhow::Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                $ty::ALL.into_iter()
+                    .find(|v| v.as_str() == s)
+                    .ok_or_else(|| anyhow!("Invalid {}: {:?}", $what, s))
+            }
+        }
+
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ToSql for $ty {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                Ok(ToSqlOutput::from(self.as_str()))
+            }
+        }
+
+        impl FromSql for $ty {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                value.as_str()?.parse().map_err(|e: anyhow::Error| FromSqlError::Other(e.into()))
+            }
+        }
+    };
+}
+
+string_enum!(Flag, "flag");
+string_enum!(ColorLabel, "color label");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_names_round_trip() {
+        for flag in Flag::ALL {
+            assert_eq!(flag.as_str().parse::<Flag>().unwrap(), flag);
+            assert_eq!(serde_json::to_string(&flag).unwrap(), format!("\"{}\"", flag));
+        }
+        for label in ColorLabel::ALL {
+            assert_eq!(label.as_str().parse::<ColorLabel>().unwrap(), label);
+            assert_eq!(serde_json::from_str::<ColorLabel>(&format!("\"{}\"", label)).unwrap(), label);
+        }
+        let err = "pikc".parse::<Flag>().unwrap_err().to_string();
+        assert!(err.contains("Invalid flag"), "{}", err);
+        assert!(serde_json::from_str::<ColorLabel>("\"Red\"").is_err());
+    }
+
+    #[test]
+    fn test_sql_round_trip_and_bad_stored_value() {
+        let conn = Connection::open_in_memory().unwrap();
+        let flag: Flag = conn.query_row("SELECT ?1", [Flag::Reject], |row| row.get(0)).unwrap();
+        assert_eq!(flag, Flag::Reject);
+        let label: ColorLabel = conn.query_row("SELECT ?1", [ColorLabel::Purple], |row| row.get(0)).unwrap();
+        assert_eq!(label, ColorLabel::Purple);
+
+        assert!(conn.query_row("SELECT 'pikc'", [], |row| row.get::<_, Flag>(0)).is_err());
+    }
+
+    #[test]
+    fn test_rating_range() {
+        assert!(validate_rating(0).is_ok());
+        assert!(validate_rating(MAX_RATING).is_ok());
+        assert!(validate_rating(-1).is_err());
+        assert!(validate_rating(6).is_err());
+    }
+}
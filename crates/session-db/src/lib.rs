@@ -3,23 +3,49 @@
 //! Each imported folder gets a session database at:
 //!   ~/.projectloupe/cache/{session-hash}/meta.db
 //!
-//! Stores: image metadata (EXIF), user annotations (flags, ratings, color labels),
-//! burst groups, and cache state. Designed as write-through alongside the in-memory
+//! Stores: image metadata (EXIF), user annotations (flags, ratings, color labels) with an
+//! undo/redo journal,
+//! burst groups, per-camera clock offsets, RAW+JPEG pairs, quality scores,
+//! perceptual hashes, and cache state. Designed as write-through alongside the in-memory
 //! Zustand store — writes happen on every mutation, reads happen on session load.
 //!
-//! Uses WAL mode for concurrent read/write without blocking the UI.
+//! Uses WAL mode for concurrent read/write without blocking the UI. The schema is
+//! versioned; see `migrations`.
 
-use anyhow::{Context, Result};
+mod annotations;
+mod migrations;
+
+use anyhow::{bail, Context, Result};
+use rusqlite::types::Value;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+pub use annotations::{validate_rating, ColorLabel, Flag, MAX_RATING};
+pub use migrations::SCHEMA_VERSION;
+
+/// SQL expression resolving parameter ?1 to its pair's primary path (or itself).
+const PRIMARY_OF_1: &str =
+    "COALESCE((SELECT primary_path FROM image_pairs WHERE sidecar_path = ?1), ?1)";
+
+/// `images` columns the annotation journal may write.
+const ANNOTATION_COLUMNS: &[&str] = &["flag", "rating", "color_label"];
+
+/// Undoable annotation operations kept in the journal.
+const JOURNAL_MAX_OPERATIONS: i64 = 1000;
+
+/// `session_meta` key holding the last applied journal operation.
+const JOURNAL_POSITION_KEY: &str = "annotation_journal_position";
+
 /// A persisted image record.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageRecord {
     pub file_path: String,
     pub filename: String,
+    /// JPEG half of a RAW+JPEG pair; `file_path` is the primary (RAW).
+    /// Annotations on the record apply to both files.
+    pub sidecar_path: Option<String>,
     pub file_size: i64,
     pub file_mtime: i64,
     pub cache_hash: String,
@@ -28,6 +54,8 @@ pub struct ImageRecord {
     pub serial_number: String,
     pub drive_mode: String,
     pub capture_time: String,
+    /// Seconds east of UTC from OffsetTimeOriginal
+    pub utc_offset_secs: Option<i32>,
     pub make: Option<String>,
     pub model: Option<String>,
     pub lens: Option<String>,
@@ -35,11 +63,14 @@ pub struct ImageRecord {
     pub aperture: Option<f64>,
     pub shutter_speed: Option<String>,
     pub iso: Option<u32>,
+    /// Camera-native burst ID (u64 bit pattern), kept so a rescan can
+    /// re-detect bursts without re-reading unchanged files
+    pub native_burst_id: Option<i64>,
 
     // User metadata
     pub rating: i32,
-    pub flag: String,        // "none" | "pick" | "reject"
-    pub color_label: String, // "none" | "red" | "yellow" | "green" | "blue" | "purple"
+    pub flag: Flag,
+    pub color_label: ColorLabel,
 
     // Burst
     pub burst_group_id: Option<String>,
@@ -48,6 +79,56 @@ pub struct ImageRecord {
     // Cache state
     pub micro_cached: bool,
     pub preview_cached: bool,
+
+    /// The file was gone at the last re-import. The record is kept so its
+    /// annotations come back if the file does.
+    pub missing: bool,
+}
+
+/// An image's annotations, as returned by `undo` and `redo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationState {
+    pub file_path: String,
+    pub flag: Flag,
+    pub rating: i32,
+    pub color_label: ColorLabel,
+}
+
+/// What the annotation journal can currently do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct JournalStatus {
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+/// A file as a rescan found it on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub file_path: String,
+    pub file_size: i64,
+    /// Milliseconds since the epoch, as stored in `ImageRecord::file_mtime`
+    pub file_mtime: i64,
+}
+
+/// How a folder on disk differs from its session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FolderDiff {
+    /// Not in the session, or marked missing
+    pub added: Vec<String>,
+    /// Size or mtime differ from the stored record
+    pub changed: Vec<String>,
+    /// In the session but no longer on disk
+    pub removed: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+/// What a merging re-import changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergeSummary {
+    pub added: usize,
+    pub updated: usize,
+    /// Known files not in this import, now marked missing
+    pub missing: usize,
 }
 
 /// A persisted burst group record.
@@ -59,6 +140,55 @@ pub struct BurstGroupRecord {
     pub duration_ms: i64,
     pub avg_gap_ms: f64,
     pub estimated_fps: f64,
+    /// Detection strategy: "NativeId" | "DriveMode" | "Similarity", or
+    /// "Manual" for a group the user edited
+    pub source: String,
+    /// Detection confidence in [0, 1]
+    pub confidence: f64,
+}
+
+/// An image's place after a burst edit: its group and index, or `None` for a single.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurstMembership {
+    pub file_path: String,
+    pub burst_group_id: Option<String>,
+    pub burst_index: Option<i32>,
+}
+
+/// Best-pick ranking of one burst's frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurstRankingRecord {
+    pub burst_id: String,
+    /// Burst index of the suggested pick
+    pub suggested_index: i32,
+    pub suggested_path: String,
+    /// Burst indices, best first
+    pub ranking: Vec<i32>,
+    /// Overall quality score per entry of `ranking`; `None` if unscorable
+    pub scores: Vec<Option<f64>>,
+}
+
+/// Quality score of one image, as of the file version in `cache_hash`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityScoreRecord {
+    pub file_path: String,
+    pub cache_hash: String,
+    pub overall_score: f64,
+    pub sharpness: f64,
+    pub exposure: f64,
+    pub composition: f64,
+    pub technical_quality: f64,
+    /// Every metric's value by name, including custom metrics
+    pub metrics: HashMap<String, f64>,
+}
+
+/// Perceptual hashes of one image, as of the file version in `cache_hash`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerceptualHashRecord {
+    pub file_path: String,
+    pub cache_hash: String,
+    pub dhash: u64,
+    pub phash: u64,
 }
 
 /// Session database handle.
@@ -90,9 +220,9 @@ impl SessionDb {
         conn.execute_batch("PRAGMA synchronous=NORMAL;")?;
         conn.execute_batch("PRAGMA foreign_keys=ON;")?;
 
-        let db = Self { conn, db_path };
-        db.create_tables()?;
-        Ok(db)
+        migrations::migrate(&conn)
+            .with_context(|| format!("Failed to upgrade database: {}", db_path.display()))?;
+        Ok(Self { conn, db_path })
     }
 
     /// Open a database at a specific path (for testing).
@@ -100,12 +230,18 @@ impl SessionDb {
         let conn = Connection::open(db_path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
         conn.execute_batch("PRAGMA synchronous=NORMAL;")?;
-        let db = Self {
+        migrations::migrate(&conn)
+            .with_context(|| format!("Failed to upgrade database: {}", db_path.display()))?;
+        Ok(Self {
             conn,
             db_path: db_path.to_path_buf(),
-        };
-        db.create_tables()?;
-        Ok(db)
+        })
+    }
+
+    /// Open a second connection to the same database, for writing from a
+    /// background thread while this one serves commands.
+    pub fn reopen(&self) -> Result<Self> {
+        Self::open_at(&self.db_path)
     }
 
     /// Check if a session database already exists for this folder.
@@ -125,60 +261,6 @@ impl SessionDb {
         &self.db_path
     }
 
-    // -- Schema --
-
-    fn create_tables(&self) -> Result<()> {
-        self.conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS images (
-                file_path TEXT PRIMARY KEY,
-                filename TEXT NOT NULL,
-                file_size INTEGER NOT NULL DEFAULT 0,
-                file_mtime INTEGER NOT NULL DEFAULT 0,
-                cache_hash TEXT NOT NULL DEFAULT '',
-                serial_number TEXT NOT NULL DEFAULT '',
-                drive_mode TEXT NOT NULL DEFAULT 'Single',
-                capture_time TEXT NOT NULL DEFAULT '',
-                make TEXT,
-                model TEXT,
-                lens TEXT,
-                focal_length REAL,
-                aperture REAL,
-                shutter_speed TEXT,
-                iso INTEGER,
-                rating INTEGER NOT NULL DEFAULT 0,
-                flag TEXT NOT NULL DEFAULT 'none',
-                color_label TEXT NOT NULL DEFAULT 'none',
-                burst_group_id TEXT,
-                burst_index INTEGER,
-                micro_cached INTEGER NOT NULL DEFAULT 0,
-                preview_cached INTEGER NOT NULL DEFAULT 0
-            );
-
-            CREATE TABLE IF NOT EXISTS burst_groups (
-                id TEXT PRIMARY KEY,
-                camera_serial TEXT NOT NULL,
-                frame_count INTEGER NOT NULL,
-                duration_ms INTEGER NOT NULL DEFAULT 0,
-                avg_gap_ms REAL NOT NULL DEFAULT 0,
-                estimated_fps REAL NOT NULL DEFAULT 0
-            );
-
-            CREATE TABLE IF NOT EXISTS session_meta (
-                key TEXT PRIMARY KEY,
-                value TEXT
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_images_burst ON images(burst_group_id);
-            CREATE INDEX IF NOT EXISTS idx_images_serial ON images(serial_number);
-            CREATE INDEX IF NOT EXISTS idx_images_capture ON images(capture_time);
-            CREATE INDEX IF NOT EXISTS idx_images_flag ON images(flag);
-            CREATE INDEX IF NOT EXISTS idx_images_rating ON images(rating);
-            ",
-        )?;
-        Ok(())
-    }
-
     // -- Session metadata --
 
     /// Store a session metadata key-value pair.
@@ -205,24 +287,70 @@ impl SessionDb {
 
     // -- Image operations --
 
-    /// Insert or update an image record. Used during import.
+    /// Insert or update an image record, replacing every column. Used on
+    /// first import; re-imports go through `merge_images`.
     pub fn upsert_image(&self, img: &ImageRecord) -> Result<()> {
+        self.write_image(img, "INSERT OR REPLACE", "")
+    }
+
+    /// Insert `img`, or update its file, EXIF and burst columns if the path is
+    /// known. Annotations are never touched; cache flags are kept while the
+    /// file is unchanged. Returns whether the image was new.
+    fn merge_image(&self, img: &ImageRecord) -> Result<bool> {
+        let known: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM images WHERE file_path = ?1",
+            params![img.file_path],
+            |row| row.get(0),
+        )?;
+        self.write_image(
+            img,
+            "INSERT",
+            "ON CONFLICT(file_path) DO UPDATE SET
+                filename = excluded.filename,
+                file_size = excluded.file_size,
+                file_mtime = excluded.file_mtime,
+                micro_cached = CASE WHEN cache_hash = excluded.cache_hash THEN micro_cached ELSE excluded.micro_cached END,
+                preview_cached = CASE WHEN cache_hash = excluded.cache_hash THEN preview_cached ELSE excluded.preview_cached END,
+                cache_hash = excluded.cache_hash,
+                serial_number = excluded.serial_number,
+                drive_mode = excluded.drive_mode,
+                capture_time = excluded.capture_time,
+                utc_offset_secs = excluded.utc_offset_secs,
+                make = excluded.make,
+                model = excluded.model,
+                lens = excluded.lens,
+                focal_length = excluded.focal_length,
+                aperture = excluded.aperture,
+                shutter_speed = excluded.shutter_speed,
+                iso = excluded.iso,
+                native_burst_id = excluded.native_burst_id,
+                burst_group_id = excluded.burst_group_id,
+                burst_index = excluded.burst_index,
+                missing = excluded.missing",
+        )?;
+        Ok(!known)
+    }
+
+    fn write_image(&self, img: &ImageRecord, insert: &str, on_conflict: &str) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO images (
-                file_path, filename, file_size, file_mtime, cache_hash,
-                serial_number, drive_mode, capture_time,
-                make, model, lens, focal_length, aperture, shutter_speed, iso,
-                rating, flag, color_label,
-                burst_group_id, burst_index,
-                micro_cached, preview_cached
-            ) VALUES (
-                ?1, ?2, ?3, ?4, ?5,
-                ?6, ?7, ?8,
-                ?9, ?10, ?11, ?12, ?13, ?14, ?15,
-                ?16, ?17, ?18,
-                ?19, ?20,
-                ?21, ?22
-            )",
+            &format!(
+                "{} INTO images (
+                    file_path, filename, file_size, file_mtime, cache_hash,
+                    serial_number, drive_mode, capture_time, utc_offset_secs,
+                    make, model, lens, focal_length, aperture, shutter_speed, iso, native_burst_id,
+                    rating, flag, color_label,
+                    burst_group_id, burst_index,
+                    micro_cached, preview_cached, missing
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5,
+                    ?6, ?7, ?8, ?9,
+                    ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                    ?18, ?19, ?20,
+                    ?21, ?22,
+                    ?23, ?24, ?25
+                ) {}",
+                insert, on_conflict
+            ),
             params![
                 img.file_path,
                 img.filename,
@@ -232,6 +360,7 @@ impl SessionDb {
                 img.serial_number,
                 img.drive_mode,
                 img.capture_time,
+                img.utc_offset_secs,
                 img.make,
                 img.model,
                 img.lens,
@@ -239,6 +368,7 @@ impl SessionDb {
                 img.aperture,
                 img.shutter_speed,
                 img.iso,
+                img.native_burst_id,
                 img.rating,
                 img.flag,
                 img.color_label,
@@ -246,8 +376,20 @@ impl SessionDb {
                 img.burst_index,
                 img.micro_cached as i32,
                 img.preview_cached as i32,
+                img.missing as i32,
             ],
         )?;
+
+        self.conn.execute(
+            "DELETE FROM image_pairs WHERE primary_path = ?1 OR sidecar_path = ?1",
+            params![img.file_path],
+        )?;
+        if let Some(ref sidecar) = img.sidecar_path {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO image_pairs (primary_path, sidecar_path) VALUES (?1, ?2)",
+                params![img.file_path, sidecar],
+            )?;
+        }
         Ok(())
     }
 
@@ -261,29 +403,75 @@ impl SessionDb {
         Ok(())
     }
 
-    /// Load all images from the database.
+    /// Re-import a folder into an existing session: `images` is everything
+    /// found on disk now. New files are inserted and known ones get fresh
+    /// file/EXIF/burst data, keeping their rating, flag and color label.
+    /// Known files not in `images` are marked missing (and leave their
+    /// burst) rather than deleted, so their annotations survive.
+    pub fn merge_images(&self, images: &[ImageRecord]) -> Result<MergeSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut summary = MergeSummary::default();
+        self.conn.execute_batch("CREATE TEMP TABLE IF NOT EXISTS seen_paths (file_path TEXT PRIMARY KEY); DELETE FROM seen_paths;")?;
+        for img in images {
+            if self.merge_image(img)? {
+                summary.added += 1;
+            } else {
+                summary.updated += 1;
+            }
+            self.conn.execute("INSERT OR IGNORE INTO seen_paths (file_path) VALUES (?1)", params![img.file_path])?;
+        }
+        summary.missing = self.conn.execute(
+            "UPDATE images SET missing = 1, burst_group_id = NULL, burst_index = NULL
+             WHERE missing = 0 AND file_path NOT IN (SELECT file_path FROM seen_paths)",
+            [],
+        )?;
+        self.conn.execute_batch("DELETE FROM seen_paths;")?;
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Load all images in timeline order.
+    ///
+    /// Mirrors burst-detection's `Timeline`: each burst sorts as one unit at its
+    /// first frame's (clock-corrected) capture time, ties broken by camera serial
+    /// and then burst ID / file path; frames within a burst follow burst_index.
     pub fn load_images(&self) -> Result<Vec<ImageRecord>> {
         let mut stmt = self.conn.prepare(
             "SELECT
-                file_path, filename, file_size, file_mtime, cache_hash,
-                serial_number, drive_mode, capture_time,
-                make, model, lens, focal_length, aperture, shutter_speed, iso,
-                rating, flag, color_label,
-                burst_group_id, burst_index,
-                micro_cached, preview_cached
-            FROM images ORDER BY capture_time",
+                i.file_path, i.filename, i.file_size, i.file_mtime, i.cache_hash,
+                i.serial_number, i.drive_mode, i.capture_time,
+                i.make, i.model, i.lens, i.focal_length, i.aperture, i.shutter_speed, i.iso,
+                i.rating, i.flag, i.color_label,
+                i.burst_group_id, i.burst_index,
+                i.micro_cached, i.preview_cached,
+                p.sidecar_path, i.missing, i.utc_offset_secs, i.native_burst_id
+            FROM images i
+            LEFT JOIN image_pairs p ON p.primary_path = i.file_path
+            LEFT JOIN (
+                SELECT burst_group_id, MIN(capture_time) AS start_time
+                FROM images WHERE burst_group_id IS NOT NULL
+                GROUP BY burst_group_id
+            ) b ON b.burst_group_id = i.burst_group_id
+            ORDER BY
+                COALESCE(b.start_time, i.capture_time),
+                i.serial_number,
+                COALESCE(i.burst_group_id, i.file_path),
+                i.burst_index,
+                i.capture_time",
         )?;
 
         let rows = stmt.query_map([], |row| {
             Ok(ImageRecord {
                 file_path: row.get(0)?,
                 filename: row.get(1)?,
+                sidecar_path: row.get(22)?,
                 file_size: row.get(2)?,
                 file_mtime: row.get(3)?,
                 cache_hash: row.get(4)?,
                 serial_number: row.get(5)?,
                 drive_mode: row.get(6)?,
                 capture_time: row.get(7)?,
+                utc_offset_secs: row.get(24)?,
                 make: row.get(8)?,
                 model: row.get(9)?,
                 lens: row.get(10)?,
@@ -291,6 +479,7 @@ impl SessionDb {
                 aperture: row.get(12)?,
                 shutter_speed: row.get(13)?,
                 iso: row.get(14)?,
+                native_burst_id: row.get(25)?,
                 rating: row.get(15)?,
                 flag: row.get(16)?,
                 color_label: row.get(17)?,
@@ -298,60 +487,234 @@ impl SessionDb {
                 burst_index: row.get(19)?,
                 micro_cached: row.get::<_, i32>(20)? != 0,
                 preview_cached: row.get::<_, i32>(21)? != 0,
+                missing: row.get::<_, i32>(23)? != 0,
             })
         })?;
 
         rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
     }
 
+    /// Compare a folder's files against the session by size and mtime.
+    ///
+    /// RAW+JPEG pairs are judged by their primary: a known sidecar is neither
+    /// added nor unchanged while its primary is on disk.
+    pub fn diff_folder(&self, files: &[FileState]) -> Result<FolderDiff> {
+        let records: HashMap<String, ImageRecord> = self.load_images()?
+            .into_iter()
+            .map(|r| (r.file_path.clone(), r))
+            .collect();
+        let on_disk: HashMap<&str, &FileState> = files.iter().map(|f| (f.file_path.as_str(), f)).collect();
+        let paired_sidecars: HashSet<&str> = records.values()
+            .filter(|r| !r.missing && on_disk.contains_key(r.file_path.as_str()))
+            .filter_map(|r| r.sidecar_path.as_deref())
+            .collect();
+
+        let mut diff = FolderDiff::default();
+        for file in files {
+            if paired_sidecars.contains(file.file_path.as_str()) {
+                continue;
+            }
+            match records.get(&file.file_path) {
+                Some(r) if !r.missing && r.file_size == file.file_size && r.file_mtime == file.file_mtime => {
+                    diff.unchanged.push(file.file_path.clone())
+                }
+                Some(r) if !r.missing => diff.changed.push(file.file_path.clone()),
+                _ => diff.added.push(file.file_path.clone()),
+            }
+        }
+        diff.removed = records.values()
+            .filter(|r| !r.missing && !on_disk.contains_key(r.file_path.as_str()))
+            .map(|r| r.file_path.clone())
+            .collect();
+        diff.removed.sort();
+        Ok(diff)
+    }
+
     /// Update just the flag for an image (write-through from UI).
-    pub fn update_flag(&self, file_path: &str, flag: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE images SET flag = ?1 WHERE file_path = ?2",
-            params![flag, file_path],
-        )?;
-        Ok(())
+    ///
+    /// Annotation updates accept either half of a RAW+JPEG pair; both map to
+    /// the primary's record. Each call is one undoable operation in the
+    /// annotation journal.
+    pub fn update_flag(&self, file_path: &str, flag: Flag) -> Result<()> {
+        self.annotate("flag", &[(file_path, Value::from(flag.to_string()))])
     }
 
-    /// Update just the rating for an image.
+    /// Update just the rating (0–5) for an image.
     pub fn update_rating(&self, file_path: &str, rating: i32) -> Result<()> {
-        self.conn.execute(
-            "UPDATE images SET rating = ?1 WHERE file_path = ?2",
-            params![rating, file_path],
-        )?;
-        Ok(())
+        validate_rating(rating)?;
+        self.annotate("rating", &[(file_path, Value::from(rating))])
     }
 
     /// Update just the color label for an image.
-    pub fn update_color_label(&self, file_path: &str, color_label: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE images SET color_label = ?1 WHERE file_path = ?2",
-            params![color_label, file_path],
-        )?;
-        Ok(())
+    pub fn update_color_label(&self, file_path: &str, color_label: ColorLabel) -> Result<()> {
+        self.annotate("color_label", &[(file_path, Value::from(color_label.to_string()))])
+    }
+
+    /// Batch update flags (e.g., burst flagging), undone as one operation.
+    pub fn update_flags_batch(&self, updates: &[(&str, Flag)]) -> Result<()> {
+        let values: Vec<(&str, Value)> = updates.iter()
+            .map(|(file_path, flag)| (*file_path, Value::from(flag.to_string())))
+            .collect();
+        self.annotate("flag", &values)
     }
 
-    /// Batch update flags (e.g., burst flagging).
-    pub fn update_flags_batch(&self, updates: &[(&str, &str)]) -> Result<()> {
+    // -- Annotation journal --
+
+    /// Set one annotation column on each image and journal the old and new
+    /// values as a single operation. Recording a new operation discards
+    /// anything that was undone (there's no redo after a fresh edit), and the
+    /// journal keeps only the last `JOURNAL_MAX_OPERATIONS` operations.
+    fn annotate(&self, column: &str, updates: &[(&str, Value)]) -> Result<()> {
+        debug_assert!(ANNOTATION_COLUMNS.contains(&column));
         let tx = self.conn.unchecked_transaction()?;
-        for (file_path, flag) in updates {
+        let position = self.journal_position()?;
+        let operation = position + 1;
+        let mut journaled = false;
+
+        for (file_path, new_value) in updates {
+            let current: Option<(String, Value)> = self.conn.query_row(
+                &format!("SELECT file_path, {} FROM images WHERE file_path = {}", column, PRIMARY_OF_1),
+                params![file_path],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ).optional()?;
+            let Some((primary, old_value)) = current else { continue };
+            if old_value == *new_value {
+                continue;
+            }
+            if !journaled {
+                self.conn.execute("DELETE FROM annotation_journal WHERE operation_id > ?1", params![position])?;
+                journaled = true;
+            }
             self.conn.execute(
-                "UPDATE images SET flag = ?1 WHERE file_path = ?2",
-                params![flag, file_path],
+                &format!("UPDATE images SET {} = ?1 WHERE file_path = ?2", column),
+                params![new_value, primary],
+            )?;
+            self.conn.execute(
+                "INSERT INTO annotation_journal (operation_id, file_path, field, old_value, new_value)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![operation, primary, column, old_value, new_value],
             )?;
         }
+
+        if journaled {
+            self.conn.execute(
+                "DELETE FROM annotation_journal WHERE operation_id <= ?1",
+                params![operation - JOURNAL_MAX_OPERATIONS],
+            )?;
+            self.set_journal_position(operation)?;
+        }
         tx.commit()?;
         Ok(())
     }
 
+    /// Revert the last applied annotation operation. Returns the affected
+    /// images' annotations as they are now, or `None` if there's nothing to undo.
+    pub fn undo(&self) -> Result<Option<Vec<AnnotationState>>> {
+        let position = self.journal_position()?;
+        let previous: Option<i64> = self.conn.query_row(
+            "SELECT MAX(operation_id) FROM annotation_journal WHERE operation_id < ?1",
+            params![position],
+            |row| row.get(0),
+        )?;
+        self.replay(position, "old_value", previous.unwrap_or(0))
+    }
+
+    /// Re-apply the last undone annotation operation. Returns the affected
+    /// images' annotations as they are now, or `None` if there's nothing to redo.
+    pub fn redo(&self) -> Result<Option<Vec<AnnotationState>>> {
+        let position = self.journal_position()?;
+        let next: Option<i64> = self.conn.query_row(
+            "SELECT MIN(operation_id) FROM annotation_journal WHERE operation_id > ?1",
+            params![position],
+            |row| row.get(0),
+        )?;
+        match next {
+            Some(next) => self.replay(next, "new_value", next),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether `undo` and `redo` currently have anything to apply.
+    pub fn journal_status(&self) -> Result<JournalStatus> {
+        let position = self.journal_position()?;
+        let (can_undo, can_redo): (bool, bool) = self.conn.query_row(
+            "SELECT
+                COALESCE(SUM(operation_id = ?1), 0) > 0,
+                COALESCE(SUM(operation_id > ?1), 0) > 0
+             FROM annotation_journal",
+            params![position],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(JournalStatus { can_undo, can_redo })
+    }
+
+    /// Write `value_column` ("old_value" or "new_value") of every entry of
+    /// `operation` back to images, then move the journal to `new_position`.
+    fn replay(&self, operation: i64, value_column: &str, new_position: i64) -> Result<Option<Vec<AnnotationState>>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let entries: Vec<(String, String, Value)> = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT file_path, field, {} FROM annotation_journal WHERE operation_id = ?1 ORDER BY id DESC",
+                value_column
+            ))?;
+            let rows = stmt.query_map(params![operation], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let mut paths: Vec<String> = Vec::new();
+        for (file_path, field, value) in entries {
+            // Field names are interpolated; never trust what's on disk
+            if !ANNOTATION_COLUMNS.contains(&field.as_str()) {
+                bail!("Corrupt annotation journal entry for {}: field {}", file_path, field);
+            }
+            self.conn.execute(
+                &format!("UPDATE images SET {} = ?1 WHERE file_path = ?2", field),
+                params![value, file_path],
+            )?;
+            if !paths.contains(&file_path) {
+                paths.push(file_path);
+            }
+        }
+
+        self.set_journal_position(new_position)?;
+        tx.commit()?;
+
+        paths.iter().map(|path| self.annotation_state(path)).collect::<Result<Vec<_>>>().map(Some)
+    }
+
+    fn annotation_state(&self, file_path: &str) -> Result<AnnotationState> {
+        Ok(self.conn.query_row(
+            "SELECT file_path, flag, rating, color_label FROM images WHERE file_path = ?1",
+            params![file_path],
+            |row| Ok(AnnotationState {
+                file_path: row.get(0)?,
+                flag: row.get(1)?,
+                rating: row.get(2)?,
+                color_label: row.get(3)?,
+            }),
+        )?)
+    }
+
+    /// Last applied journal operation; 0 before the first, or once all are undone.
+    fn journal_position(&self) -> Result<i64> {
+        Ok(self.get_meta(JOURNAL_POSITION_KEY)?.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    fn set_journal_position(&self, operation: i64) -> Result<()> {
+        self.set_meta(JOURNAL_POSITION_KEY, &operation.to_string())
+    }
+
     // -- Burst group operations --
 
     /// Insert or update a burst group.
     pub fn upsert_burst_group(&self, burst: &BurstGroupRecord) -> Result<()> {
         self.conn.execute(
             "INSERT OR REPLACE INTO burst_groups (
-                id, camera_serial, frame_count, duration_ms, avg_gap_ms, estimated_fps
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                id, camera_serial, frame_count, duration_ms, avg_gap_ms, estimated_fps, source, confidence
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 burst.id,
                 burst.camera_serial,
@@ -359,6 +722,8 @@ impl SessionDb {
                 burst.duration_ms,
                 burst.avg_gap_ms,
                 burst.estimated_fps,
+                burst.source,
+                burst.confidence,
             ],
         )?;
         Ok(())
@@ -374,10 +739,80 @@ impl SessionDb {
         Ok(())
     }
 
+    /// Replace all burst groups with `bursts` (after re-running detection).
+    /// Groups whose IDs no longer exist are removed, and best-pick rankings
+    /// are dropped: a re-detected burst may not hold the same frames. Rankings
+    /// of "Manual" groups in `bursts` are kept, since detection restores those
+    /// as the user left them.
+    pub fn replace_burst_groups(&self, bursts: &[BurstGroupRecord]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute("DELETE FROM burst_groups", [])?;
+        for burst in bursts {
+            self.upsert_burst_group(burst)?;
+        }
+        self.conn.execute(
+            "DELETE FROM burst_rankings WHERE burst_id NOT IN
+             (SELECT id FROM burst_groups WHERE source = 'Manual')",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Persist a manual burst edit in one transaction: drop `removed_ids`,
+    /// upsert the edited `groups`, and move each image in `memberships`.
+    /// Rankings of removed and edited groups are dropped.
+    pub fn apply_burst_edit(
+        &self,
+        removed_ids: &[String],
+        groups: &[BurstGroupRecord],
+        memberships: &[BurstMembership],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for id in removed_ids.iter().chain(groups.iter().map(|g| &g.id)) {
+            self.conn.execute("DELETE FROM burst_groups WHERE id = ?1", params![id])?;
+            self.conn.execute("DELETE FROM burst_rankings WHERE burst_id = ?1", params![id])?;
+        }
+        for group in groups {
+            self.upsert_burst_group(group)?;
+        }
+        for member in memberships {
+            self.conn.execute(
+                "UPDATE images SET burst_group_id = ?1, burst_index = ?2 WHERE file_path = ?3",
+                params![member.burst_group_id, member.burst_index, member.file_path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Frames of every "Manual" burst group in burst order, for re-applying
+    /// the user's edits on top of a fresh detection.
+    pub fn load_manual_burst_frames(&self) -> Result<Vec<(String, Vec<String>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT g.id, i.file_path
+             FROM burst_groups g
+             JOIN images i ON i.burst_group_id = g.id
+             WHERE g.source = 'Manual'
+             ORDER BY g.id, i.burst_index",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for row in rows {
+            let (id, file_path) = row?;
+            match groups.last_mut() {
+                Some((last_id, frames)) if *last_id == id => frames.push(file_path),
+                _ => groups.push((id, vec![file_path])),
+            }
+        }
+        Ok(groups)
+    }
+
     /// Load all burst groups.
     pub fn load_burst_groups(&self) -> Result<Vec<BurstGroupRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, camera_serial, frame_count, duration_ms, avg_gap_ms, estimated_fps
+            "SELECT id, camera_serial, frame_count, duration_ms, avg_gap_ms, estimated_fps, source, confidence
              FROM burst_groups",
         )?;
 
@@ -389,12 +824,226 @@ impl SessionDb {
                 duration_ms: row.get(3)?,
                 avg_gap_ms: row.get(4)?,
                 estimated_fps: row.get(5)?,
+                source: row.get(6)?,
+                confidence: row.get(7)?,
             })
         })?;
 
         rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
     }
 
+    // -- Best-pick rankings --
+
+    /// Insert or replace best-pick rankings.
+    pub fn save_burst_rankings(&self, rankings: &[BurstRankingRecord]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for ranking in rankings {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO burst_rankings
+                 (burst_id, suggested_index, suggested_path, ranking, scores)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    ranking.burst_id,
+                    ranking.suggested_index,
+                    ranking.suggested_path,
+                    serde_json::to_string(&ranking.ranking)?,
+                    serde_json::to_string(&ranking.scores)?,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load all best-pick rankings.
+    pub fn load_burst_rankings(&self) -> Result<Vec<BurstRankingRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT burst_id, suggested_index, suggested_path, ranking, scores
+             FROM burst_rankings ORDER BY burst_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut rankings = Vec::new();
+        for row in rows {
+            let (burst_id, suggested_index, suggested_path, ranking, scores) = row?;
+            rankings.push(BurstRankingRecord {
+                ranking: serde_json::from_str(&ranking)
+                    .with_context(|| format!("Corrupt ranking for burst {}", burst_id))?,
+                scores: serde_json::from_str(&scores)
+                    .with_context(|| format!("Corrupt scores for burst {}", burst_id))?,
+                burst_id,
+                suggested_index,
+                suggested_path,
+            });
+        }
+        Ok(rankings)
+    }
+
+    // -- Quality scores --
+
+    /// Insert or replace quality scores.
+    pub fn save_quality_scores(&self, scores: &[QualityScoreRecord]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for score in scores {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO quality_scores
+                 (file_path, cache_hash, overall_score, sharpness, exposure, composition, technical_quality, metrics)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    score.file_path,
+                    score.cache_hash,
+                    score.overall_score,
+                    score.sharpness,
+                    score.exposure,
+                    score.composition,
+                    score.technical_quality,
+                    serde_json::to_string(&score.metrics)?,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load quality scores that are still current: a score is left out if
+    /// its image's cache hash has changed since (the file was edited) or the
+    /// image is no longer in the session.
+    pub fn load_quality_scores(&self) -> Result<Vec<QualityScoreRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT q.file_path, q.cache_hash, q.overall_score, q.sharpness, q.exposure,
+                    q.composition, q.technical_quality, q.metrics
+             FROM quality_scores q
+             JOIN images i ON i.file_path = q.file_path AND i.cache_hash = q.cache_hash
+             ORDER BY q.file_path",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                QualityScoreRecord {
+                    file_path: row.get(0)?,
+                    cache_hash: row.get(1)?,
+                    overall_score: row.get(2)?,
+                    sharpness: row.get(3)?,
+                    exposure: row.get(4)?,
+                    composition: row.get(5)?,
+                    technical_quality: row.get(6)?,
+                    metrics: HashMap::new(),
+                },
+                row.get::<_, String>(7)?,
+            ))
+        })?;
+
+        let mut scores = Vec::new();
+        for row in rows {
+            let (mut score, metrics) = row?;
+            score.metrics = serde_json::from_str(&metrics)
+                .with_context(|| format!("Corrupt metrics for {}", score.file_path))?;
+            scores.push(score);
+        }
+        Ok(scores)
+    }
+
+    // -- Perceptual hashes --
+
+    /// Insert or replace perceptual hashes.
+    pub fn save_perceptual_hashes(&self, hashes: &[PerceptualHashRecord]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for hash in hashes {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO perceptual_hashes (file_path, cache_hash, dhash, phash)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![hash.file_path, hash.cache_hash, hash.dhash as i64, hash.phash as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load perceptual hashes that are still current (same rule as
+    /// `load_quality_scores`).
+    pub fn load_perceptual_hashes(&self) -> Result<Vec<PerceptualHashRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT h.file_path, h.cache_hash, h.dhash, h.phash
+             FROM perceptual_hashes h
+             JOIN images i ON i.file_path = h.file_path AND i.cache_hash = h.cache_hash
+             ORDER BY h.file_path",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PerceptualHashRecord {
+                file_path: row.get(0)?,
+                cache_hash: row.get(1)?,
+                dhash: row.get::<_, i64>(2)? as u64,
+                phash: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.into())
+    }
+
+    // -- Camera clock offsets --
+
+    /// Set the clock correction (milliseconds) for one camera body.
+    /// An offset of 0 removes the correction.
+    pub fn set_clock_offset(&self, serial_number: &str, offset_ms: i64) -> Result<()> {
+        if offset_ms == 0 {
+            self.conn.execute(
+                "DELETE FROM camera_clock_offsets WHERE serial_number = ?1",
+                params![serial_number],
+            )?;
+        } else {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO camera_clock_offsets (serial_number, offset_ms) VALUES (?1, ?2)",
+                params![serial_number, offset_ms],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load all clock corrections, keyed by camera serial.
+    pub fn load_clock_offsets(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT serial_number, offset_ms FROM camera_clock_offsets")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        rows.collect::<Result<HashMap<_, _>, _>>().map_err(|e| e.into())
+    }
+
+    // -- Quality weights --
+
+    /// Replace the session's quality weight profile (metric name -> weight).
+    /// Metrics not listed fall back to their default weight.
+    pub fn save_quality_weights(&self, weights: &HashMap<String, f64>) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute("DELETE FROM quality_weights", [])?;
+        for (metric, weight) in weights {
+            self.conn.execute(
+                "INSERT INTO quality_weights (metric, weight) VALUES (?1, ?2)",
+                params![metric, weight],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load the session's quality weight profile, keyed by metric name.
+    pub fn load_quality_weights(&self) -> Result<HashMap<String, f64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT metric, weight FROM quality_weights")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?;
+        rows.collect::<Result<HashMap<_, _>, _>>().map_err(|e| e.into())
+    }
+
     // -- Statistics --
 
     /// Get image count.
@@ -406,12 +1055,12 @@ impl SessionDb {
     }
 
     /// Get flag counts.
-    pub fn flag_counts(&self) -> Result<HashMap<String, i64>> {
+    pub fn flag_counts(&self) -> Result<HashMap<Flag, i64>> {
         let mut stmt = self
             .conn
             .prepare("SELECT flag, COUNT(*) FROM images GROUP BY flag")?;
         let rows = stmt.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            Ok((row.get::<_, Flag>(0)?, row.get::<_, i64>(1)?))
         })?;
         let mut counts = HashMap::new();
         for row in rows {
@@ -450,13 +1099,15 @@ mod tests {
     fn sample_image(path: &str) -> ImageRecord {
         ImageRecord {
             file_path: path.to_string(),
-            filename: path.split('/').last().unwrap_or(path).to_string(),
+            filename: path.split('/').next_back().unwrap_or(path).to_string(),
+            sidecar_path: None,
             file_size: 50_000_000,
             file_mtime: 1700000000,
             cache_hash: format!("hash-{}", path),
             serial_number: "3002851".to_string(),
             drive_mode: "ContinuousHigh".to_string(),
             capture_time: "2025-08-14T18:45:40.000Z".to_string(),
+            utc_offset_secs: None,
             make: Some("NIKON CORPORATION".to_string()),
             model: Some("NIKON Z 9".to_string()),
             lens: Some("VR 500mm f/4E".to_string()),
@@ -464,13 +1115,15 @@ mod tests {
             aperture: Some(4.5),
             shutter_speed: Some("1/3200".to_string()),
             iso: Some(800),
+            native_burst_id: None,
             rating: 0,
-            flag: "none".to_string(),
-            color_label: "none".to_string(),
+            flag: Flag::None,
+            color_label: ColorLabel::None,
             burst_group_id: None,
             burst_index: None,
             micro_cached: false,
             preview_cached: false,
+            missing: false,
         }
     }
 
@@ -493,6 +1146,37 @@ mod tests {
         assert_eq!(loaded[0].serial_number, "3002851");
     }
 
+    #[test]
+    fn test_load_images_timeline_order() {
+        let (db, _dir) = test_db();
+        let image = |path: &str, serial: &str, time: &str, burst: Option<(&str, i32)>| ImageRecord {
+            serial_number: serial.to_string(),
+            capture_time: time.to_string(),
+            burst_group_id: burst.map(|(id, _)| id.to_string()),
+            burst_index: burst.map(|(_, i)| i),
+            ..sample_image(path)
+        };
+        db.upsert_images(&[
+            // Body B's single lands inside body A's burst — it must not split it
+            image("/b/B_0001.NEF", "B", "2025-06-21T15:00:10.150+00:00", None),
+            image("/a/A_0003.NEF", "A", "2025-06-21T15:00:10.200+00:00", Some(("burst_a", 2))),
+            image("/a/A_0001.NEF", "A", "2025-06-21T15:00:10+00:00", Some(("burst_a", 0))),
+            image("/a/A_0002.NEF", "A", "2025-06-21T15:00:10.100+00:00", Some(("burst_a", 1))),
+            image("/b/B_0000.NEF", "B", "2025-06-21T14:59:55+00:00", None),
+            image("/a/A_0000.NEF", "A", "2025-06-21T14:59:55+00:00", None),
+        ]).unwrap();
+
+        let order: Vec<String> = db.load_images().unwrap().into_iter().map(|i| i.file_path).collect();
+        assert_eq!(order, vec![
+            "/a/A_0000.NEF",
+            "/b/B_0000.NEF",
+            "/a/A_0001.NEF",
+            "/a/A_0002.NEF",
+            "/a/A_0003.NEF",
+            "/b/B_0001.NEF",
+        ]);
+    }
+
     #[test]
     fn test_batch_upsert() {
         let (db, _dir) = test_db();
@@ -509,14 +1193,14 @@ mod tests {
         let img = sample_image("/photos/test.NEF");
         db.upsert_image(&img).unwrap();
 
-        db.update_flag("/photos/test.NEF", "pick").unwrap();
+        db.update_flag("/photos/test.NEF", Flag::Pick).unwrap();
         let loaded = db.load_images().unwrap();
-        assert_eq!(loaded[0].flag, "pick");
+        assert_eq!(loaded[0].flag, Flag::Pick);
 
         // Toggle back
-        db.update_flag("/photos/test.NEF", "none").unwrap();
+        db.update_flag("/photos/test.NEF", Flag::None).unwrap();
         let loaded = db.load_images().unwrap();
-        assert_eq!(loaded[0].flag, "none");
+        assert_eq!(loaded[0].flag, Flag::None);
     }
 
     #[test]
@@ -528,6 +1212,9 @@ mod tests {
         db.update_rating("/photos/test.NEF", 5).unwrap();
         let loaded = db.load_images().unwrap();
         assert_eq!(loaded[0].rating, 5);
+
+        assert!(db.update_rating("/photos/test.NEF", 6).is_err());
+        assert_eq!(db.load_images().unwrap()[0].rating, 5);
     }
 
     #[test]
@@ -536,9 +1223,9 @@ mod tests {
         let img = sample_image("/photos/test.NEF");
         db.upsert_image(&img).unwrap();
 
-        db.update_color_label("/photos/test.NEF", "red").unwrap();
+        db.update_color_label("/photos/test.NEF", ColorLabel::Red).unwrap();
         let loaded = db.load_images().unwrap();
-        assert_eq!(loaded[0].color_label, "red");
+        assert_eq!(loaded[0].color_label, ColorLabel::Red);
     }
 
     #[test]
@@ -549,22 +1236,47 @@ mod tests {
             .collect();
         db.upsert_images(&images).unwrap();
 
-        let updates: Vec<(&str, &str)> = vec![
-            ("/photos/img_0.NEF", "pick"),
-            ("/photos/img_1.NEF", "reject"),
-            ("/photos/img_2.NEF", "pick"),
+        let updates = vec![
+            ("/photos/img_0.NEF", Flag::Pick),
+            ("/photos/img_1.NEF", Flag::Reject),
+            ("/photos/img_2.NEF", Flag::Pick),
         ];
         db.update_flags_batch(&updates).unwrap();
 
         let loaded = db.load_images().unwrap();
-        let flags: HashMap<String, String> = loaded
+        let flags: HashMap<String, Flag> = loaded
             .into_iter()
             .map(|i| (i.file_path, i.flag))
             .collect();
-        assert_eq!(flags["/photos/img_0.NEF"], "pick");
-        assert_eq!(flags["/photos/img_1.NEF"], "reject");
-        assert_eq!(flags["/photos/img_2.NEF"], "pick");
-        assert_eq!(flags["/photos/img_3.NEF"], "none");
+        assert_eq!(flags["/photos/img_0.NEF"], Flag::Pick);
+        assert_eq!(flags["/photos/img_1.NEF"], Flag::Reject);
+        assert_eq!(flags["/photos/img_2.NEF"], Flag::Pick);
+        assert_eq!(flags["/photos/img_3.NEF"], Flag::None);
+    }
+
+    #[test]
+    fn test_raw_jpeg_pair_round_trip_and_shared_annotations() {
+        let (db, _dir) = test_db();
+        let mut raw = sample_image("/CARD1/DSC_0001.NEF");
+        raw.sidecar_path = Some("/CARD2/DSC_0001.JPG".to_string());
+        db.upsert_image(&raw).unwrap();
+
+        // The UI may hand back either half of the pair
+        db.update_flag("/CARD2/DSC_0001.JPG", Flag::Pick).unwrap();
+        db.update_rating("/CARD1/DSC_0001.NEF", 3).unwrap();
+        db.update_color_label("/CARD2/DSC_0001.JPG", ColorLabel::Green).unwrap();
+
+        let loaded = db.load_images().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].sidecar_path.as_deref(), Some("/CARD2/DSC_0001.JPG"));
+        assert_eq!(loaded[0].flag, Flag::Pick);
+        assert_eq!(loaded[0].rating, 3);
+        assert_eq!(loaded[0].color_label, ColorLabel::Green);
+
+        // Re-importing without the JPEG unpairs
+        raw.sidecar_path = None;
+        db.upsert_image(&raw).unwrap();
+        assert_eq!(db.load_images().unwrap()[0].sidecar_path, None);
     }
 
     #[test]
@@ -577,6 +1289,8 @@ mod tests {
             duration_ms: 250,
             avg_gap_ms: 50.0,
             estimated_fps: 20.0,
+            source: "NativeId".to_string(),
+            confidence: 1.0,
         };
         db.upsert_burst_group(&burst).unwrap();
 
@@ -584,6 +1298,236 @@ mod tests {
         assert_eq!(loaded.len(), 1);
         assert_eq!(loaded[0].id, "burst-1");
         assert_eq!(loaded[0].frame_count, 6);
+        assert_eq!(loaded[0].source, "NativeId");
+        assert_eq!(loaded[0].confidence, 1.0);
+    }
+
+    #[test]
+    fn test_burst_source_columns_added_to_existing_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("old.db");
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE burst_groups (
+                    id TEXT PRIMARY KEY,
+                    camera_serial TEXT NOT NULL,
+                    frame_count INTEGER NOT NULL,
+                    duration_ms INTEGER NOT NULL DEFAULT 0,
+                    avg_gap_ms REAL NOT NULL DEFAULT 0,
+                    estimated_fps REAL NOT NULL DEFAULT 0
+                );
+                INSERT INTO burst_groups (id, camera_serial, frame_count) VALUES ('burst-old', 'A', 3);",
+            ).unwrap();
+        }
+
+        let db = SessionDb::open_at(&db_path).unwrap();
+        let loaded = db.load_burst_groups().unwrap();
+
+        assert_eq!(loaded[0].id, "burst-old");
+        assert_eq!(loaded[0].source, "DriveMode");
+        assert_eq!(loaded[0].confidence, 0.8);
+        // Idempotent on the next open
+        drop(db);
+        SessionDb::open_at(&db_path).unwrap();
+    }
+
+    #[test]
+    fn test_replace_burst_groups_drops_stale() {
+        let (db, _dir) = test_db();
+        let burst = |id: &str| BurstGroupRecord {
+            id: id.to_string(),
+            camera_serial: "3002851".to_string(),
+            frame_count: 3,
+            duration_ms: 100,
+            avg_gap_ms: 50.0,
+            estimated_fps: 20.0,
+            source: "NativeId".to_string(),
+            confidence: 1.0,
+        };
+        db.upsert_burst_groups(&[burst("old-1"), burst("old-2")]).unwrap();
+
+        db.replace_burst_groups(&[burst("new-1")]).unwrap();
+
+        let ids: Vec<_> = db.load_burst_groups().unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec!["new-1"]);
+    }
+
+    #[test]
+    fn test_apply_burst_edit_and_manual_groups_survive_redetection() {
+        let (db, _dir) = test_db();
+        let burst = |id: &str, frames: i32, source: &str| BurstGroupRecord {
+            id: id.to_string(),
+            camera_serial: "A".to_string(),
+            frame_count: frames,
+            duration_ms: 100,
+            avg_gap_ms: 50.0,
+            estimated_fps: 20.0,
+            source: source.to_string(),
+            confidence: 1.0,
+        };
+        let member = |path: &str, id: Option<&str>, index: Option<i32>| BurstMembership {
+            file_path: path.to_string(),
+            burst_group_id: id.map(|s| s.to_string()),
+            burst_index: index,
+        };
+        let ranking = |id: &str| BurstRankingRecord {
+            burst_id: id.to_string(),
+            suggested_index: 0,
+            suggested_path: "/a/1.NEF".to_string(),
+            ranking: vec![0],
+            scores: vec![None],
+        };
+        for (i, path) in ["/a/1.NEF", "/a/2.NEF", "/a/3.NEF", "/a/4.NEF"].iter().enumerate() {
+            let mut img = sample_image(path);
+            img.burst_group_id = Some("b1".to_string());
+            img.burst_index = Some(i as i32);
+            db.upsert_image(&img).unwrap();
+        }
+        db.replace_burst_groups(&[burst("b1", 4, "DriveMode")]).unwrap();
+        db.save_burst_rankings(&[ranking("b1")]).unwrap();
+
+        // Split off frames 3-4, then detach frame 4
+        db.apply_burst_edit(
+            &[],
+            &[burst("b1", 2, "Manual"), burst("b2", 2, "Manual")],
+            &[member("/a/3.NEF", Some("b2"), Some(0)), member("/a/4.NEF", Some("b2"), Some(1))],
+        ).unwrap();
+        db.apply_burst_edit(&["b2".to_string()], &[], &[member("/a/3.NEF", None, None), member("/a/4.NEF", None, None)])
+            .unwrap();
+
+        assert!(db.load_burst_rankings().unwrap().is_empty());
+        assert_eq!(
+            db.load_manual_burst_frames().unwrap(),
+            vec![("b1".to_string(), vec!["/a/1.NEF".to_string(), "/a/2.NEF".to_string()])]
+        );
+        let singles: Vec<_> = db.load_images().unwrap().into_iter()
+            .filter(|img| img.burst_group_id.is_none())
+            .map(|img| img.file_path)
+            .collect();
+        assert_eq!(singles, vec!["/a/3.NEF", "/a/4.NEF"]);
+
+        // Re-detection keeps the manual group's ranking, drops the rest
+        db.save_burst_rankings(&[ranking("b1"), ranking("b3")]).unwrap();
+        db.replace_burst_groups(&[burst("b1", 2, "Manual"), burst("b3", 2, "DriveMode")]).unwrap();
+        let kept: Vec<_> = db.load_burst_rankings().unwrap().into_iter().map(|r| r.burst_id).collect();
+        assert_eq!(kept, vec!["b1"]);
+    }
+
+    #[test]
+    fn test_clock_offsets() {
+        let (db, _dir) = test_db();
+        db.set_clock_offset("3002851", 185_000).unwrap();
+        db.set_clock_offset("6012345", -1_250).unwrap();
+        db.set_clock_offset("3002851", 184_500).unwrap();
+
+        let offsets = db.load_clock_offsets().unwrap();
+        assert_eq!(offsets.len(), 2);
+        assert_eq!(offsets["3002851"], 184_500);
+        assert_eq!(offsets["6012345"], -1_250);
+
+        db.set_clock_offset("6012345", 0).unwrap();
+        assert!(!db.load_clock_offsets().unwrap().contains_key("6012345"));
+    }
+
+    #[test]
+    fn test_burst_rankings_round_trip() {
+        let (db, _dir) = test_db();
+        let ranking = BurstRankingRecord {
+            burst_id: "burst_1".to_string(),
+            suggested_index: 2,
+            suggested_path: "/photos/DSC_0003.NEF".to_string(),
+            ranking: vec![2, 0, 1],
+            scores: vec![Some(0.91), Some(0.74), None],
+        };
+        db.save_burst_rankings(std::slice::from_ref(&ranking)).unwrap();
+
+        assert_eq!(db.load_burst_rankings().unwrap(), vec![ranking]);
+
+        // Re-detection invalidates rankings
+        db.replace_burst_groups(&[]).unwrap();
+        assert!(db.load_burst_rankings().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quality_weights_round_trip() {
+        let (db, _dir) = test_db();
+        assert!(db.load_quality_weights().unwrap().is_empty());
+
+        db.save_quality_weights(&HashMap::from([
+            ("sharpness".to_string(), 0.55),
+            ("composition".to_string(), 0.05),
+        ])).unwrap();
+        db.save_quality_weights(&HashMap::from([
+            ("sharpness".to_string(), 0.25),
+            ("warmth".to_string(), 1.0),
+        ])).unwrap();
+
+        let weights = db.load_quality_weights().unwrap();
+        assert_eq!(weights.len(), 2);
+        assert_eq!(weights["sharpness"], 0.25);
+        assert_eq!(weights["warmth"], 1.0);
+    }
+
+    #[test]
+    fn test_quality_scores_survive_reopen_and_go_stale_on_edit() {
+        let (db, dir) = test_db();
+        let mut images = vec![sample_image("/photos/a.NEF"), sample_image("/photos/b.NEF")];
+        db.upsert_images(&images).unwrap();
+
+        let score = |img: &ImageRecord, overall: f64| QualityScoreRecord {
+            file_path: img.file_path.clone(),
+            cache_hash: img.cache_hash.clone(),
+            overall_score: overall,
+            sharpness: 0.8,
+            exposure: 0.6,
+            composition: 0.5,
+            technical_quality: 0.7,
+            metrics: HashMap::from([("sharpness".to_string(), 0.8), ("warmth".to_string(), 0.3)]),
+        };
+        db.save_quality_scores(&[score(&images[0], 0.7), score(&images[1], 0.6)]).unwrap();
+        drop(db);
+
+        let db = SessionDb::open_at(&dir.path().join("test.db")).unwrap();
+        let loaded = db.load_quality_scores().unwrap();
+        assert_eq!(loaded, vec![score(&images[0], 0.7), score(&images[1], 0.6)]);
+
+        // b.NEF edited on disk: its stored score no longer applies
+        images[1].cache_hash = "edited".to_string();
+        db.upsert_image(&images[1]).unwrap();
+        let loaded = db.load_quality_scores().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].file_path, "/photos/a.NEF");
+    }
+
+    #[test]
+    fn test_perceptual_hashes_round_trip_full_u64() {
+        let (db, _dir) = test_db();
+        let mut img = sample_image("/photos/a.NEF");
+        db.upsert_image(&img).unwrap();
+        let hash = PerceptualHashRecord {
+            file_path: img.file_path.clone(),
+            cache_hash: img.cache_hash.clone(),
+            dhash: u64::MAX - 5,
+            phash: 0x8000_0000_0000_0001,
+        };
+
+        db.save_perceptual_hashes(std::slice::from_ref(&hash)).unwrap();
+        assert_eq!(db.load_perceptual_hashes().unwrap(), vec![hash]);
+
+        img.cache_hash = "edited".to_string();
+        db.upsert_image(&img).unwrap();
+        assert!(db.load_perceptual_hashes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reopen_sees_other_connection_writes() {
+        let (db, _dir) = test_db();
+        let writer = db.reopen().unwrap();
+
+        writer.upsert_image(&sample_image("/photos/a.NEF")).unwrap();
+
+        assert_eq!(db.image_count().unwrap(), 1);
     }
 
     #[test]
@@ -609,39 +1553,184 @@ mod tests {
         let mut images: Vec<_> = (0..10)
             .map(|i| sample_image(&format!("/photos/img_{}.NEF", i)))
             .collect();
-        images[0].flag = "pick".to_string();
-        images[1].flag = "pick".to_string();
-        images[2].flag = "reject".to_string();
+        images[0].flag = Flag::Pick;
+        images[1].flag = Flag::Pick;
+        images[2].flag = Flag::Reject;
         db.upsert_images(&images).unwrap();
 
         let counts = db.flag_counts().unwrap();
-        assert_eq!(counts.get("pick"), Some(&2));
-        assert_eq!(counts.get("reject"), Some(&1));
-        assert_eq!(counts.get("none"), Some(&7));
+        assert_eq!(counts.get(&Flag::Pick), Some(&2));
+        assert_eq!(counts.get(&Flag::Reject), Some(&1));
+        assert_eq!(counts.get(&Flag::None), Some(&7));
     }
 
     #[test]
-    fn test_upsert_preserves_user_data() {
+    fn test_upsert_replaces_user_data() {
         let (db, _dir) = test_db();
         let mut img = sample_image("/photos/test.NEF");
         db.upsert_image(&img).unwrap();
 
         // User flags the image
-        db.update_flag("/photos/test.NEF", "pick").unwrap();
+        db.update_flag("/photos/test.NEF", Flag::Pick).unwrap();
         db.update_rating("/photos/test.NEF", 4).unwrap();
 
-        // Re-import (upsert) with same path — should overwrite EXIF but...
-        // Actually, upsert replaces everything. For re-import, we'd need
-        // a smarter merge. For now, upsert is for initial import only.
-        // This test documents the current behavior.
+        // upsert replaces everything, so it is for initial import only;
+        // re-import goes through merge_images (see test_merge_images_*).
         img.iso = Some(1600); // Changed EXIF
-        img.flag = "none".to_string(); // Would reset flag!
+        img.flag = Flag::None; // Resets the flag
         db.upsert_image(&img).unwrap();
 
         let loaded = db.load_images().unwrap();
         assert_eq!(loaded[0].iso, Some(1600));
-        // Flag was reset by upsert — this is expected for v1.
-        // v2 should merge EXIF changes while preserving user metadata.
-        assert_eq!(loaded[0].flag, "none");
+        assert_eq!(loaded[0].flag, Flag::None);
+        assert_eq!(loaded[0].rating, 0);
+    }
+
+    #[test]
+    fn test_merge_images_keeps_annotations_and_marks_missing() {
+        let (db, _dir) = test_db();
+        let mut kept = sample_image("/photos/a.NEF");
+        kept.micro_cached = true;
+        db.upsert_images(&[kept.clone(), sample_image("/photos/b.NEF")]).unwrap();
+        db.update_flag("/photos/a.NEF", Flag::Pick).unwrap();
+        db.update_rating("/photos/a.NEF", 4).unwrap();
+        db.update_color_label("/photos/b.NEF", ColorLabel::Red).unwrap();
+
+        // a.NEF re-read with new EXIF, b.NEF gone, c.NEF new
+        kept.iso = Some(1600);
+        kept.micro_cached = false;
+        kept.burst_group_id = Some("burst-1".to_string());
+        let summary = db.merge_images(&[kept, sample_image("/photos/c.NEF")]).unwrap();
+
+        assert_eq!(summary, MergeSummary { added: 1, updated: 1, missing: 1 });
+        let loaded: HashMap<String, ImageRecord> = db.load_images().unwrap()
+            .into_iter()
+            .map(|r| (r.file_path.clone(), r))
+            .collect();
+        let a = &loaded["/photos/a.NEF"];
+        assert_eq!((a.iso, a.flag, a.rating), (Some(1600), Flag::Pick, 4));
+        assert_eq!(a.burst_group_id.as_deref(), Some("burst-1"));
+        assert!(a.micro_cached, "unchanged file keeps its cache flags");
+        assert!(loaded["/photos/b.NEF"].missing);
+        assert_eq!(loaded["/photos/b.NEF"].color_label, ColorLabel::Red);
+        assert!(!loaded["/photos/c.NEF"].missing);
+
+        // The card comes back: b.NEF reappears with its label
+        let summary = db.merge_images(&[sample_image("/photos/b.NEF")]).unwrap();
+        assert_eq!(summary.missing, 2);
+        let b = db.load_images().unwrap().into_iter().find(|r| r.file_path == "/photos/b.NEF").unwrap();
+        assert!(!b.missing);
+        assert_eq!(b.color_label, ColorLabel::Red);
+    }
+
+    #[test]
+    fn test_diff_folder_by_size_and_mtime() {
+        let (db, _dir) = test_db();
+        let mut raw = sample_image("/card/1.NEF");
+        raw.sidecar_path = Some("/card/1.JPG".to_string());
+        raw.native_burst_id = Some(-42);
+        let gone = sample_image("/card/2.NEF");
+        let mut was_missing = sample_image("/card/3.NEF");
+        was_missing.missing = true;
+        db.upsert_images(&[raw, gone, was_missing, sample_image("/card/4.NEF"), sample_image("/card/5.NEF")]).unwrap();
+        let file = |path: &str, size: i64| FileState {
+            file_path: path.to_string(),
+            file_size: size,
+            file_mtime: 1700000000,
+        };
+
+        let diff = db.diff_folder(&[
+            file("/card/1.NEF", 50_000_000),
+            file("/card/1.JPG", 9_000_000),
+            file("/card/3.NEF", 50_000_000),
+            file("/card/4.NEF", 51_000_000),
+            file("/card/5.NEF", 50_000_000),
+            file("/card2/1.NEF", 50_000_000),
+        ]).unwrap();
+
+        assert_eq!(diff.unchanged, vec!["/card/1.NEF", "/card/5.NEF"]);
+        assert_eq!(diff.changed, vec!["/card/4.NEF"]);
+        assert_eq!(diff.added, vec!["/card/3.NEF", "/card2/1.NEF"]);
+        assert_eq!(diff.removed, vec!["/card/2.NEF"]);
+        let raw = db.load_images().unwrap().into_iter().find(|r| r.file_path == "/card/1.NEF").unwrap();
+        assert_eq!(raw.native_burst_id, Some(-42));
+    }
+
+    #[test]
+    fn test_undo_redo_batch_reject() {
+        let (db, _dir) = test_db();
+        let paths = ["/burst/1.NEF", "/burst/2.NEF", "/burst/3.NEF"];
+        for path in paths {
+            db.upsert_image(&sample_image(path)).unwrap();
+        }
+        db.update_flag("/burst/2.NEF", Flag::Pick).unwrap();
+        db.update_rating("/burst/2.NEF", 5).unwrap();
+
+        // Mis-keyed reject of the whole burst
+        let rejects: Vec<(&str, Flag)> = paths.iter().map(|p| (*p, Flag::Reject)).collect();
+        db.update_flags_batch(&rejects).unwrap();
+
+        let restored = db.undo().unwrap().unwrap();
+        assert_eq!(restored.len(), 3);
+        let flags: HashMap<String, Flag> = db.load_images().unwrap().into_iter().map(|r| (r.file_path, r.flag)).collect();
+        assert_eq!(flags["/burst/1.NEF"], Flag::None);
+        assert_eq!(flags["/burst/2.NEF"], Flag::Pick);
+        assert_eq!(db.journal_status().unwrap(), JournalStatus { can_undo: true, can_redo: true });
+
+        // Survives a restart
+        let db = db.reopen().unwrap();
+        let redone = db.redo().unwrap().unwrap();
+        assert!(redone.iter().all(|a| a.flag == Flag::Reject));
+        assert!(db.redo().unwrap().is_none());
+
+        // Undo all the way back, then a fresh edit drops the redo history
+        db.undo().unwrap();
+        let rating = db.undo().unwrap().unwrap();
+        assert_eq!(rating, vec![AnnotationState {
+            file_path: "/burst/2.NEF".to_string(),
+            flag: Flag::Pick,
+            rating: 0,
+            color_label: ColorLabel::None,
+        }]);
+        db.undo().unwrap();
+        assert!(db.undo().unwrap().is_none());
+        db.update_color_label("/burst/3.NEF", ColorLabel::Red).unwrap();
+        assert_eq!(db.journal_status().unwrap(), JournalStatus { can_undo: true, can_redo: false });
+    }
+
+    #[test]
+    fn test_journal_pairs_and_no_op_edits() {
+        let (db, _dir) = test_db();
+        let mut raw = sample_image("/card/1.NEF");
+        raw.sidecar_path = Some("/card/1.JPG".to_string());
+        db.upsert_image(&raw).unwrap();
+
+        // Setting a value it already has isn't an operation
+        db.update_flag("/card/1.NEF", Flag::None).unwrap();
+        assert!(!db.journal_status().unwrap().can_undo);
+
+        db.update_flag("/card/1.JPG", Flag::Pick).unwrap();
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!(undone[0].file_path, "/card/1.NEF");
+        assert_eq!(undone[0].flag, Flag::None);
+    }
+
+    #[test]
+    fn test_journal_bounded() {
+        let (db, _dir) = test_db();
+        db.upsert_image(&sample_image("/card/1.NEF")).unwrap();
+        for i in 0..(JOURNAL_MAX_OPERATIONS + 10) {
+            db.update_rating("/card/1.NEF", (i % 5 + 1) as i32).unwrap();
+        }
+
+        let operations: i64 = db.conn
+            .query_row("SELECT COUNT(DISTINCT operation_id) FROM annotation_journal", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(operations, JOURNAL_MAX_OPERATIONS);
+        let mut undone = 0;
+        while db.undo().unwrap().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, JOURNAL_MAX_OPERATIONS);
     }
 }
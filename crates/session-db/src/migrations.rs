@@ -0,0 +1,426 @@
+//! Versioned schema migrations.
+//!
+//! Session databases live in users' `~/.projectloupe/cache` across app
+//! updates, so the schema can't just be recreated. `PRAGMA user_version`
+//! records how many steps of `MIGRATIONS` a database has run; opening runs
+//! the rest in order, each in its own transaction together with its version
+//! bump, so a failed step leaves the database at the last good version.
+//!
+//! Version 0 is a database from before versioning. Those may hold any subset
+//! of the v1 and v2 schema, so those two steps tolerate existing tables and
+//! columns. Steps after v2 only ever see the previous version and can
+//! alter tables directly.
+//!
+//! Never edit a shipped step; add a new one.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+
+/// Upgrades a database by one version.
+pub(crate) type Migration = fn(&Connection) -> Result<()>;
+
+/// `MIGRATIONS[i]` upgrades a database from version `i` to `i + 1`.
+const MIGRATIONS: &[Migration] = &[v1_initial, v2_pairs_clocks_and_scoring, v3_missing_images, v4_rescan_fields, v5_annotation_journal, v6_annotation_checks];
+
+/// Schema version this build creates and understands.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Bring `conn` up to `SCHEMA_VERSION`.
+pub(crate) fn migrate(conn: &Connection) -> Result<()> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+pub(crate) fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+fn run_migrations(conn: &Connection, migrations: &[Migration]) -> Result<()> {
+    let latest = migrations.len() as u32;
+    let version = schema_version(conn)?;
+    if version > latest {
+        bail!(
+            "Session database is schema version {}, but this version of ProjectLoupe only supports up to {}. \
+             Update ProjectLoupe to open it.",
+            version,
+            latest
+        );
+    }
+
+    for (from, step) in migrations.iter().enumerate().skip(version as usize) {
+        let to = from as u32 + 1;
+        let tx = conn.unchecked_transaction()?;
+        step(&tx).with_context(|| format!("Migration to schema version {} failed", to))?;
+        tx.pragma_update(None, "user_version", to)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Add a column unless a pre-versioning build already did.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1", table),
+        params![column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+    }
+    Ok(())
+}
+
+/// The schema as first shipped: images, burst groups and session metadata.
+fn v1_initial(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS images (
+            file_path TEXT PRIMARY KEY,
+            filename TEXT NOT NULL,
+            file_size INTEGER NOT NULL DEFAULT 0,
+            file_mtime INTEGER NOT NULL DEFAULT 0,
+            cache_hash TEXT NOT NULL DEFAULT '',
+            serial_number TEXT NOT NULL DEFAULT '',
+            drive_mode TEXT NOT NULL DEFAULT 'Single',
+            capture_time TEXT NOT NULL DEFAULT '',
+            make TEXT,
+            model TEXT,
+            lens TEXT,
+            focal_length REAL,
+            aperture REAL,
+            shutter_speed TEXT,
+            iso INTEGER,
+            rating INTEGER NOT NULL DEFAULT 0,
+            flag TEXT NOT NULL DEFAULT 'none',
+            color_label TEXT NOT NULL DEFAULT 'none',
+            burst_group_id TEXT,
+            burst_index INTEGER,
+            micro_cached INTEGER NOT NULL DEFAULT 0,
+            preview_cached INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS burst_groups (
+            id TEXT PRIMARY KEY,
+            camera_serial TEXT NOT NULL,
+            frame_count INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL DEFAULT 0,
+            avg_gap_ms REAL NOT NULL DEFAULT 0,
+            estimated_fps REAL NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS session_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_images_burst ON images(burst_group_id);
+        CREATE INDEX IF NOT EXISTS idx_images_serial ON images(serial_number);
+        CREATE INDEX IF NOT EXISTS idx_images_capture ON images(capture_time);
+        CREATE INDEX IF NOT EXISTS idx_images_flag ON images(flag);
+        CREATE INDEX IF NOT EXISTS idx_images_rating ON images(rating);
+        ",
+    )?;
+    Ok(())
+}
+
+/// RAW+JPEG pairs, clock offsets, best-pick rankings, quality scores,
+/// perceptual hashes, and how each burst was detected.
+fn v2_pairs_clocks_and_scoring(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS image_pairs (
+            primary_path TEXT PRIMARY KEY,
+            sidecar_path TEXT NOT NULL UNIQUE
+        );
+
+        CREATE TABLE IF NOT EXISTS camera_clock_offsets (
+            serial_number TEXT PRIMARY KEY,
+            offset_ms INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS burst_rankings (
+            burst_id TEXT PRIMARY KEY,
+            suggested_index INTEGER NOT NULL,
+            suggested_path TEXT NOT NULL,
+            ranking TEXT NOT NULL DEFAULT '[]',
+            scores TEXT NOT NULL DEFAULT '[]'
+        );
+
+        CREATE TABLE IF NOT EXISTS quality_weights (
+            metric TEXT PRIMARY KEY,
+            weight REAL NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS quality_scores (
+            file_path TEXT PRIMARY KEY,
+            cache_hash TEXT NOT NULL,
+            overall_score REAL NOT NULL,
+            sharpness REAL NOT NULL,
+            exposure REAL NOT NULL,
+            composition REAL NOT NULL,
+            technical_quality REAL NOT NULL,
+            metrics TEXT NOT NULL DEFAULT '{}'
+        );
+
+        -- Hashes are u64 bit patterns stored as INTEGER (i64)
+        CREATE TABLE IF NOT EXISTS perceptual_hashes (
+            file_path TEXT PRIMARY KEY,
+            cache_hash TEXT NOT NULL,
+            dhash INTEGER NOT NULL,
+            phash INTEGER NOT NULL
+        );
+        ",
+    )?;
+    // Bursts detected before strategies were recorded came from the drive-mode fallback
+    add_column_if_missing(conn, "burst_groups", "source", "TEXT NOT NULL DEFAULT 'DriveMode'")?;
+    add_column_if_missing(conn, "burst_groups", "confidence", "REAL NOT NULL DEFAULT 0.8")?;
+    Ok(())
+}
+
+/// Re-import marks vanished files instead of deleting them.
+fn v3_missing_images(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE images ADD COLUMN missing INTEGER NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+/// EXIF fields a rescan needs to re-detect bursts from stored records.
+fn v4_rescan_fields(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE images ADD COLUMN utc_offset_secs INTEGER;
+         ALTER TABLE images ADD COLUMN native_burst_id INTEGER;",
+    )?;
+    Ok(())
+}
+
+/// Old and new values of every annotation change, grouped into undoable operations.
+fn v5_annotation_journal(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE annotation_journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation_id INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            field TEXT NOT NULL,
+            -- Untyped: TEXT for flag and color_label, INTEGER for rating
+            old_value,
+            new_value
+        );
+        CREATE INDEX idx_journal_operation ON annotation_journal(operation_id);
+        ",
+    )?;
+    Ok(())
+}
+
+/// CHECK constraints on flag, color label and rating. SQLite can't add a
+/// constraint to an existing table, so images is rebuilt; values the
+/// constraints would reject (typos from before validation) are reset.
+fn v6_annotation_checks(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE images_checked (
+            file_path TEXT PRIMARY KEY,
+            filename TEXT NOT NULL,
+            file_size INTEGER NOT NULL DEFAULT 0,
+            file_mtime INTEGER NOT NULL DEFAULT 0,
+            cache_hash TEXT NOT NULL DEFAULT '',
+            serial_number TEXT NOT NULL DEFAULT '',
+            drive_mode TEXT NOT NULL DEFAULT 'Single',
+            capture_time TEXT NOT NULL DEFAULT '',
+            make TEXT,
+            model TEXT,
+            lens TEXT,
+            focal_length REAL,
+            aperture REAL,
+            shutter_speed TEXT,
+            iso INTEGER,
+            rating INTEGER NOT NULL DEFAULT 0 CHECK (rating BETWEEN 0 AND 5),
+            flag TEXT NOT NULL DEFAULT 'none' CHECK (flag IN ('none', 'pick', 'reject')),
+            color_label TEXT NOT NULL DEFAULT 'none'
+                CHECK (color_label IN ('none', 'red', 'yellow', 'green', 'blue', 'purple')),
+            burst_group_id TEXT,
+            burst_index INTEGER,
+            micro_cached INTEGER NOT NULL DEFAULT 0,
+            preview_cached INTEGER NOT NULL DEFAULT 0,
+            missing INTEGER NOT NULL DEFAULT 0,
+            utc_offset_secs INTEGER,
+            native_burst_id INTEGER
+        );
+
+        INSERT INTO images_checked
+        SELECT
+            file_path, filename, file_size, file_mtime, cache_hash,
+            serial_number, drive_mode, capture_time,
+            make, model, lens, focal_length, aperture, shutter_speed, iso,
+            MIN(MAX(rating, 0), 5),
+            CASE WHEN flag IN ('none', 'pick', 'reject') THEN flag ELSE 'none' END,
+            CASE WHEN color_label IN ('none', 'red', 'yellow', 'green', 'blue', 'purple')
+                THEN color_label ELSE 'none' END,
+            burst_group_id, burst_index,
+            micro_cached, preview_cached, missing, utc_offset_secs, native_burst_id
+        FROM images;
+
+        DROP TABLE images;
+        ALTER TABLE images_checked RENAME TO images;
+
+        CREATE INDEX idx_images_burst ON images(burst_group_id);
+        CREATE INDEX idx_images_serial ON images(serial_number);
+        CREATE INDEX idx_images_capture ON images(capture_time);
+        CREATE INDEX idx_images_flag ON images(flag);
+        CREATE INDEX idx_images_rating ON images(rating);
+
+        -- Undo and redo must not write back what the constraints now reject
+        UPDATE annotation_journal SET
+            old_value = CASE WHEN old_value IN ('none', 'pick', 'reject') THEN old_value ELSE 'none' END,
+            new_value = CASE WHEN new_value IN ('none', 'pick', 'reject') THEN new_value ELSE 'none' END
+            WHERE field = 'flag';
+        UPDATE annotation_journal SET
+            old_value = CASE WHEN old_value IN ('none', 'red', 'yellow', 'green', 'blue', 'purple')
+                THEN old_value ELSE 'none' END,
+            new_value = CASE WHEN new_value IN ('none', 'red', 'yellow', 'green', 'blue', 'purple')
+                THEN new_value ELSE 'none' END
+            WHERE field = 'color_label';
+        UPDATE annotation_journal SET
+            old_value = MIN(MAX(old_value, 0), 5),
+            new_value = MIN(MAX(new_value, 0), 5)
+            WHERE field = 'rating';
+        ",
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorLabel, Flag};
+
+    /// A session as written by the first release: the v1 tables at user_version
+    /// 0, holding a burst, its frames and a flagged single.
+    const BASELINE_FIXTURE: &str = include_str!("../fixtures/session_baseline.sql");
+
+    fn table_exists(conn: &Connection, table: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_fresh_database_gets_latest_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(table_exists(&conn, "perceptual_hashes"));
+        // Idempotent once current
+        migrate(&conn).unwrap();
+    }
+
+    #[test]
+    fn test_baseline_fixture_upgrades_and_keeps_data() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(BASELINE_FIXTURE).unwrap();
+        // Unversioned, so v1 runs over tables that already exist
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        migrate(&conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        let frames: Vec<(String, String)> = conn
+            .prepare("SELECT file_path, flag FROM images WHERE burst_group_id = 'burst_A_1' ORDER BY burst_index").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(frames, vec![
+            ("/card/DSC_0001.NEF".to_string(), "none".to_string()),
+            ("/card/DSC_0002.NEF".to_string(), "reject".to_string()),
+            ("/card/DSC_0003.NEF".to_string(), "none".to_string()),
+        ]);
+        let (flag, rating): (String, i32) = conn.query_row(
+            "SELECT flag, rating FROM images WHERE file_path = '/card/DSC_0010.NEF'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((flag.as_str(), rating), ("pick", 4));
+        let (source, confidence): (String, f64) = conn.query_row(
+            "SELECT source, confidence FROM burst_groups WHERE id = 'burst_A_1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((source.as_str(), confidence), ("DriveMode", 0.8));
+        let root: String = conn
+            .query_row("SELECT value FROM session_meta WHERE key = 'root_folder'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(root, "/card");
+        for table in ["image_pairs", "camera_clock_offsets", "burst_rankings", "quality_scores", "annotation_journal"] {
+            assert!(table_exists(&conn, table), "{} missing", table);
+        }
+    }
+
+    #[test]
+    fn test_annotation_checks_clean_up_and_enforce() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn, &MIGRATIONS[..5]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO images (file_path, filename, flag, color_label, rating)
+             VALUES ('/card/1.NEF', '1.NEF', 'pikc', 'mauve', 9), ('/card/2.NEF', '2.NEF', 'pick', 'red', 3);
+             -- An undone edit of 2.NEF that recorded the same typos, waiting to be redone
+             INSERT INTO annotation_journal (operation_id, file_path, field, old_value, new_value)
+             VALUES (1, '/card/2.NEF', 'flag', 'pick', 'pikc'),
+                    (1, '/card/2.NEF', 'color_label', 'mauve', 'red'),
+                    (1, '/card/2.NEF', 'rating', 3, 9);",
+        ).unwrap();
+
+        migrate(&conn).unwrap();
+
+        let rows: Vec<(String, String, i32)> = conn
+            .prepare("SELECT flag, color_label, rating FROM images ORDER BY file_path").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, vec![
+            ("none".to_string(), "none".to_string(), 5),
+            ("pick".to_string(), "red".to_string(), 3),
+        ]);
+        for bad in ["flag = 'pikc'", "color_label = 'mauve'", "rating = 6", "rating = -1"] {
+            let result = conn.execute(&format!("UPDATE images SET {} WHERE file_path = '/card/2.NEF'", bad), []);
+            assert!(result.is_err(), "{} accepted", bad);
+        }
+
+        let db = crate::SessionDb { conn, db_path: Default::default() };
+        let redone = db.redo().unwrap().unwrap();
+        assert_eq!((redone[0].flag, redone[0].color_label, redone[0].rating), (Flag::None, ColorLabel::Red, 5));
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!((undone[0].flag, undone[0].color_label, undone[0].rating), (Flag::Pick, ColorLabel::None, 3));
+    }
+
+    #[test]
+    fn test_newer_database_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+
+        let err = migrate(&conn).unwrap_err().to_string();
+
+        assert!(err.contains(&format!("schema version {}", SCHEMA_VERSION + 1)), "{}", err);
+        assert!(err.contains("Update ProjectLoupe"), "{}", err);
+    }
+
+    #[test]
+    fn test_failed_step_rolls_back_to_last_good_version() {
+        fn create_notes(conn: &Connection) -> Result<()> {
+            conn.execute_batch("CREATE TABLE notes (id INTEGER PRIMARY KEY)")?;
+            Ok(())
+        }
+        fn half_done(conn: &Connection) -> Result<()> {
+            conn.execute_batch("ALTER TABLE notes ADD COLUMN body TEXT")?;
+            bail!("disk full")
+        }
+        let conn = Connection::open_in_memory().unwrap();
+
+        let err = run_migrations(&conn, &[create_notes, half_done]).unwrap_err();
+
+        assert!(format!("{:#}", err).contains("schema version 2"));
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        let columns: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_table_info('notes')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(columns, 1);
+    }
+}
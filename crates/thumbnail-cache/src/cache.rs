@@ -8,6 +8,8 @@ use crate::generate::{ThumbnailTier, generate_thumbnail, extract_color_swatch, C
 use crate::lru::LruCache;
 use crate::{ThumbnailConfig, generate_cache_key};
 use anyhow::{Context, Result, bail};
+use burst_detection::quality::PreviewSource;
+use burst_detection::similarity::MicroSource;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
@@ -16,9 +18,7 @@ use std::sync::Arc;
 
 /// Main thumbnail cache manager
 pub struct ThumbnailCache {
-    session_hash: String,
     cache_dir: PathBuf,
-    config: ThumbnailConfig,
     micro_cache: Arc<LruCache<String, Vec<u8>>>,
     preview_cache: Arc<LruCache<String, Vec<u8>>>,
     loupe_cache: Arc<LruCache<String, Vec<u8>>>,
@@ -48,9 +48,7 @@ impl ThumbnailCache {
         let loupe_cache = Arc::new(LruCache::new(config.loupe_memory_budget));
 
         Ok(Self {
-            session_hash: session_hash.to_string(),
             cache_dir,
-            config,
             micro_cache,
             preview_cache,
             loupe_cache,
@@ -157,7 +155,7 @@ impl ThumbnailCache {
                     let file_hash = generate_cache_key(path)?;
 
                     // Check if already cached
-                    if let Some(_) = self.get(&file_hash, tier) {
+                    if self.get(&file_hash, tier).is_some() {
                         return Ok(self.get_disk_cache_path(&file_hash, tier).to_string_lossy().to_string());
                     }
 
@@ -246,6 +244,21 @@ impl ThumbnailCache {
     }
 }
 
+/// Quality scoring runs on the Preview tier, so scoring a session also warms
+/// the cache the grid reads from.
+impl PreviewSource for ThumbnailCache {
+    fn preview_jpeg(&self, path: &Path) -> Result<Vec<u8>> {
+        self.get_or_generate(&path.to_string_lossy(), ThumbnailTier::Preview)
+    }
+}
+
+/// Near-duplicate hashing runs on the Micro tier the grid already shows.
+impl MicroSource for ThumbnailCache {
+    fn micro_jpeg(&self, path: &Path) -> Result<Vec<u8>> {
+        self.get_or_generate(&path.to_string_lossy(), ThumbnailTier::Micro)
+    }
+}
+
 /// Cache statistics for monitoring and debugging
 #[derive(Debug, Clone)]
 pub struct CacheStats {
@@ -290,7 +303,6 @@ mod tests {
 
     #[test]
     fn test_cache_creation() -> Result<()> {
-        let temp_dir = tempdir()?;
         let session_hash = "test_session";
         
         let cache = ThumbnailCache::new(session_hash)?;
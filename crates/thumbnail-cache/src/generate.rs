@@ -109,8 +109,8 @@ pub fn generate_thumbnail(file_path: &Path, tier: ThumbnailTier) -> Result<Vec<u
     // Resize if needed
     let resized_img = resize_image(img, tier);
     
-    // Encode to JPEG at target quality
-    encode_jpeg(resized_img, tier.jpeg_quality())
+    // Re-encode as JPEG
+    encode_jpeg(resized_img)
 }
 
 /// Extract embedded JPEG from a RAW file using exiftool
@@ -158,8 +158,10 @@ fn resize_image(img: DynamicImage, tier: ThumbnailTier) -> DynamicImage {
     img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
 }
 
-/// Encode image as JPEG with specified quality
-fn encode_jpeg(img: DynamicImage, quality: u8) -> Result<Vec<u8>> {
+/// Encode image as JPEG at the encoder's default quality. The per-tier
+/// `jpeg_quality` isn't applied: doing so changes every cached file's bytes,
+/// which needs cache invalidation first.
+fn encode_jpeg(img: DynamicImage) -> Result<Vec<u8>> {
     let mut buffer = Vec::new();
     let mut cursor = Cursor::new(&mut buffer);
     
@@ -225,8 +227,6 @@ pub fn extract_color_swatch(file_path: &Path) -> Result<ColorSwatch> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use tempfile::tempdir;
 
     #[test]
     fn test_thumbnail_tier_parsing() {
@@ -68,8 +68,8 @@ pub fn generate_cache_key(file_path: &Path) -> Result<String> {
 
     let mut hasher = Sha256::new();
     hasher.update(absolute_path.as_bytes());
-    hasher.update(&file_size.to_le_bytes());
-    hasher.update(&modified_time.to_le_bytes());
+    hasher.update(file_size.to_le_bytes());
+    hasher.update(modified_time.to_le_bytes());
     
     let result = hasher.finalize();
     // Use first 16 bytes (32 hex chars) for a compact but collision-resistant key
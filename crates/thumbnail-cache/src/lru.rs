@@ -86,9 +86,8 @@ impl<K: Clone + Hash + Eq, V: Clone> LruCacheInner<K, V> {
         K: std::borrow::Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        if let Some((value, byte_size, _)) = self.data.get(key) {
+        if let Some((value, _, _)) = self.data.get(key) {
             let value = value.clone();
-            let byte_size = *byte_size;
             
             // Update access time
             self.access_counter += 1;
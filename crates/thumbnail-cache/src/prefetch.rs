@@ -80,11 +80,7 @@ impl PrefetchScheduler {
         self.cancel_current_job();
 
         let total_files = job.file_paths.len();
-        let viewport_total = if job.viewport_end > job.viewport_start {
-            job.viewport_end - job.viewport_start
-        } else {
-            0
-        };
+        let viewport_total = job.viewport_end.saturating_sub(job.viewport_start);
 
         let progress = Arc::new(Mutex::new(PrefetchProgress {
             total_files,
@@ -182,7 +178,6 @@ impl PrefetchScheduler {
         }
 
         // Add non-viewport items in expanding rings
-        let viewport_center = (viewport_start + viewport_end) / 2;
         let mut before_items = Vec::new();
         let mut after_items = Vec::new();
 
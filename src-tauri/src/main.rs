@@ -1,7 +1,7 @@
 //! ProjectLoupe Tauri backend — bridges the Rust burst-detection crate to the React frontend.
 //!
 //! Command architecture:
-//! - `import_folder`: Scan → exiftool EXIF extraction → burst detection → structured JSON response
+//! - `import_folder`: Scan → EXIF extraction → burst detection → structured JSON response
 //! - `extract_thumbnails`: Batch extract PreviewImage (640px) for grid thumbnails
 //! - `extract_loupe_image` / `extract_burst_loupe_images`: On-demand JpgFromRaw (8K) for loupe view
 //!
@@ -12,19 +12,43 @@
 //! Both tiers cache to ~/.projectloupe/cache/{thumbnails,loupe}/ and are served to the
 //! frontend via Tauri's asset:// protocol (convertFileSrc).
 //!
-//! State management: AppState holds a persistent exiftool process (Mutex<Option<ExiftoolRunner>>)
-//! to avoid respawning for each command. The last BurstResult is cached for the analysis endpoint.
+//! `rescan_folder` is the fast path for re-opening a known folder (or adding a second card):
+//! only new or modified files (by size and mtime) go through EXIF extraction, unchanged frames
+//! are rebuilt from the session DB, and only bodies with changes are re-detected.
+//!
+//! State management: AppState holds a persistent EXIF source (Mutex<Option<Box<dyn ExifSource>>>)
+//! to avoid respawning for each command — the native reader, with a pool of warm exiftool processes
+//! as fallback when exiftool is installed. The last BurstResult is cached for the analysis endpoint.
+//!
+//! Quality scoring runs as a background `ScoringJob` started after import. It writes through a
+//! second SessionDb connection and reports via `scoring-progress` events; scores persist, so a
+//! re-import only scores new or edited files.
+//!
+//! Burst edits (`split_burst`, `merge_bursts`, `detach_from_burst`, `attach_to_burst`) mark the
+//! groups they touch as Manual. Re-detection re-applies Manual groups from the session DB on top
+//! of its own result, so the user's corrections survive re-imports and clock fixes.
 
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tauri::{command, State, Emitter};
-use burst_detection::{BurstDetector, BurstResult, ExifData, ExiftoolRunner};
-use session_db::{SessionDb, ImageRecord, BurstGroupRecord};
+use burst_detection::{
+    apply_clock_offsets, clock_offset_from_pair, pair_raw_jpeg, BurstDetector, BurstEdit, BurstGroup, BurstResult,
+    BurstSource, CameraInfo, DriveMode, ExifData, ExifError,
+    BurstDetectorConfig, BurstRanker, BurstRanking, ExifSource, ExiftoolPool, NativeExifReader, QualityAnalyzer, QualityScore,
+    ScoredImage, ScoringItem, ScoringJob, ScoringProgress, TimelineEntry, WeightProfile,
+    group_similar, hash_images, PerceptualHash, SimilarityConfig,
+};
+use burst_detection::quality::algorithms::{self as quality_algorithms, ExposureMetrics};
+use session_db::{
+    SessionDb, AnnotationState, ImageRecord, BurstGroupRecord, BurstMembership, BurstRankingRecord, ColorLabel, FileState, Flag, JournalStatus,
+    PerceptualHashRecord, QualityScoreRecord, validate_rating,
+};
 use thumbnail_cache::{ThumbnailCache, ThumbnailTier};
 
 /// Supported image file extensions
@@ -35,8 +59,8 @@ const IMAGE_EXTENSIONS: &[&str] = &[
 // -- State --
 
 struct AppState {
-    /// Persistent exiftool process for fast EXIF extraction
-    exiftool: Mutex<Option<ExiftoolRunner>>,
+    /// EXIF backend: native reader, falling back to a pool of persistent exiftool processes if available
+    exif_source: Mutex<Option<Box<dyn ExifSource + Send>>>,
     /// Last analysis result (cached for frontend queries)
     last_result: Mutex<Option<BurstResult>>,
     /// Cache directory for thumbnails
@@ -46,7 +70,9 @@ struct AppState {
     /// SQLite session database (initialized on first import/load)
     session_db: Mutex<Option<SessionDb>>,
     /// New thumbnail cache manager (v2)
-    thumbnail_cache_v2: Mutex<Option<ThumbnailCache>>,
+    thumbnail_cache_v2: Mutex<Option<Arc<ThumbnailCache>>>,
+    /// Background quality scoring for the current session, if one was started
+    scoring_job: Mutex<Option<ScoringJob>>,
 }
 
 // -- Command payloads --
@@ -64,9 +90,43 @@ struct ImportResult {
     result: Option<BurstResultPayload>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    /// Files skipped during EXIF extraction (corrupt, unsupported, or no capture time)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exif_errors: Vec<ExifErrorPayload>,
+}
+
+/// Result of an incremental rescan: the full session plus what changed on disk.
+#[derive(Debug, Serialize)]
+struct RescanResult {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<BurstResultPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exif_errors: Vec<ExifErrorPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<RescanDiffPayload>,
 }
 
-/// Serializable burst result for the frontend
+/// File paths by how they changed since the session was last written.
+#[derive(Debug, Serialize)]
+struct RescanDiffPayload {
+    added: Vec<String>,
+    changed: Vec<String>,
+    removed: Vec<String>,
+}
+
+/// A single file that produced no EXIF data, reported instead of failing the import
+#[derive(Debug, Serialize)]
+struct ExifErrorPayload {
+    file_path: String,
+    kind: String,
+    message: String,
+}
+
+/// Serializable burst result for the frontend.
+/// `bursts` and `singles` are in timeline order; `timeline` interleaves them.
 #[derive(Debug, Serialize)]
 struct BurstResultPayload {
     total_images: usize,
@@ -75,6 +135,17 @@ struct BurstResultPayload {
     cameras: Vec<CameraPayload>,
     bursts: Vec<BurstPayload>,
     singles: Vec<ImagePayload>,
+    timeline: Vec<TimelineEntryPayload>,
+}
+
+/// One position on the cross-camera timeline: a burst (by ID) or a single (by path)
+#[derive(Debug, Serialize)]
+struct TimelineEntryPayload {
+    position: usize,
+    kind: String, // "burst" | "single"
+    id: String,
+    camera_serial: String,
+    start_time: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -84,8 +155,14 @@ struct CameraPayload {
     model: String,
     image_count: usize,
     burst_count: usize,
+    clock_offset_ms: i64,
+    /// Detection strategy for this body ("NativeId" | "DriveMode" | "Similarity")
+    burst_source: String,
+    /// Mean confidence of this body's detected bursts
+    burst_confidence: f64,
 }
 
+
 #[derive(Debug, Serialize)]
 struct BurstPayload {
     id: String,
@@ -94,6 +171,19 @@ struct BurstPayload {
     duration_ms: i64,
     avg_gap_ms: f64,
     estimated_fps: f64,
+    /// "NativeId" (ground truth) | "DriveMode" | "Similarity"
+    source: String,
+    /// Detection confidence in [0, 1]
+    confidence: f64,
+    images: Vec<ImagePayload>,
+}
+
+/// Near-duplicate singles (see `burst_detection::similarity`)
+#[derive(Debug, Serialize)]
+struct SimilarityGroupPayload {
+    id: String,
+    camera_serial: String,
+    frame_count: usize,
     images: Vec<ImagePayload>,
 }
 
@@ -101,9 +191,14 @@ struct BurstPayload {
 struct ImagePayload {
     file_path: String,
     filename: String,
+    /// JPEG half of a RAW+JPEG pair (file_path is the RAW)
+    sidecar_path: Option<String>,
     serial_number: String,
     drive_mode: String,
     capture_time: String,
+    /// Seconds east of UTC from OffsetTimeOriginal, for displaying local time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    utc_offset_secs: Option<i32>,
     make: Option<String>,
     model: Option<String>,
     lens: Option<String>,
@@ -124,9 +219,11 @@ fn exif_to_payload(exif: &ExifData) -> ImagePayload {
             .and_then(|n| n.to_str())
             .unwrap_or("unknown")
             .to_string(),
+        sidecar_path: exif.sidecar_path.as_ref().map(|p| p.display().to_string()),
         serial_number: exif.serial_number.clone(),
         drive_mode: format!("{:?}", exif.drive_mode),
         capture_time: exif.capture_time.to_rfc3339(),
+        utc_offset_secs: exif.utc_offset_secs,
         make: exif.make.clone(),
         model: exif.model.clone(),
         lens: exif.lens.clone(),
@@ -139,55 +236,89 @@ fn exif_to_payload(exif: &ExifData) -> ImagePayload {
     }
 }
 
+fn exif_error_to_payload(err: &ExifError) -> ExifErrorPayload {
+    ExifErrorPayload {
+        file_path: err.file_path().display().to_string(),
+        kind: err.kind().to_string(),
+        message: err.to_string(),
+    }
+}
+
+fn camera_to_payload(camera: &CameraInfo) -> CameraPayload {
+    CameraPayload {
+        serial: camera.serial.clone(),
+        make: camera.make.clone(),
+        model: camera.model.clone(),
+        image_count: camera.image_count,
+        burst_count: camera.burst_count,
+        clock_offset_ms: camera.clock_offset_ms,
+        burst_source: format!("{:?}", camera.burst_source),
+        burst_confidence: camera.burst_confidence,
+    }
+}
+
 fn result_to_payload(result: &BurstResult) -> BurstResultPayload {
+    let timeline = result.timeline();
+    let mut bursts = Vec::new();
+    let mut singles = Vec::new();
+    let mut entries = Vec::with_capacity(timeline.len());
+
+    for (position, entry) in timeline.entries().iter().enumerate() {
+        let kind = match entry {
+            TimelineEntry::Burst(b) => {
+                bursts.push(BurstPayload {
+                    id: b.id.clone(),
+                    camera_serial: b.camera_serial.clone(),
+                    frame_count: b.frame_count,
+                    duration_ms: b.duration_ms,
+                    avg_gap_ms: b.avg_gap_ms,
+                    estimated_fps: b.estimated_fps,
+                    source: format!("{:?}", b.source),
+                    confidence: b.confidence,
+                    images: b.images.iter().map(exif_to_payload).collect(),
+                });
+                "burst"
+            }
+            TimelineEntry::Single(img) => {
+                singles.push(exif_to_payload(img));
+                "single"
+            }
+        };
+        entries.push(TimelineEntryPayload {
+            position,
+            kind: kind.to_string(),
+            id: entry.key(),
+            camera_serial: entry.camera_serial().to_string(),
+            start_time: entry.start_time().to_rfc3339(),
+        });
+    }
+
     BurstResultPayload {
         total_images: result.total_images(),
         total_bursts: result.total_bursts(),
         total_singles: result.singles.len(),
-        cameras: result.cameras.iter().map(|c| CameraPayload {
-            serial: c.serial.clone(),
-            make: c.make.clone(),
-            model: c.model.clone(),
-            image_count: c.image_count,
-            burst_count: c.burst_count,
-        }).collect(),
-        bursts: result.bursts.iter().map(|b| BurstPayload {
-            id: b.id.clone(),
-            camera_serial: b.camera_serial.clone(),
-            frame_count: b.frame_count,
-            duration_ms: b.duration_ms,
-            avg_gap_ms: b.avg_gap_ms,
-            estimated_fps: b.estimated_fps,
-            images: b.images.iter().map(exif_to_payload).collect(),
-        }).collect(),
-        singles: result.singles.iter().map(exif_to_payload).collect(),
+        cameras: result.cameras.iter().map(camera_to_payload).collect(),
+        bursts,
+        singles,
+        timeline: entries,
     }
 }
 
 /// Convert a frontend ImagePayload to a database ImageRecord.
 fn payload_to_record(img: &ImagePayload, burst_id: Option<&str>, burst_index: Option<i32>) -> ImageRecord {
-    let path = PathBuf::from(&img.file_path);
-    let (file_size, file_mtime) = std::fs::metadata(&path)
-        .map(|m| {
-            let size = m.len() as i64;
-            let mtime = m.modified()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| d.as_millis() as i64)
-                .unwrap_or(0);
-            (size, mtime)
-        })
-        .unwrap_or((0, 0));
+    let (file_size, file_mtime, cache_hash) = file_cache_state(Path::new(&img.file_path));
 
     ImageRecord {
         file_path: img.file_path.clone(),
         filename: img.filename.clone(),
+        sidecar_path: img.sidecar_path.clone(),
         file_size,
         file_mtime,
-        cache_hash: format!("{:x}", file_size.wrapping_mul(file_mtime.wrapping_add(1))),
+        cache_hash,
         serial_number: img.serial_number.clone(),
         drive_mode: img.drive_mode.clone(),
         capture_time: img.capture_time.clone(),
+        utc_offset_secs: img.utc_offset_secs,
         make: img.make.clone(),
         model: img.model.clone(),
         lens: img.lens.clone(),
@@ -195,16 +326,179 @@ fn payload_to_record(img: &ImagePayload, burst_id: Option<&str>, burst_index: Op
         aperture: img.aperture,
         shutter_speed: img.shutter_speed.clone(),
         iso: img.iso,
+        native_burst_id: img.burst_group_id.map(|id| id as i64),
         rating: 0,
-        flag: "none".to_string(),
-        color_label: "none".to_string(),
+        flag: Flag::None,
+        color_label: ColorLabel::None,
         burst_group_id: burst_id.map(|s| s.to_string()),
         burst_index,
         micro_cached: false,
         preview_cached: false,
+        missing: false,
+    }
+}
+
+/// Size, mtime (ms) and cache hash of a file as it is on disk now.
+/// The hash changes whenever the file does, invalidating anything stored against it.
+fn file_cache_state(path: &Path) -> (i64, i64, String) {
+    let (file_size, file_mtime) = std::fs::metadata(path)
+        .map(|m| {
+            let size = m.len() as i64;
+            let mtime = m.modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+            (size, mtime)
+        })
+        .unwrap_or((0, 0));
+    (file_size, file_mtime, format!("{:x}", file_size.wrapping_mul(file_mtime.wrapping_add(1))))
+}
+
+/// Flatten a payload into DB image records; burst frames carry their group ID and index.
+fn payload_image_records(payload: &BurstResultPayload) -> Vec<ImageRecord> {
+    let mut records: Vec<ImageRecord> = Vec::new();
+    for burst in &payload.bursts {
+        for (i, img) in burst.images.iter().enumerate() {
+            records.push(payload_to_record(img, Some(&burst.id), Some(i as i32)));
+        }
+    }
+    for img in &payload.singles {
+        records.push(payload_to_record(img, None, None));
+    }
+    records
+}
+
+fn burst_group_record(burst: &BurstGroup) -> BurstGroupRecord {
+    BurstGroupRecord {
+        id: burst.id.clone(),
+        camera_serial: burst.camera_serial.clone(),
+        frame_count: burst.frame_count as i32,
+        duration_ms: burst.duration_ms,
+        avg_gap_ms: burst.avg_gap_ms,
+        estimated_fps: burst.estimated_fps,
+        source: format!("{:?}", burst.source),
+        confidence: burst.confidence,
     }
 }
 
+fn payload_burst_records(payload: &BurstResultPayload) -> Vec<BurstGroupRecord> {
+    payload.bursts.iter().map(|b| {
+        BurstGroupRecord {
+            id: b.id.clone(),
+            camera_serial: b.camera_serial.clone(),
+            frame_count: b.frame_count as i32,
+            duration_ms: b.duration_ms,
+            avg_gap_ms: b.avg_gap_ms,
+            estimated_fps: b.estimated_fps,
+            source: b.source.clone(),
+            confidence: b.confidence,
+        }
+    }).collect()
+}
+
+/// The user's manual bursts, to re-apply after detection.
+fn manual_bursts(db: &SessionDb) -> Result<Vec<(String, Vec<PathBuf>)>, String> {
+    Ok(db.load_manual_burst_frames().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(id, frames)| (id, frames.into_iter().map(PathBuf::from).collect()))
+        .collect())
+}
+
+/// Rebuild an image's EXIF from its stored record, so unchanged files need not
+/// be re-read. The stored capture time is already clock-corrected.
+fn record_to_exif(record: &ImageRecord, clock_offsets: &HashMap<String, i64>) -> Option<ExifData> {
+    let capture_time = DateTime::parse_from_rfc3339(&record.capture_time).ok()?.with_timezone(&Utc);
+    let mut exif = ExifData::new(
+        PathBuf::from(&record.file_path),
+        record.serial_number.clone(),
+        record.drive_mode.parse().unwrap_or(DriveMode::Unknown),
+        capture_time,
+    );
+    exif.clock_offset_ms = clock_offsets.get(&record.serial_number).copied().unwrap_or(0);
+    exif.utc_offset_secs = record.utc_offset_secs;
+    exif.make = record.make.clone();
+    exif.model = record.model.clone();
+    exif.lens = record.lens.clone();
+    exif.focal_length = record.focal_length;
+    exif.aperture = record.aperture;
+    exif.shutter_speed = record.shutter_speed.clone();
+    exif.iso = record.iso;
+    exif.burst_group_id = record.native_burst_id.map(|id| id as u64);
+    exif.sidecar_path = record.sidecar_path.as_ref().map(PathBuf::from);
+    Some(exif)
+}
+
+/// Rebuild a detection result from stored records (in `load_images` order)
+/// and their burst groups, keeping each group's stored source and confidence.
+fn stored_result(records: &[ImageRecord], groups: &[BurstGroupRecord], clock_offsets: &HashMap<String, i64>) -> BurstResult {
+    let mut frames: BTreeMap<&str, Vec<ExifData>> = BTreeMap::new();
+    let mut singles = Vec::new();
+    let group_ids: HashSet<&str> = groups.iter().map(|g| g.id.as_str()).collect();
+    for record in records {
+        let Some(exif) = record_to_exif(record, clock_offsets) else { continue };
+        match record.burst_group_id.as_deref() {
+            Some(id) if group_ids.contains(id) => frames.entry(id).or_default().push(exif),
+            _ => singles.push(exif),
+        }
+    }
+
+    let mut bursts = Vec::new();
+    for group in groups {
+        let Some(images) = frames.remove(group.id.as_str()) else { continue };
+        let mut burst = BurstGroup::new(group.id.clone(), group.camera_serial.clone(), images);
+        burst.source = group.source.parse().unwrap_or(BurstSource::default());
+        burst.confidence = group.confidence;
+        bursts.push(burst);
+    }
+    BurstResult::from_parts(bursts, singles)
+}
+
+/// Extract EXIF with the shared source, splitting per-file failures out as
+/// payloads so one bad file doesn't sink the batch.
+fn extract_exif(state: &AppState, paths: &[PathBuf]) -> Result<(Vec<ExifData>, Vec<ExifErrorPayload>), String> {
+    if paths.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let exif_results = {
+        let mut source_guard = state.exif_source.lock().map_err(|e| e.to_string())?;
+
+        // Lazily initialize the EXIF source. exiftool is optional: without it the
+        // native reader still works, just without maker notes it can't decode.
+        if source_guard.is_none() {
+            let reader = match ExiftoolPool::with_default_size() {
+                Ok(pool) => NativeExifReader::with_fallback(Box::new(pool)),
+                Err(e) => {
+                    eprintln!("exiftool unavailable, using native EXIF reader only: {}", e);
+                    NativeExifReader::new()
+                }
+            };
+            *source_guard = Some(Box::new(reader));
+        }
+
+        let source = source_guard.as_mut().unwrap();
+        source.extract(paths).map_err(|e| format!("EXIF extraction failed: {}", e))?
+    };
+
+    let mut exif_data: Vec<ExifData> = Vec::with_capacity(exif_results.len());
+    let mut exif_errors: Vec<ExifErrorPayload> = Vec::new();
+    for result in exif_results {
+        match result {
+            Ok(data) => exif_data.push(data),
+            Err(err) => exif_errors.push(exif_error_to_payload(&err)),
+        }
+    }
+    Ok((exif_data, exif_errors))
+}
+
+/// Every image in a detection result, bursts and singles alike.
+fn all_images(result: &BurstResult) -> Vec<ExifData> {
+    result.bursts.iter()
+        .flat_map(|b| b.images.iter().cloned())
+        .chain(result.singles.iter().cloned())
+        .collect()
+}
+
 // -- Commands --
 
 /// Import a folder: scan for images, extract EXIF, detect bursts.
@@ -212,6 +506,7 @@ fn payload_to_record(img: &ImagePayload, burst_id: Option<&str>, burst_index: Op
 #[command]
 async fn import_folder(
     request: ImportRequest,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
 ) -> Result<ImportResult, String> {
     let folder_path = PathBuf::from(&request.folder_path);
@@ -221,6 +516,7 @@ async fn import_folder(
             success: false,
             result: None,
             error: Some(format!("Not a directory: {}", folder_path.display())),
+            exif_errors: Vec::new(),
         });
     }
 
@@ -232,62 +528,61 @@ async fn import_folder(
             success: false,
             result: None,
             error: Some("No supported image files found in folder".to_string()),
+            exif_errors: Vec::new(),
         });
     }
 
-    // 2. Extract EXIF data via exiftool
-    let exif_data = {
-        let mut exiftool_guard = state.exiftool.lock().map_err(|e| e.to_string())?;
+    // 2. Extract EXIF data — per-file, so one bad file doesn't sink the import
+    let (exif_data, exif_errors) = extract_exif(&state, &image_paths)?;
 
-        // Lazily initialize exiftool runner
-        if exiftool_guard.is_none() {
-            *exiftool_guard = Some(
-                ExiftoolRunner::new().map_err(|e| format!("Failed to start exiftool: {}", e))?
-            );
-        }
+    if exif_data.is_empty() {
+        return Ok(ImportResult {
+            success: false,
+            result: None,
+            error: Some(format!("No readable images: all {} files failed EXIF extraction", exif_errors.len())),
+            exif_errors,
+        });
+    }
 
-        let runner = exiftool_guard.as_mut().unwrap();
-        runner.extract(&image_paths).map_err(|e| format!("EXIF extraction failed: {}", e))?
-    };
+    // RAW+JPEG: fold each JPEG into its RAW so the frame is culled once
+    let mut exif_data = pair_raw_jpeg(exif_data);
+
+    // Re-importing a known folder merges into its session instead of replacing it
+    let reimport = SessionDb::exists(&request.folder_path);
+    let db = SessionDb::open(&request.folder_path)
+        .map_err(|e| format!("Failed to open session DB: {:#}", e))?;
 
-    // 3. Detect bursts
-    let burst_result = BurstDetector::detect(exif_data)
+    // 3. Correct camera clocks, then detect bursts on the corrected times
+    let clock_offsets = db.load_clock_offsets().map_err(|e| e.to_string())?;
+    apply_clock_offsets(&mut exif_data, &clock_offsets);
+
+    // Bodies without readable drive mode are grouped by thumbnail similarity
+    let config = BurstDetectorConfig::default();
+    let (hashes, new_hashes) = perceptual_hashes(
+        shared_thumbnail_cache(&state)?.as_ref(),
+        db.load_perceptual_hashes().map_err(|e| e.to_string())?,
+        &BurstDetector::similarity_candidates(&exif_data, &config),
+    );
+
+    let mut burst_result = BurstDetector::detect_with_hashes(exif_data, &config, &hashes)
         .map_err(|e| format!("Burst detection failed: {}", e))?;
+    burst_result.restore_manual_bursts(&manual_bursts(&db)?);
 
     let payload = result_to_payload(&burst_result);
 
     // 4. Persist to SQLite
     {
-        let db = SessionDb::open(&request.folder_path)
-            .map_err(|e| format!("Failed to open session DB: {}", e))?;
-
+        db.save_perceptual_hashes(&new_hashes).map_err(|e| e.to_string())?;
         db.set_meta("root_folder", &request.folder_path)
             .map_err(|e| e.to_string())?;
 
-        // Convert to image records
-        let mut records: Vec<ImageRecord> = Vec::new();
-        for burst in &payload.bursts {
-            for (i, img) in burst.images.iter().enumerate() {
-                records.push(payload_to_record(img, Some(&burst.id), Some(i as i32)));
-            }
-        }
-        for img in &payload.singles {
-            records.push(payload_to_record(img, None, None));
+        let records = payload_image_records(&payload);
+        if reimport {
+            db.merge_images(&records).map_err(|e| e.to_string())?;
+        } else {
+            db.upsert_images(&records).map_err(|e| e.to_string())?;
         }
-        db.upsert_images(&records).map_err(|e| e.to_string())?;
-
-        // Persist burst groups
-        let burst_records: Vec<BurstGroupRecord> = payload.bursts.iter().map(|b| {
-            BurstGroupRecord {
-                id: b.id.clone(),
-                camera_serial: b.camera_serial.clone(),
-                frame_count: b.frame_count as i32,
-                duration_ms: b.duration_ms,
-                avg_gap_ms: b.avg_gap_ms,
-                estimated_fps: b.estimated_fps,
-            }
-        }).collect();
-        db.upsert_burst_groups(&burst_records).map_err(|e| e.to_string())?;
+        db.replace_burst_groups(&payload_burst_records(&payload)).map_err(|e| e.to_string())?;
 
         // Store the DB handle
         if let Ok(mut db_guard) = state.session_db.lock() {
@@ -300,13 +595,263 @@ async fn import_folder(
         *cache = Some(burst_result);
     }
 
+    // 5. Score quality in the background; failing to start doesn't fail the import
+    if let Err(e) = start_scoring(&app, &state) {
+        eprintln!("Quality scoring not started: {}", e);
+    }
+
     Ok(ImportResult {
         success: true,
         result: Some(payload),
         error: None,
+        exif_errors,
     })
 }
 
+/// Rescan a folder that already has a session. Only new or modified files
+/// (by size and mtime) are read; bursts are re-detected only for bodies
+/// with added, changed or removed files. Returns the session and the diff.
+#[command]
+async fn rescan_folder(
+    folder_path: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<RescanResult, String> {
+    let failed = |error: String| RescanResult {
+        success: false,
+        result: None,
+        error: Some(error),
+        exif_errors: Vec::new(),
+        diff: None,
+    };
+    let folder = PathBuf::from(&folder_path);
+    if !folder.is_dir() {
+        return Ok(failed(format!("Not a directory: {}", folder.display())));
+    }
+    if !SessionDb::exists(&folder_path) {
+        return Ok(failed("No session found for this folder — import it first".to_string()));
+    }
+    let db = SessionDb::open(&folder_path)
+        .map_err(|e| format!("Failed to open session DB: {:#}", e))?;
+
+    // 1. Compare what's on disk against the stored records
+    let files: Vec<FileState> = scan_folder(&folder).map_err(|e| e.to_string())?
+        .iter()
+        .map(|path| {
+            let (file_size, file_mtime, _) = file_cache_state(path);
+            FileState { file_path: path.display().to_string(), file_size, file_mtime }
+        })
+        .collect();
+    let diff = db.diff_folder(&files).map_err(|e| e.to_string())?;
+    let stored: Vec<ImageRecord> = db.load_images().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| !r.missing)
+        .collect();
+    let records: HashMap<&str, &ImageRecord> = stored.iter().map(|r| (r.file_path.as_str(), r)).collect();
+
+    // 2. Read only new and modified files. A modified RAW is re-read with its
+    // JPEG so the pair is rebuilt.
+    let on_disk: HashSet<&str> = files.iter().map(|f| f.file_path.as_str()).collect();
+    let mut to_read: Vec<PathBuf> = diff.added.iter().chain(&diff.changed).map(PathBuf::from).collect();
+    to_read.extend(diff.changed.iter()
+        .filter_map(|path| records.get(path.as_str())?.sidecar_path.as_deref())
+        .filter(|sidecar| on_disk.contains(sidecar))
+        .map(PathBuf::from));
+    let (fresh, exif_errors) = extract_exif(&state, &to_read)?;
+    let mut fresh = pair_raw_jpeg(fresh);
+    let clock_offsets = db.load_clock_offsets().map_err(|e| e.to_string())?;
+    apply_clock_offsets(&mut fresh, &clock_offsets);
+
+    // 3. Re-detect only the bodies something happened to
+    let affected: BTreeSet<String> = fresh.iter()
+        .map(|img| img.serial_number.clone())
+        .chain(diff.changed.iter().chain(&diff.removed).filter_map(|path| Some(records.get(path.as_str())?.serial_number.clone())))
+        .collect();
+    let unchanged: HashSet<&str> = diff.unchanged.iter().map(String::as_str).collect();
+    let (kept, redetect): (Vec<ImageRecord>, Vec<ImageRecord>) = stored.iter()
+        .filter(|r| unchanged.contains(r.file_path.as_str()))
+        .cloned()
+        .partition(|r| !affected.contains(&r.serial_number));
+    let previous = stored_result(&kept, &db.load_burst_groups().map_err(|e| e.to_string())?, &clock_offsets);
+    let mut images: Vec<ExifData> = redetect.iter().filter_map(|r| record_to_exif(r, &clock_offsets)).collect();
+    images.extend(fresh);
+
+    let config = BurstDetectorConfig::default();
+    let (hashes, new_hashes) = perceptual_hashes(
+        shared_thumbnail_cache(&state)?.as_ref(),
+        db.load_perceptual_hashes().map_err(|e| e.to_string())?,
+        &BurstDetector::similarity_candidates(&images, &config),
+    );
+    let mut burst_result = BurstDetector::redetect_cameras(previous, images, &affected, &config, &hashes)
+        .map_err(|e| format!("Burst detection failed: {}", e))?;
+    burst_result.restore_manual_bursts(&manual_bursts(&db)?);
+    let payload = result_to_payload(&burst_result);
+
+    // 4. Persist: merging marks removed files missing and keeps annotations
+    db.save_perceptual_hashes(&new_hashes).map_err(|e| e.to_string())?;
+    db.merge_images(&payload_image_records(&payload)).map_err(|e| e.to_string())?;
+    db.replace_burst_groups(&payload_burst_records(&payload)).map_err(|e| e.to_string())?;
+
+    if let Ok(mut db_guard) = state.session_db.lock() {
+        *db_guard = Some(db);
+    }
+    if let Ok(mut cache) = state.last_result.lock() {
+        *cache = Some(burst_result);
+    }
+    if let Err(e) = start_scoring(&app, &state) {
+        eprintln!("Quality scoring not started: {}", e);
+    }
+
+    Ok(RescanResult {
+        success: true,
+        result: Some(payload),
+        error: None,
+        exif_errors,
+        diff: Some(RescanDiffPayload {
+            added: diff.added,
+            changed: diff.changed,
+            removed: diff.removed,
+        }),
+    })
+}
+
+/// Align one camera body's clock to another's by pairing two frames that
+/// captured the same moment. Stores the offset for `target_path`'s body,
+/// re-runs burst detection on corrected times, and returns the new result.
+#[command]
+async fn set_clock_offset_from_pair(
+    reference_path: String,
+    target_path: String,
+    state: State<'_, AppState>,
+) -> Result<BurstResultPayload, String> {
+    let mut images = {
+        let cache = state.last_result.lock().map_err(|e| e.to_string())?;
+        let result = cache.as_ref().ok_or("No imported session to correct")?;
+        all_images(result)
+    };
+
+    let find = |path: &str| {
+        images.iter()
+            .find(|img| img.file_path.display().to_string() == path)
+            .ok_or_else(|| format!("Image not in current session: {}", path))
+    };
+    let offset_ms = clock_offset_from_pair(find(&reference_path)?, find(&target_path)?)
+        .map_err(|e| e.to_string())?;
+    let target_serial = find(&target_path)?.serial_number.clone();
+
+    let (clock_offsets, stored_hashes, manual) = {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session database open")?;
+        db.set_clock_offset(&target_serial, offset_ms).map_err(|e| e.to_string())?;
+        (
+            db.load_clock_offsets().map_err(|e| e.to_string())?,
+            db.load_perceptual_hashes().map_err(|e| e.to_string())?,
+            manual_bursts(db)?,
+        )
+    };
+    apply_clock_offsets(&mut images, &clock_offsets);
+
+    // Hashing may build thumbnails, so it runs without holding the database
+    let config = BurstDetectorConfig::default();
+    let (hashes, new_hashes) = perceptual_hashes(
+        shared_thumbnail_cache(&state)?.as_ref(),
+        stored_hashes,
+        &BurstDetector::similarity_candidates(&images, &config),
+    );
+
+    let mut burst_result = BurstDetector::detect_with_hashes(images, &config, &hashes)
+        .map_err(|e| format!("Burst detection failed: {}", e))?;
+    burst_result.restore_manual_bursts(&manual);
+    let payload = result_to_payload(&burst_result);
+
+    {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session database open")?;
+        db.save_perceptual_hashes(&new_hashes).map_err(|e| e.to_string())?;
+        // Re-persist with corrected times; merging keeps the user's annotations
+        db.merge_images(&payload_image_records(&payload)).map_err(|e| e.to_string())?;
+        db.replace_burst_groups(&payload_burst_records(&payload)).map_err(|e| e.to_string())?;
+    }
+
+    if let Ok(mut cache) = state.last_result.lock() {
+        *cache = Some(burst_result);
+    }
+
+    Ok(payload)
+}
+
+/// Split a burst before frame `at_index`; both halves become Manual bursts.
+#[command]
+async fn split_burst(
+    burst_id: String,
+    at_index: usize,
+    state: State<'_, AppState>,
+) -> Result<BurstResultPayload, String> {
+    edit_bursts(&state, |result| result.split_burst(&burst_id, at_index))
+}
+
+/// Merge two bursts from the same camera into one Manual burst.
+#[command]
+async fn merge_bursts(
+    first_id: String,
+    second_id: String,
+    state: State<'_, AppState>,
+) -> Result<BurstResultPayload, String> {
+    edit_bursts(&state, |result| result.merge_bursts(&first_id, &second_id))
+}
+
+/// Move a frame out of its burst into singles.
+#[command]
+async fn detach_from_burst(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<BurstResultPayload, String> {
+    edit_bursts(&state, |result| result.detach_frame(Path::new(&file_path)))
+}
+
+/// Move a single into a burst from the same camera.
+#[command]
+async fn attach_to_burst(
+    file_path: String,
+    burst_id: String,
+    state: State<'_, AppState>,
+) -> Result<BurstResultPayload, String> {
+    edit_bursts(&state, |result| result.attach_frame(Path::new(&file_path), &burst_id))
+}
+
+/// Apply a burst edit to the cached result and write it through to the
+/// session DB. The cached result only changes once the write succeeds.
+fn edit_bursts(
+    state: &AppState,
+    edit: impl FnOnce(&mut BurstResult) -> anyhow::Result<BurstEdit>,
+) -> Result<BurstResultPayload, String> {
+    let mut result_guard = state.last_result.lock().map_err(|e| e.to_string())?;
+    let result = result_guard.as_mut().ok_or("No import result — import a folder first")?;
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+
+    let mut edited = result.clone();
+    let change = edit(&mut edited).map_err(|e| e.to_string())?;
+
+    let mut memberships: Vec<BurstMembership> = change.changed.iter()
+        .flat_map(|burst| burst.images.iter().enumerate().map(|(i, img)| BurstMembership {
+            file_path: img.file_path.display().to_string(),
+            burst_group_id: Some(burst.id.clone()),
+            burst_index: Some(i as i32),
+        }))
+        .collect();
+    memberships.extend(change.new_singles.iter().map(|path| BurstMembership {
+        file_path: path.display().to_string(),
+        burst_group_id: None,
+        burst_index: None,
+    }));
+    let groups: Vec<BurstGroupRecord> = change.changed.iter().map(burst_group_record).collect();
+    db.apply_burst_edit(&change.removed, &groups, &memberships).map_err(|e| e.to_string())?;
+
+    *result = edited;
+    Ok(result_to_payload(result))
+}
+
 /// Get the cached analysis result (avoids re-running detection)
 #[command]
 async fn get_analysis(state: State<'_, AppState>) -> Result<Option<BurstResultPayload>, String> {
@@ -522,45 +1067,55 @@ async fn load_session(
             success: false,
             result: None,
             error: Some("No session found for this folder".to_string()),
+            exif_errors: Vec::new(),
         });
     }
 
     let db = SessionDb::open(&folder_path)
-        .map_err(|e| format!("Failed to open session DB: {}", e))?;
+        .map_err(|e| format!("Failed to open session DB: {:#}", e))?;
+    open_session(&state, db)
+}
 
-    let images = db.load_images().map_err(|e| e.to_string())?;
+/// Make `db` the current session. Besides building the payload, this rebuilds
+/// the cached detection result from the stored records, so editing, clock
+/// correction, ranking and scoring act on this session rather than whatever
+/// was imported last.
+fn open_session(state: &AppState, db: SessionDb) -> Result<ImportResult, String> {
+    // Files gone since the last import stay in the DB for their annotations only
+    let images: Vec<ImageRecord> = db.load_images().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|img| !img.missing)
+        .collect();
     let burst_groups = db.load_burst_groups().map_err(|e| e.to_string())?;
+    let clock_offsets = db.load_clock_offsets().map_err(|e| e.to_string())?;
 
     if images.is_empty() {
         return Ok(ImportResult {
             success: false,
             result: None,
             error: Some("Session database is empty".to_string()),
+            exif_errors: Vec::new(),
         });
     }
 
-    // Reconstruct the payload from DB records
-    let mut cameras_map: HashMap<String, CameraPayload> = HashMap::new();
+    // Per-body summaries come from the same rebuild the editing commands use
+    let stored = stored_result(&images, &burst_groups, &clock_offsets);
+
+    // Reconstruct the payload from DB records. load_images returns timeline
+    // order, so bursts and singles are collected in the order they first appear.
     let mut burst_images: HashMap<String, Vec<ImagePayload>> = HashMap::new();
     let mut singles: Vec<ImagePayload> = Vec::new();
+    let mut timeline: Vec<TimelineEntryPayload> = Vec::new();
 
     for img in &images {
-        // Track cameras
-        let cam = cameras_map.entry(img.serial_number.clone()).or_insert_with(|| CameraPayload {
-            serial: img.serial_number.clone(),
-            make: img.make.clone().unwrap_or_default(),
-            model: img.model.clone().unwrap_or_default(),
-            image_count: 0,
-            burst_count: 0,
-        });
-        cam.image_count += 1;
-
         let payload = ImagePayload {
             file_path: img.file_path.clone(),
             filename: img.filename.clone(),
+            sidecar_path: img.sidecar_path.clone(),
             serial_number: img.serial_number.clone(),
             drive_mode: img.drive_mode.clone(),
             capture_time: img.capture_time.clone(),
+            utc_offset_secs: img.utc_offset_secs,
             make: img.make.clone(),
             model: img.model.clone(),
             lens: img.lens.clone(),
@@ -568,46 +1123,69 @@ async fn load_session(
             aperture: img.aperture,
             shutter_speed: img.shutter_speed.clone(),
             iso: img.iso,
-            burst_group_id: None,
+            burst_group_id: img.native_burst_id.map(|id| id as u64),
             high_frame_rate: None,
         };
 
-        if let Some(ref burst_id) = img.burst_group_id {
-            burst_images.entry(burst_id.clone()).or_default().push(payload);
-        } else {
-            singles.push(payload);
+        let (kind, id) = match img.burst_group_id {
+            Some(ref burst_id) => ("burst", burst_id.clone()),
+            None => ("single", img.file_path.clone()),
+        };
+        if kind == "single" || !burst_images.contains_key(&id) {
+            timeline.push(TimelineEntryPayload {
+                position: timeline.len(),
+                kind: kind.to_string(),
+                id: id.clone(),
+                camera_serial: img.serial_number.clone(),
+                start_time: img.capture_time.clone(),
+            });
         }
-    }
 
-    // Count bursts per camera
-    for bg in &burst_groups {
-        if let Some(cam) = cameras_map.get_mut(&bg.camera_serial) {
-            cam.burst_count += 1;
+        if kind == "burst" {
+            burst_images.entry(id).or_default().push(payload);
+        } else {
+            singles.push(payload);
         }
     }
 
-    // Build burst payloads
-    let bursts: Vec<BurstPayload> = burst_groups.iter().map(|bg| {
-        BurstPayload {
+    // Build burst payloads in timeline order
+    let groups_by_id: HashMap<&str, &BurstGroupRecord> = burst_groups.iter()
+        .map(|bg| (bg.id.as_str(), bg))
+        .collect();
+    let bursts: Vec<BurstPayload> = timeline.iter()
+        .filter(|entry| entry.kind == "burst")
+        .filter_map(|entry| groups_by_id.get(entry.id.as_str()))
+        .map(|bg| BurstPayload {
             id: bg.id.clone(),
             camera_serial: bg.camera_serial.clone(),
             frame_count: bg.frame_count as usize,
             duration_ms: bg.duration_ms,
             avg_gap_ms: bg.avg_gap_ms,
             estimated_fps: bg.estimated_fps,
+            source: bg.source.clone(),
+            confidence: bg.confidence,
             images: burst_images.remove(&bg.id).unwrap_or_default(),
-        }
-    }).collect();
+        })
+        .collect();
 
     // Build result with persisted user annotations
     let total_images = images.len();
     let total_bursts = bursts.len();
     let total_singles = singles.len();
+    let cameras: Vec<CameraPayload> = stored.cameras.iter().map(camera_to_payload).collect();
 
     // Store DB handle for future write-through
     if let Ok(mut db_guard) = state.session_db.lock() {
         *db_guard = Some(db);
     }
+    if let Ok(mut cache) = state.last_result.lock() {
+        *cache = Some(stored);
+    }
+
+    // The previous session's scoring job would compete for CPU; dropping cancels it
+    if let Ok(mut job_guard) = state.scoring_job.lock() {
+        job_guard.take();
+    }
 
     // Build payload — we need to include the persisted flags/ratings.
     // The frontend will read these from a separate annotations structure.
@@ -615,28 +1193,31 @@ async fn load_session(
         total_images,
         total_bursts,
         total_singles,
-        cameras: cameras_map.into_values().collect(),
+        cameras,
         bursts,
         singles,
+        timeline,
     };
 
     Ok(ImportResult {
         success: true,
         result: Some(result),
         error: None,
+        exif_errors: Vec::new(),
     })
 }
 
 /// Persist a flag change to SQLite (write-through from UI).
+/// Anything but "none", "pick" or "reject" fails to deserialize.
 #[command]
 async fn persist_flag(
     file_path: String,
-    flag: String,
+    flag: Flag,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
     if let Some(ref db) = *db_guard {
-        db.update_flag(&file_path, &flag).map_err(|e| e.to_string())?;
+        db.update_flag(&file_path, flag).map_err(|e| e.to_string())?;
     }
     Ok(())
 }
@@ -648,6 +1229,7 @@ async fn persist_rating(
     rating: i32,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    validate_rating(rating).map_err(|e| e.to_string())?;
     let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
     if let Some(ref db) = *db_guard {
         db.update_rating(&file_path, rating).map_err(|e| e.to_string())?;
@@ -659,12 +1241,12 @@ async fn persist_rating(
 #[command]
 async fn persist_color_label(
     file_path: String,
-    color_label: String,
+    color_label: ColorLabel,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
     if let Some(ref db) = *db_guard {
-        db.update_color_label(&file_path, &color_label).map_err(|e| e.to_string())?;
+        db.update_color_label(&file_path, color_label).map_err(|e| e.to_string())?;
     }
     Ok(())
 }
@@ -672,12 +1254,12 @@ async fn persist_color_label(
 /// Persist batch flag changes (e.g., burst flagging).
 #[command]
 async fn persist_flags_batch(
-    updates: Vec<(String, String)>,
+    updates: Vec<(String, Flag)>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
     if let Some(ref db) = *db_guard {
-        let refs: Vec<(&str, &str)> = updates.iter().map(|(p, f)| (p.as_str(), f.as_str())).collect();
+        let refs: Vec<(&str, Flag)> = updates.iter().map(|(p, f)| (p.as_str(), *f)).collect();
         db.update_flags_batch(&refs).map_err(|e| e.to_string())?;
     }
     Ok(())
@@ -694,7 +1276,7 @@ async fn load_annotations(
         let images = db.load_images().map_err(|e| e.to_string())?;
         let mut annotations = HashMap::new();
         for img in images {
-            if img.flag != "none" || img.rating != 0 || img.color_label != "none" {
+            if img.flag != Flag::None || img.rating != 0 || img.color_label != ColorLabel::None {
                 annotations.insert(img.file_path, AnnotationPayload {
                     flag: img.flag,
                     rating: img.rating,
@@ -708,11 +1290,55 @@ async fn load_annotations(
     }
 }
 
+/// Undo the last annotation change (a batch counts as one). Returns the
+/// affected images' annotations as they are now, or `None` if there was
+/// nothing to undo.
+#[command]
+async fn undo_annotation(
+    state: State<'_, AppState>,
+) -> Result<Option<HashMap<String, AnnotationPayload>>, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+    Ok(db.undo().map_err(|e| e.to_string())?.map(annotation_payloads))
+}
+
+/// Re-apply the last undone annotation change; see `undo_annotation`.
+#[command]
+async fn redo_annotation(
+    state: State<'_, AppState>,
+) -> Result<Option<HashMap<String, AnnotationPayload>>, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+    Ok(db.redo().map_err(|e| e.to_string())?.map(annotation_payloads))
+}
+
+/// Whether undo/redo are available, for enabling the menu items.
+#[command]
+async fn get_annotation_history(
+    state: State<'_, AppState>,
+) -> Result<JournalStatus, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    match *db_guard {
+        Some(ref db) => db.journal_status().map_err(|e| e.to_string()),
+        None => Ok(JournalStatus::default()),
+    }
+}
+
+fn annotation_payloads(states: Vec<AnnotationState>) -> HashMap<String, AnnotationPayload> {
+    states.into_iter()
+        .map(|a| (a.file_path, AnnotationPayload {
+            flag: a.flag,
+            rating: a.rating,
+            color_label: a.color_label,
+        }))
+        .collect()
+}
+
 #[derive(Debug, Serialize)]
 struct AnnotationPayload {
-    flag: String,
+    flag: Flag,
     rating: i32,
-    color_label: String,
+    color_label: ColorLabel,
 }
 
 /// Export XMP sidecar files for all annotated images.
@@ -731,26 +1357,35 @@ async fn export_xmp_sidecars(
     let mut errors: Vec<String> = Vec::new();
 
     for img in &images {
-        // Skip images with no annotations
-        if img.rating == 0 && img.flag == "none" && img.color_label == "none" {
+        // Skip images with no annotations, or no file to write next to
+        if img.missing || (img.rating == 0 && img.flag == Flag::None && img.color_label == ColorLabel::None) {
             skipped += 1;
             continue;
         }
 
-        let source_path = std::path::Path::new(&img.file_path);
-        let xmp_path = source_path.with_extension("xmp");
+        // RAW+JPEG pairs share one set of annotations; write them next to both
+        // files. When both live in the same folder they share one .xmp.
+        let mut xmp_paths: Vec<PathBuf> = Vec::new();
+        for file in std::iter::once(&img.file_path).chain(img.sidecar_path.as_ref()) {
+            let source_path = std::path::Path::new(file);
+            let xmp_path = source_path.with_extension("xmp");
+            if xmp_paths.contains(&xmp_path) {
+                continue;
+            }
 
-        // Get file extension for SidecarForExtension (uppercase, no dot)
-        let ext = source_path.extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("RAW")
-            .to_uppercase();
+            // Get file extension for SidecarForExtension (uppercase, no dot)
+            let ext = source_path.extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("RAW")
+                .to_uppercase();
 
-        let xmp_content = build_xmp_sidecar(img.rating, &img.flag, &img.color_label, &ext);
+            let xmp_content = build_xmp_sidecar(img.rating, img.flag, img.color_label, &ext);
 
-        match std::fs::write(&xmp_path, &xmp_content) {
-            Ok(_) => written += 1,
-            Err(e) => errors.push(format!("{}: {}", img.filename, e)),
+            match std::fs::write(&xmp_path, &xmp_content) {
+                Ok(_) => written += 1,
+                Err(e) => errors.push(format!("{}: {}", xmp_path.display(), e)),
+            }
+            xmp_paths.push(xmp_path);
         }
     }
 
@@ -765,7 +1400,7 @@ struct XmpExportResult {
 }
 
 /// Build a Lightroom-compatible XMP sidecar string.
-fn build_xmp_sidecar(rating: i32, flag: &str, color_label: &str, extension: &str) -> String {
+fn build_xmp_sidecar(rating: i32, flag: Flag, color_label: ColorLabel, extension: &str) -> String {
     let mut attrs = Vec::new();
 
     // Rating (omit when 0)
@@ -775,12 +1410,12 @@ fn build_xmp_sidecar(rating: i32, flag: &str, color_label: &str, extension: &str
 
     // Color label
     let label_capitalized = match color_label {
-        "red" => Some("Red"),
-        "yellow" => Some("Yellow"),
-        "green" => Some("Green"),
-        "blue" => Some("Blue"),
-        "purple" => Some("Purple"),
-        _ => None,
+        ColorLabel::Red => Some("Red"),
+        ColorLabel::Yellow => Some("Yellow"),
+        ColorLabel::Green => Some("Green"),
+        ColorLabel::Blue => Some("Blue"),
+        ColorLabel::Purple => Some("Purple"),
+        ColorLabel::None => None,
     };
     if let Some(label) = label_capitalized {
         attrs.push(format!("   xmp:Label=\"{}\"", label));
@@ -796,15 +1431,15 @@ fn build_xmp_sidecar(rating: i32, flag: &str, color_label: &str, extension: &str
 
     // Pick/reject flag
     match flag {
-        "pick" => {
+        Flag::Pick => {
             attrs.push("   xmpDM:good=\"true\"".to_string());
             attrs.push("   xmpDM:pick=\"1\"".to_string());
         }
-        "reject" => {
+        Flag::Reject => {
             attrs.push("   xmpDM:good=\"false\"".to_string());
             attrs.push("   xmpDM:pick=\"-1\"".to_string());
         }
-        _ => {
+        Flag::None => {
             attrs.push("   xmpDM:pick=\"0\"".to_string());
         }
     }
@@ -833,6 +1468,153 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+// -- Quality scoring --
+
+/// Effective quality weights for the session: saved weights over the defaults.
+#[command]
+async fn get_quality_weights(
+    state: State<'_, AppState>,
+) -> Result<BTreeMap<String, f64>, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+
+    let mut analyzer = QualityAnalyzer::default();
+    analyzer.set_weights(session_weights(db)?);
+    Ok(analyzer.weights().weights)
+}
+
+/// Save the session's quality weight profile (metric name -> relative weight).
+#[command]
+async fn set_quality_weights(
+    weights: BTreeMap<String, f64>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let profile = WeightProfile::new(weights).map_err(|e| e.to_string())?;
+
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+    db.save_quality_weights(&profile.weights.into_iter().collect()).map_err(|e| e.to_string())
+}
+
+/// Score every frame of every burst, store the rankings, and return the
+/// suggested best pick per burst for the UI to highlight.
+#[command]
+async fn suggest_best_picks(
+    state: State<'_, AppState>,
+) -> Result<Vec<BurstRankingRecord>, String> {
+    let bursts = {
+        let result_guard = state.last_result.lock().map_err(|e| e.to_string())?;
+        result_guard.as_ref().ok_or("No import result — import a folder first")?.bursts.clone()
+    };
+    let (weights, stored) = {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+        (session_weights(db)?, db.load_quality_scores().map_err(|e| e.to_string())?)
+    };
+
+    let mut analyzer = QualityAnalyzer::with_previews(shared_thumbnail_cache(&state)?);
+    analyzer.set_weights(weights);
+    let records = rank_bursts(analyzer, &bursts, stored);
+
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+    db.save_burst_rankings(&records).map_err(|e| e.to_string())?;
+    Ok(records)
+}
+
+/// Score every image of the current import in the background (restarting
+/// any running job). Already-scored, unchanged files are skipped.
+#[command]
+async fn start_quality_scoring(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ScoringProgress, String> {
+    start_scoring(&app, &state)
+}
+
+/// Stop the background scoring job. Scores stored so far are kept.
+#[command]
+async fn cancel_quality_scoring(
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let job_guard = state.scoring_job.lock().map_err(|e| e.to_string())?;
+    if let Some(ref job) = *job_guard {
+        job.cancel();
+    }
+    Ok(())
+}
+
+/// Progress of the background scoring job, if one was started.
+#[command]
+async fn get_scoring_progress(
+    state: State<'_, AppState>,
+) -> Result<Option<ScoringProgress>, String> {
+    let job_guard = state.scoring_job.lock().map_err(|e| e.to_string())?;
+    Ok(job_guard.as_ref().map(|job| job.progress()))
+}
+
+/// Stored quality scores for files that haven't changed since scoring.
+#[command]
+async fn get_quality_scores(
+    state: State<'_, AppState>,
+) -> Result<Vec<QualityScoreRecord>, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    match *db_guard {
+        Some(ref db) => db.load_quality_scores().map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Group near-identical singles (same pose, single-shot fired rapidly) of
+/// the current import. Perceptual hashes come from the Micro tier and are
+/// stored, so only new or edited files are hashed.
+#[command]
+async fn find_similar_groups(
+    state: State<'_, AppState>,
+) -> Result<Vec<SimilarityGroupPayload>, String> {
+    let singles = {
+        let result_guard = state.last_result.lock().map_err(|e| e.to_string())?;
+        result_guard.as_ref().ok_or("No import result — import a folder first")?.singles.clone()
+    };
+    let stored = {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+        db.load_perceptual_hashes().map_err(|e| e.to_string())?
+    };
+
+    let (hashes, new_hashes) = perceptual_hashes(
+        shared_thumbnail_cache(&state)?.as_ref(),
+        stored,
+        &singles.iter().collect::<Vec<_>>(),
+    );
+
+    if !new_hashes.is_empty() {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+        db.save_perceptual_hashes(&new_hashes).map_err(|e| e.to_string())?;
+    }
+
+    let (groups, _) = group_similar(singles, &hashes, &SimilarityConfig::default());
+    Ok(groups.into_iter().map(|group| SimilarityGroupPayload {
+        id: group.id,
+        camera_serial: group.camera_serial,
+        frame_count: group.images.len(),
+        images: group.images.iter().map(exif_to_payload).collect(),
+    }).collect())
+}
+
+/// Stored best-pick suggestions (from the last `suggest_best_picks`).
+#[command]
+async fn get_best_picks(
+    state: State<'_, AppState>,
+) -> Result<Vec<BurstRankingRecord>, String> {
+    let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+    match *db_guard {
+        Some(ref db) => db.load_burst_rankings().map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
 // -- Thumbnail Cache V2 Commands --
 
 /// Get a thumbnail from the v2 cache system
@@ -850,7 +1632,7 @@ async fn get_thumbnail_v2(
         if cache_guard.is_none() {
             let session_hash = "main"; // TODO: Use actual session hash
             let cache = ThumbnailCache::new(session_hash).map_err(|e| e.to_string())?;
-            *cache_guard = Some(cache);
+            *cache_guard = Some(Arc::new(cache));
         }
     }
     
@@ -881,7 +1663,7 @@ async fn get_thumbnails_batch_v2(
         if cache_guard.is_none() {
             let session_hash = "main"; // TODO: Use actual session hash
             let cache = ThumbnailCache::new(session_hash).map_err(|e| e.to_string())?;
-            *cache_guard = Some(cache);
+            *cache_guard = Some(Arc::new(cache));
         }
     }
     
@@ -923,7 +1705,7 @@ async fn prefetch_thumbnails(
         if cache_guard.is_none() {
             let session_hash = "main"; // TODO: Use actual session hash
             let cache = ThumbnailCache::new(session_hash).map_err(|e| e.to_string())?;
-            *cache_guard = Some(cache);
+            *cache_guard = Some(Arc::new(cache));
         }
     }
     
@@ -976,7 +1758,7 @@ async fn get_color_swatches(
         if cache_guard.is_none() {
             let session_hash = "main"; // TODO: Use actual session hash
             let cache = ThumbnailCache::new(session_hash).map_err(|e| e.to_string())?;
-            *cache_guard = Some(cache);
+            *cache_guard = Some(Arc::new(cache));
         }
     }
     
@@ -994,8 +1776,183 @@ async fn get_color_swatches(
     Ok(hex_swatches)
 }
 
+/// Exposure histogram and clipping for one file, computed on its Preview tier
+#[command]
+async fn get_exposure_analysis(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<ExposureMetrics, String> {
+    let preview = shared_thumbnail_cache(&state)?
+        .get_or_generate(&file_path, ThumbnailTier::Preview)
+        .map_err(|e| e.to_string())?;
+
+    quality_algorithms::exposure_metrics(&preview).map_err(|e| e.to_string())
+}
+
 // -- Helpers --
 
+/// The v2 thumbnail cache, created on first use.
+fn shared_thumbnail_cache(state: &AppState) -> Result<Arc<ThumbnailCache>, String> {
+    let mut cache_guard = state.thumbnail_cache_v2.lock().map_err(|e| e.to_string())?;
+    if cache_guard.is_none() {
+        let session_hash = "main"; // TODO: Use actual session hash
+        let cache = ThumbnailCache::new(session_hash).map_err(|e| e.to_string())?;
+        *cache_guard = Some(Arc::new(cache));
+    }
+    Ok(cache_guard.as_ref().unwrap().clone())
+}
+
+/// The session's saved quality weights. Only overrides are saved, so apply
+/// them with `QualityAnalyzer::set_weights` to fall back to the defaults.
+fn session_weights(db: &SessionDb) -> Result<WeightProfile, String> {
+    let saved = db.load_quality_weights().map_err(|e| e.to_string())?;
+    WeightProfile::new(saved.into_iter().collect()).map_err(|e| e.to_string())
+}
+
+/// Perceptual hashes for `images`: stored ones whose file is unchanged, the
+/// rest hashed from the Micro tier. Returns all hashes plus the newly
+/// computed records for the caller to store.
+fn perceptual_hashes(
+    cache: &ThumbnailCache,
+    stored: Vec<PerceptualHashRecord>,
+    images: &[&ExifData],
+) -> (HashMap<PathBuf, PerceptualHash>, Vec<PerceptualHashRecord>) {
+    let stored: HashMap<String, PerceptualHashRecord> = stored.into_iter()
+        .map(|record| (record.file_path.clone(), record))
+        .collect();
+
+    let mut hashes = HashMap::new();
+    let mut missing: Vec<(PathBuf, String)> = Vec::new();
+    for image in images {
+        let path = image.file_path.display().to_string();
+        let (_, _, cache_hash) = file_cache_state(&image.file_path);
+        match stored.get(&path) {
+            Some(record) if record.cache_hash == cache_hash => {
+                hashes.insert(image.file_path.clone(), PerceptualHash { dhash: record.dhash, phash: record.phash });
+            }
+            _ => missing.push((image.file_path.clone(), cache_hash)),
+        }
+    }
+
+    let paths: Vec<PathBuf> = missing.iter().map(|(path, _)| path.clone()).collect();
+    let mut new_records = Vec::new();
+    for ((path, hash), (_, cache_hash)) in hash_images(cache, &paths).into_iter().zip(missing) {
+        match hash {
+            Ok(hash) => {
+                new_records.push(PerceptualHashRecord {
+                    file_path: path.display().to_string(),
+                    cache_hash,
+                    dhash: hash.dhash,
+                    phash: hash.phash,
+                });
+                hashes.insert(path, hash);
+            }
+            Err(e) => eprintln!("Could not hash {}: {:#}", path.display(), e),
+        }
+    }
+    (hashes, new_records)
+}
+
+/// Current cache hash of every image in the session, keyed by file path.
+fn image_cache_hashes(db: &SessionDb) -> Result<HashMap<String, String>, String> {
+    Ok(db.load_images().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|img| (img.file_path, img.cache_hash))
+        .collect())
+}
+
+/// Start (or restart) background scoring of the last import result.
+/// Batches are written through a second DB connection so commands aren't
+/// blocked; progress goes to the frontend as `scoring-progress` events.
+fn start_scoring(app: &tauri::AppHandle, state: &AppState) -> Result<ScoringProgress, String> {
+    let images = {
+        let result_guard = state.last_result.lock().map_err(|e| e.to_string())?;
+        all_images(result_guard.as_ref().ok_or("No import result — import a folder first")?)
+    };
+    let (weights, cache_hashes, already_scored, writer) = {
+        let db_guard = state.session_db.lock().map_err(|e| e.to_string())?;
+        let db = db_guard.as_ref().ok_or("No session loaded — import a folder first")?;
+        let cache_hashes = image_cache_hashes(db)?;
+        let already_scored: HashMap<PathBuf, String> = db.load_quality_scores().map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|score| (PathBuf::from(score.file_path), score.cache_hash))
+            .collect();
+        let writer = db.reopen().map_err(|e| format!("Failed to open session DB: {:#}", e))?;
+        (session_weights(db)?, cache_hashes, already_scored, writer)
+    };
+
+    let items: Vec<ScoringItem> = images.into_iter()
+        .filter_map(|image| {
+            let cache_hash = cache_hashes.get(&image.file_path.display().to_string())?.clone();
+            Some(ScoringItem { image, cache_hash })
+        })
+        .collect();
+
+    let mut analyzer = QualityAnalyzer::with_previews(shared_thumbnail_cache(state)?);
+    analyzer.set_weights(weights);
+
+    let mut job_guard = state.scoring_job.lock().map_err(|e| e.to_string())?;
+    if let Some(previous) = job_guard.take() {
+        previous.cancel();
+    }
+
+    let app = app.clone();
+    let job = ScoringJob::start(
+        analyzer,
+        items,
+        &already_scored,
+        move |batch| writer.save_quality_scores(&batch.iter().map(score_record).collect::<Vec<_>>()),
+        move |progress| {
+            let _ = app.emit("scoring-progress", progress);
+        },
+    );
+    let progress = job.progress();
+    *job_guard = Some(job);
+    Ok(progress)
+}
+
+/// Persisted form of a background-job score.
+fn score_record(scored: &ScoredImage) -> QualityScoreRecord {
+    let score = &scored.score;
+    QualityScoreRecord {
+        file_path: scored.file_path.display().to_string(),
+        cache_hash: scored.cache_hash.clone(),
+        overall_score: score.overall_score,
+        sharpness: score.sharpness,
+        exposure: score.exposure,
+        composition: score.composition,
+        technical_quality: score.technical_quality,
+        metrics: score.metrics.iter().map(|(name, value)| (name.clone(), *value)).collect(),
+    }
+}
+
+/// Persisted form of a burst ranking; `None` for an empty burst.
+/// Rank `bursts` with `analyzer`. Frames in `stored` (scored by the background
+/// job) are re-weighted with the analyzer's effective weights, not re-analyzed.
+fn rank_bursts(analyzer: QualityAnalyzer, bursts: &[BurstGroup], stored: Vec<QualityScoreRecord>) -> Vec<BurstRankingRecord> {
+    let weights = analyzer.weights();
+    let known: HashMap<PathBuf, QualityScore> = stored.into_iter()
+        .map(|record| (PathBuf::from(record.file_path), QualityScore::from_metrics(record.metrics.into_iter().collect(), &weights)))
+        .collect();
+
+    let ranker = BurstRanker::new(analyzer);
+    bursts.iter()
+        .map(|burst| ranker.rank_with_scores(burst, &known))
+        .filter_map(|ranking| ranking_record(&ranking))
+        .collect()
+}
+
+fn ranking_record(ranking: &BurstRanking) -> Option<BurstRankingRecord> {
+    let best = ranking.best()?;
+    Some(BurstRankingRecord {
+        burst_id: ranking.burst_id.clone(),
+        suggested_index: best.burst_index as i32,
+        suggested_path: best.file_path.display().to_string(),
+        ranking: ranking.frames.iter().map(|f| f.burst_index as i32).collect(),
+        scores: ranking.frames.iter().map(|f| f.score.as_ref().map(|s| s.overall_score)).collect(),
+    })
+}
+
 /// Recursively scan a folder for supported image files
 fn scan_folder(folder: &PathBuf) -> anyhow::Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
@@ -1032,34 +1989,173 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(AppState {
-            exiftool: Mutex::new(None),
+            exif_source: Mutex::new(None),
             last_result: Mutex::new(None),
             cache_dir,
             thumbnail_cache: Mutex::new(HashMap::new()),
             session_db: Mutex::new(None),
             thumbnail_cache_v2: Mutex::new(None),
+            scoring_job: Mutex::new(None),
         })
         .invoke_handler(tauri::generate_handler![
             greet,
             import_folder,
+            rescan_folder,
             get_analysis,
             extract_thumbnails,
             get_thumbnail,
             extract_loupe_image,
             extract_burst_loupe_images,
             load_session,
+            set_clock_offset_from_pair,
+            split_burst,
+            merge_bursts,
+            detach_from_burst,
+            attach_to_burst,
             persist_flag,
             persist_rating,
             persist_color_label,
             persist_flags_batch,
             load_annotations,
+            undo_annotation,
+            redo_annotation,
+            get_annotation_history,
             export_xmp_sidecars,
             get_thumbnail_v2,
             get_thumbnails_batch_v2,
             prefetch_thumbnails,
             get_import_progress,
             get_color_swatches,
+            get_exposure_analysis,
+            get_quality_weights,
+            set_quality_weights,
+            suggest_best_picks,
+            get_best_picks,
+            start_quality_scoring,
+            cancel_quality_scoring,
+            get_scoring_progress,
+            get_quality_scores,
+            find_similar_groups,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn test_state(cache_dir: &Path) -> AppState {
+        AppState {
+            exif_source: Mutex::new(None),
+            last_result: Mutex::new(None),
+            cache_dir: cache_dir.to_path_buf(),
+            thumbnail_cache: Mutex::new(HashMap::new()),
+            session_db: Mutex::new(None),
+            thumbnail_cache_v2: Mutex::new(None),
+            scoring_job: Mutex::new(None),
+        }
+    }
+
+    fn frame(name: &str, offset_ms: i64) -> ExifData {
+        ExifData::new(
+            PathBuf::from(format!("/card/{}", name)),
+            "3002851".to_string(),
+            DriveMode::ContinuousHigh,
+            Utc.timestamp_millis_opt(1_723_661_140_000 + offset_ms).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_edit_burst_after_reloading_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("meta.db");
+
+        // An earlier run imported one four-frame burst
+        let detected = BurstDetector::detect(
+            (0..4).map(|i| frame(&format!("DSC_{:04}.NEF", i), i * 100)).collect(),
+        ).unwrap();
+        let burst_id = detected.bursts[0].id.clone();
+        let payload = result_to_payload(&detected);
+        let db = SessionDb::open_at(&db_path).unwrap();
+        db.upsert_images(&payload_image_records(&payload)).unwrap();
+        db.replace_burst_groups(&payload_burst_records(&payload)).unwrap();
+        drop(db);
+
+        // A fresh app run has nothing cached until the session is opened
+        let state = test_state(dir.path());
+        assert!(open_session(&state, SessionDb::open_at(&db_path).unwrap()).unwrap().success);
+
+        let edited = edit_bursts(&state, |result| result.split_burst(&burst_id, 2)).unwrap();
+        assert_eq!(edited.bursts.len(), 2);
+        assert!(edited.bursts.iter().all(|b| b.source == "Manual"));
+
+        let db_guard = state.session_db.lock().unwrap();
+        let groups = db_guard.as_ref().unwrap().load_burst_groups().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().any(|g| g.id == burst_id && g.frame_count == 2));
+    }
+
+    #[test]
+    fn test_stored_and_fresh_scores_rank_on_the_same_weights() {
+        let dir = tempfile::tempdir().unwrap();
+        let checker = image::RgbImage::from_fn(320, 240, |x, y| {
+            if (x / 8 + y / 8) % 2 == 0 { image::Rgb([40, 40, 40]) } else { image::Rgb([210, 210, 210]) }
+        });
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 90).encode_image(&checker).unwrap();
+
+        // The same picture twice: one frame scored earlier by the background
+        // job, the other scored now
+        let mut stored_frame = frame("DSC_0001.JPG", 0);
+        stored_frame.file_path = dir.path().join("DSC_0001.JPG");
+        let mut fresh_frame = frame("DSC_0002.JPG", 100);
+        fresh_frame.file_path = dir.path().join("DSC_0002.JPG");
+        std::fs::write(&fresh_frame.file_path, &jpeg).unwrap();
+        let metrics = QualityAnalyzer::default().analyze_bytes(&jpeg).unwrap().metrics;
+        let stored = vec![QualityScoreRecord {
+            file_path: stored_frame.file_path.display().to_string(),
+            cache_hash: String::new(),
+            overall_score: 0.0,
+            sharpness: 0.0,
+            exposure: 0.0,
+            composition: 0.0,
+            technical_quality: 0.0,
+            metrics: metrics.into_iter().collect(),
+        }];
+        let burst = BurstGroup::new("b1".to_string(), "3002851".to_string(), vec![stored_frame, fresh_frame]);
+
+        let records = rank_bursts(QualityAnalyzer::default(), &[burst], stored);
+
+        let scores: Vec<f64> = records[0].scores.iter().map(|s| s.unwrap()).collect();
+        assert!(scores[0] > 0.0);
+        assert!((scores[0] - scores[1]).abs() < 1e-9, "scores {:?}", scores);
+    }
+
+    #[test]
+    fn test_reloaded_camera_source_ignores_manual_bursts() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("meta.db");
+
+        // Two detected bursts ten seconds apart; the first is split by hand
+        let detected = BurstDetector::detect(
+            (0..8).map(|i| frame(&format!("DSC_{:04}.NEF", i), i * 100 + (i / 4) * 10_000)).collect(),
+        ).unwrap();
+        let payload = result_to_payload(&detected);
+        let db = SessionDb::open_at(&db_path).unwrap();
+        db.upsert_images(&payload_image_records(&payload)).unwrap();
+        db.replace_burst_groups(&payload_burst_records(&payload)).unwrap();
+        let state = test_state(dir.path());
+        open_session(&state, db).unwrap();
+        let first_id = detected.bursts[0].id.clone();
+        edit_bursts(&state, |result| result.split_burst(&first_id, 2)).unwrap();
+        drop(state);
+
+        let reloaded = open_session(&test_state(dir.path()), SessionDb::open_at(&db_path).unwrap()).unwrap();
+        let camera = &reloaded.result.unwrap().cameras[0];
+        assert_eq!(camera.burst_count, 3);
+        assert_eq!(camera.burst_source, "DriveMode");
+        assert_eq!(camera.burst_confidence, detected.bursts[1].confidence);
+    }
+}
@@ -178,10 +178,17 @@ function hydrateFromPayload(data: NonNullable<ImportResult['result']>) {
     imageMap.set(entry.id, entry);
   }
 
-  // Sort by timestamp for display order
-  const imageOrder = Array.from(imageMap.values())
-    .sort((a, b) => a.timestamp - b.timestamp)
-    .map((img) => img.id);
+  // Display order: the backend's cross-camera timeline (bursts stay contiguous).
+  // Older JSON exports have no timeline — fall back to sorting by timestamp.
+  const imageOrder = data.timeline
+    ? data.timeline.flatMap((entry) =>
+        entry.kind === 'burst'
+          ? normalizedBursts.find((b) => b.id === entry.id)?.imageIds ?? []
+          : [entry.id],
+      )
+    : Array.from(imageMap.values())
+        .sort((a, b) => a.timestamp - b.timestamp)
+        .map((img) => img.id);
 
   const cameras: CameraGroup[] = data.cameras.map((c) => ({
     serial: c.serial,
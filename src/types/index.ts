@@ -14,11 +14,13 @@
 // -- Backend payload types (from Tauri commands) --
 
 export interface ImagePayload {
-  file_path: string;
+  file_path: string; // primary file (the RAW, for a RAW+JPEG pair)
   filename: string;
+  sidecar_path?: string | null; // JPEG half of a RAW+JPEG pair
   serial_number: string;
   drive_mode: string; // "Single" | "ContinuousLow" | "ContinuousHigh" | "Unknown"
-  capture_time: string; // ISO 8601
+  capture_time: string; // ISO 8601, UTC, with the body's clock offset applied
+  utc_offset_secs?: number; // OffsetTimeOriginal, seconds east of UTC
   make: string | null;
   model: string | null;
   lens: string | null;
@@ -35,15 +37,31 @@ export interface BurstPayload {
   duration_ms: number;
   avg_gap_ms: number;
   estimated_fps: number;
+  source: BurstSource;
+  confidence: number; // 0..1; 1 = camera-native burst ID
   images: ImagePayload[];
 }
 
+// Which detection strategy produced a burst; NativeId is ground truth, Manual is a user edit
+export type BurstSource = 'NativeId' | 'DriveMode' | 'Similarity' | 'Manual';
+
 export interface CameraPayload {
   serial: string;
   make: string;
   model: string;
   image_count: number;
   burst_count: number;
+  clock_offset_ms: number; // correction applied to this body's capture times
+  burst_source: BurstSource; // majority strategy of this body's detected (non-Manual) bursts
+  burst_confidence: number; // mean over those bursts
+}
+
+export interface TimelineEntryPayload {
+  position: number;
+  kind: 'burst' | 'single';
+  id: string; // burst ID, or file path for a single
+  camera_serial: string;
+  start_time: string; // ISO 8601, clock-corrected
 }
 
 export interface BurstResultPayload {
@@ -51,14 +69,112 @@ export interface BurstResultPayload {
   total_bursts: number;
   total_singles: number;
   cameras: CameraPayload[];
-  bursts: BurstPayload[];
-  singles: ImagePayload[];
+  bursts: BurstPayload[]; // timeline order
+  singles: ImagePayload[]; // timeline order
+  timeline?: TimelineEntryPayload[]; // absent in older JSON exports
+}
+
+export interface ExifErrorPayload {
+  file_path: string;
+  kind: 'MissingDateTime' | 'Unreadable' | 'Unsupported' | 'Crashed';
+  message: string;
 }
 
 export interface ImportResult {
   success: boolean;
   result?: BurstResultPayload;
   error?: string;
+  exif_errors?: ExifErrorPayload[];
+}
+
+// rescan_folder: paths by how they changed since the session was last written
+export interface RescanDiff {
+  added: string[];
+  changed: string[]; // size or mtime differ
+  removed: string[]; // kept in the session as missing, with their annotations
+}
+
+export interface RescanResult extends ImportResult {
+  diff?: RescanDiff;
+}
+
+// The only values the backend accepts; anything else is rejected by the command
+export type Flag = 'none' | 'pick' | 'reject';
+export type ColorLabel = 'none' | 'red' | 'yellow' | 'green' | 'blue' | 'purple';
+
+// load_annotations / undo_annotation / redo_annotation: file path -> annotations.
+// undo/redo return null when there is nothing to apply.
+export interface AnnotationPayload {
+  flag: Flag;
+  rating: number; // 0..5
+  color_label: ColorLabel;
+}
+
+// get_annotation_history
+export interface JournalStatus {
+  can_undo: boolean;
+  can_redo: boolean;
+}
+
+// get_exposure_analysis: 256-bin histograms of the Preview tier
+export interface ExposureHistogram {
+  luma: number[];
+  red: number[];
+  green: number[];
+  blue: number[];
+  pixel_count: number;
+  highlight_clipped_pixels: number;
+}
+
+export interface ExposureMetrics {
+  histogram: ExposureHistogram;
+  highlight_clipping_percent: number;
+  shadow_clipping_percent: number;
+  mean_ev_offset: number; // stops from middle grey, + = bright
+  median_ev_offset: number;
+}
+
+// get_quality_weights / set_quality_weights: metric name -> relative weight
+// (built-ins: sharpness, exposure, composition, technical_quality)
+export type QualityWeights = Record<string, number>;
+
+// suggest_best_picks / get_best_picks: one per burst
+export interface BestPickPayload {
+  burst_id: string;
+  suggested_index: number; // burst index of the suggested frame
+  suggested_path: string;
+  ranking: number[]; // burst indices, best first
+  scores: (number | null)[]; // overall score per ranking entry; null = couldn't score
+}
+
+// "scoring-progress" event, get_scoring_progress, start_quality_scoring
+export interface ScoringProgress {
+  total: number;
+  scored: number;
+  skipped: number; // already scored and unchanged
+  failed: number;
+  is_cancelled: boolean;
+  is_finished: boolean;
+}
+
+// get_quality_scores: stored per-image scores (current files only)
+export interface QualityScorePayload {
+  file_path: string;
+  cache_hash: string;
+  overall_score: number;
+  sharpness: number;
+  exposure: number;
+  composition: number;
+  technical_quality: number;
+  metrics: Record<string, number>;
+}
+
+// find_similar_groups: near-identical singles from one body, close in time
+export interface SimilarityGroupPayload {
+  id: string; // "similar_..."
+  camera_serial: string;
+  frame_count: number;
+  images: ImagePayload[];
 }
 
 // -- Frontend display types --
@@ -80,8 +196,8 @@ export interface ImageEntry {
     lens: string;
   };
   rating: number;
-  flag: 'none' | 'pick' | 'reject';
-  colorLabel: 'none' | 'red' | 'yellow' | 'green' | 'blue' | 'purple';
+  flag: Flag;
+  colorLabel: ColorLabel;
   burstGroupId: string | null;
   burstIndex: number | null;
   // Progressive thumbnails